@@ -1,6 +1,7 @@
 struct ShaderPushConstants {
+    model: mat4x4<f32>,
     flipv: u32,
-    gamma_correct: u32,
+    alpha_cutoff: f32,
 }
 
 struct VertexInput {
@@ -10,10 +11,21 @@ struct VertexInput {
     @location(3) texcoord: vec2<f32>,
 };
 
+// Per-instance data of instanced render objects
+struct InstanceInput {
+    @location(4) model_0: vec4<f32>,
+    @location(5) model_1: vec4<f32>,
+    @location(6) model_2: vec4<f32>,
+    @location(7) model_3: vec4<f32>,
+    @location(8) tint: vec4<f32>,
+    @location(9) custom_data: vec4<f32>,
+};
+
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) uv: vec2<f32>,
     @location(1) color: vec3<f32>,
+    @location(2) tint: vec4<f32>,
 };
 
 struct ShaderCameraUniform {
@@ -36,13 +48,35 @@ fn vs_main(
 ) -> VertexOutput {
     var out: VertexOutput;
 
-    out.clip_position = camera.viewproj * vec4<f32>(vertex.position, 1.0);
+    out.clip_position = camera.viewproj * pc.model * vec4<f32>(vertex.position, 1.0);
     if (pc.flipv == 1u) {
         out.clip_position.y *= -1.0;
     }
 
     out.uv = vertex.texcoord;
     out.color = vertex.color;
+    out.tint = vec4<f32>(1.0);
+
+    return out;
+}
+
+// Places every instance with its own model matrix within the render object
+@vertex
+fn vs_instanced(
+    vertex: VertexInput,
+    instance: InstanceInput,
+) -> VertexOutput {
+    let instance_model = mat4x4<f32>(instance.model_0, instance.model_1, instance.model_2, instance.model_3);
+    var out: VertexOutput;
+
+    out.clip_position = camera.viewproj * pc.model * instance_model * vec4<f32>(vertex.position, 1.0);
+    if (pc.flipv == 1u) {
+        out.clip_position.y *= -1.0;
+    }
+
+    out.uv = vertex.texcoord;
+    out.color = vertex.color;
+    out.tint = instance.tint;
 
     return out;
 }
@@ -54,19 +88,13 @@ var t_diffuse: texture_2d<f32>;
 @group(0) @binding(1)
 var s_diffuse: sampler;
 
-fn gamma_correct(color: vec4<f32>) -> vec4<f32> {
-    // Convert from linear to sRGB
-    let new_color = pow(color.rgb, vec3<f32>(1.0 / 2.2));
-    return vec4<f32>(new_color, color.a);
-}
-
+// Outputs linear colors, the post-processing output pass encodes them for the surface
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     var out = textureSample(t_diffuse, s_diffuse, in.uv);
-
-    if (pc.gamma_correct == 1u) {
-        out = gamma_correct(out);
+    out *= in.tint;
+    if (out.a < pc.alpha_cutoff) {
+        discard;
     }
-
     return out;
 }
\ No newline at end of file
@@ -0,0 +1,123 @@
+struct ShaderParticle {
+    position: vec3<f32>,
+    age: f32,
+    velocity: vec3<f32>,
+    lifetime: f32,
+}
+
+struct ShaderParticleEmitterUniform {
+    start_color: vec4<f32>,
+    end_color: vec4<f32>,
+    position: vec3<f32>,
+    shape: u32,
+    shape_size: vec3<f32>,
+    rate: f32,
+    velocity: vec3<f32>,
+    velocity_spread: f32,
+    force: vec3<f32>,
+    drag: f32,
+    camera_right: vec3<f32>,
+    start_size: f32,
+    camera_up: vec3<f32>,
+    end_size: f32,
+    min_lifetime: f32,
+    max_lifetime: f32,
+    ground_height: f32,
+    bounciness: f32,
+    max_particles: u32,
+}
+
+struct ShaderFrameUniform {
+    resolution: vec2<f32>,
+    mouse: vec2<f32>,
+    camera_position: vec3<f32>,
+    time: f32,
+    delta_time: f32,
+    frame: u32,
+    _padding: vec2<u32>,
+}
+
+//----------------------------------------------------------------------
+
+@group(0) @binding(0)
+var<storage, read_write> particles: array<ShaderParticle>;
+@group(0) @binding(1)
+var<uniform> emitter: ShaderParticleEmitterUniform;
+
+@group(1) @binding(0)
+var<uniform> frame: ShaderFrameUniform;
+
+// PCG hash, see "Hash Functions for GPU Rendering" by Jarzynski and Olano
+fn hash(value: u32) -> u32 {
+    let state = value * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+// Uniformly distributed in [0, 1)
+fn random(seed: ptr<function, u32>) -> f32 {
+    *seed = hash(*seed);
+    return f32(*seed >> 8u) / 16777216.0;
+}
+
+fn random_direction(seed: ptr<function, u32>) -> vec3<f32> {
+    let z = random(seed) * 2.0 - 1.0;
+    let angle = random(seed) * 6.2831853;
+    let radius = sqrt(1.0 - z * z);
+    return vec3<f32>(radius * cos(angle), radius * sin(angle), z);
+}
+
+fn spawn_offset(seed: ptr<function, u32>) -> vec3<f32> {
+    switch emitter.shape {
+        case 1u: {
+            // Uniform inside the sphere
+            return random_direction(seed) * emitter.shape_size.x * pow(random(seed), 1.0 / 3.0);
+        }
+        case 2u: {
+            let unit = vec3<f32>(random(seed), random(seed), random(seed)) * 2.0 - 1.0;
+            return unit * emitter.shape_size;
+        }
+        default: {
+            return vec3<f32>(0.0);
+        }
+    }
+}
+
+// The emitter spawns particle number n at n / rate seconds into slot n % max_particles,
+// replacing the oldest particle once all slots are taken
+@compute @workgroup_size(64)
+fn simulate(@builtin(global_invocation_id) id: vec3<u32>) {
+    let slot = id.x;
+    if (slot >= emitter.max_particles || slot >= arrayLength(&particles)) {
+        return;
+    }
+    var particle = particles[slot];
+    var step = frame.delta_time;
+
+    // The last particle emitted into this slot during the frame, if any
+    let emitted = u32(floor(frame.time * emitter.rate));
+    let emitted_before = u32(floor(max(frame.time - frame.delta_time, 0.0) * emitter.rate));
+    let behind = (emitted % emitter.max_particles + emitter.max_particles - slot) % emitter.max_particles;
+    if (emitter.rate > 0.0 && emitted >= behind && emitted - behind > emitted_before) {
+        let number = emitted - behind;
+        var seed = hash(number);
+        particle.position = emitter.position + spawn_offset(&seed);
+        particle.velocity = emitter.velocity + random_direction(&seed) * emitter.velocity_spread * random(&seed);
+        particle.lifetime = mix(emitter.min_lifetime, emitter.max_lifetime, random(&seed));
+        particle.age = 0.0;
+        // Simulate the time since it was spawned
+        step = max(frame.time - f32(number) / emitter.rate, 0.0);
+    }
+
+    if (particle.age < particle.lifetime) {
+        particle.velocity += emitter.force * step;
+        particle.velocity *= max(1.0 - emitter.drag * step, 0.0);
+        particle.position += particle.velocity * step;
+        if (emitter.bounciness >= 0.0 && particle.position.y < emitter.ground_height) {
+            particle.position.y = emitter.ground_height;
+            particle.velocity.y = abs(particle.velocity.y) * emitter.bounciness;
+        }
+        particle.age += step;
+    }
+    particles[slot] = particle;
+}
@@ -0,0 +1,103 @@
+struct ShaderPushConstants {
+    model: mat4x4<f32>,
+    flipv: u32,
+    alpha_cutoff: f32,
+}
+
+struct ShaderParticleEmitterUniform {
+    start_color: vec4<f32>,
+    end_color: vec4<f32>,
+    position: vec3<f32>,
+    shape: u32,
+    shape_size: vec3<f32>,
+    rate: f32,
+    velocity: vec3<f32>,
+    velocity_spread: f32,
+    force: vec3<f32>,
+    drag: f32,
+    camera_right: vec3<f32>,
+    start_size: f32,
+    camera_up: vec3<f32>,
+    end_size: f32,
+    min_lifetime: f32,
+    max_lifetime: f32,
+    ground_height: f32,
+    bounciness: f32,
+    max_particles: u32,
+}
+
+struct ShaderCameraUniform {
+    viewproj: mat4x4<f32>,
+    near: f32,
+    far: f32,
+    _padding: vec2<f32>,
+}
+
+// One instance per particle
+struct ParticleInput {
+    @location(0) position: vec3<f32>,
+    @location(1) age: f32,
+    @location(2) velocity: vec3<f32>,
+    @location(3) lifetime: f32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    // Position on the billboard, from -1 to 1
+    @location(0) corner: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+//----------------------------------------------------------------------
+
+var<push_constant> pc: ShaderPushConstants;
+
+@group(0) @binding(0)
+var<uniform> emitter: ShaderParticleEmitterUniform;
+
+@group(1) @binding(0)
+var<uniform> camera: ShaderCameraUniform;
+
+// Camera-facing quads of two triangles, without a vertex buffer
+@vertex
+fn vs_main(
+    @builtin(vertex_index) vertex_index: u32,
+    particle: ParticleInput,
+) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0),
+        vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, 1.0),
+    );
+    var out: VertexOutput;
+    out.corner = corners[vertex_index];
+
+    // Dead particles collapse into a point outside the clip volume
+    if (particle.age >= particle.lifetime) {
+        out.clip_position = vec4<f32>(0.0, 0.0, -2.0, 1.0);
+        return out;
+    }
+    let life = particle.age / particle.lifetime;
+    let size = mix(emitter.start_size, emitter.end_size, life);
+    let position = particle.position
+        + (emitter.camera_right * out.corner.x + emitter.camera_up * out.corner.y) * size * 0.5;
+
+    out.clip_position = camera.viewproj * vec4<f32>(position, 1.0);
+    if (pc.flipv == 1u) {
+        out.clip_position.y *= -1.0;
+    }
+    out.color = mix(emitter.start_color, emitter.end_color, life);
+    return out;
+}
+
+//----------------------------------------------------------------------
+
+// Round particles fading towards their edge
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let falloff = 1.0 - smoothstep(0.5, 1.0, length(in.corner));
+    return vec4<f32>(in.color.rgb, in.color.a * falloff);
+}
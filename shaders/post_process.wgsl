@@ -0,0 +1,260 @@
+struct ShaderPushConstants {
+    model: mat4x4<f32>,
+    flipv: u32,
+    alpha_cutoff: f32,
+}
+
+struct ShaderFrameUniform {
+    resolution: vec2<f32>,
+    mouse: vec2<f32>,
+    camera_position: vec3<f32>,
+    time: f32,
+    delta_time: f32,
+    frame: u32,
+    _padding: vec2<u32>,
+}
+
+struct ShaderPostEffectUniform {
+    parameters: array<vec4<f32>, 2>,
+}
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+};
+
+//----------------------------------------------------------------------
+
+var<push_constant> pc: ShaderPushConstants;
+
+@group(0) @binding(0)
+var t_input: texture_2d<f32>;
+@group(0) @binding(1)
+var s_input: sampler;
+// Additional texture of effects such as the color grading LUT
+@group(0) @binding(2)
+var t_aux: texture_2d<f32>;
+
+@group(1) @binding(0)
+var<uniform> effect: ShaderPostEffectUniform;
+
+@group(2) @binding(0)
+var<uniform> frame: ShaderFrameUniform;
+
+// The fullscreen quad covers the whole viewport
+@vertex
+fn vs_main(vertex: VertexInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(vertex.position.xy, 0.0, 1.0);
+    return out;
+}
+
+//----------------------------------------------------------------------
+
+fn get_uv(in: VertexOutput) -> vec2<f32> {
+    return in.clip_position.xy / vec2<f32>(textureDimensions(t_input));
+}
+
+fn sample_input(uv: vec2<f32>) -> vec4<f32> {
+    return textureSampleLevel(t_input, s_input, uv, 0.0);
+}
+
+fn luma(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.299, 0.587, 0.114));
+}
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+// Writes the result of the stack to the viewport
+// Parameters: output transform, 1 to encode to sRGB for surfaces that store encoded values
+@fragment
+fn fs_output(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = sample_input(get_uv(in));
+    let clamped = clamp(color.rgb, vec3<f32>(0.0), vec3<f32>(1.0));
+    if (effect.parameters[0].x == 1.0) {
+        return vec4<f32>(linear_to_srgb(clamped), color.a);
+    }
+    return vec4<f32>(clamped, color.a);
+}
+
+@fragment
+fn fs_compute_blit(in: VertexOutput) -> @location(0) vec4<f32> {
+    return sample_input(get_uv(in));
+}
+
+fn tonemap_reinhard(color: vec3<f32>) -> vec3<f32> {
+    return color / (1.0 + color);
+}
+
+// Stephen Hill's fit of the ACES reference rendering and output transforms
+fn tonemap_aces_fitted(color: vec3<f32>) -> vec3<f32> {
+    let input_matrix = mat3x3<f32>(
+        vec3<f32>(0.59719, 0.07600, 0.02840),
+        vec3<f32>(0.35458, 0.90834, 0.13383),
+        vec3<f32>(0.04823, 0.01566, 0.83777),
+    );
+    let output_matrix = mat3x3<f32>(
+        vec3<f32>(1.60475, -0.10208, -0.00327),
+        vec3<f32>(-0.53108, 1.10813, -0.07276),
+        vec3<f32>(-0.07367, -0.00605, 1.07602),
+    );
+    let v = input_matrix * color;
+    let a = v * (v + 0.0245786) - 0.000090537;
+    let b = v * (0.983729 * v + 0.4329510) + 0.238081;
+    return clamp(output_matrix * (a / b), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+fn agx_contrast(x: vec3<f32>) -> vec3<f32> {
+    let x2 = x * x;
+    let x4 = x2 * x2;
+    return 15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x - 0.00232;
+}
+
+// Polynomial approximation of Troy Sobotka's AgX with the default look
+fn tonemap_agx(color: vec3<f32>) -> vec3<f32> {
+    let inset = mat3x3<f32>(
+        vec3<f32>(0.842479062253094, 0.0423282422610123, 0.0423756549057051),
+        vec3<f32>(0.0784335999999992, 0.878468636469772, 0.0784336),
+        vec3<f32>(0.0792237451477643, 0.0791661274605434, 0.879142973793104),
+    );
+    let outset = mat3x3<f32>(
+        vec3<f32>(1.19687900512017, -0.0528968517574562, -0.0529716355144438),
+        vec3<f32>(-0.0980208811401368, 1.15190312990417, -0.0980434501171241),
+        vec3<f32>(-0.0990297440797205, -0.0989611768448433, 1.15107367264116),
+    );
+    let min_ev = -12.47393;
+    let max_ev = 4.026069;
+    let log_color = clamp(log2(max(inset * color, vec3<f32>(1e-10))), vec3<f32>(min_ev), vec3<f32>(max_ev));
+    let encoded = agx_contrast((log_color - min_ev) / (max_ev - min_ev));
+    // The curve outputs display values, decode them back to linear
+    return pow(max(outset * encoded, vec3<f32>(0.0)), vec3<f32>(2.2));
+}
+
+// Parameters: exposure, tonemapper (0 none, 1 Reinhard, 2 ACES fitted, 3 AgX)
+@fragment
+fn fs_tonemap(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = sample_input(get_uv(in));
+    let exposed = max(color.rgb * effect.parameters[0].x, vec3<f32>(0.0));
+    var mapped = exposed;
+    switch u32(effect.parameters[0].y) {
+        case 1u: {
+            mapped = tonemap_reinhard(exposed);
+        }
+        case 2u: {
+            mapped = tonemap_aces_fitted(exposed);
+        }
+        case 3u: {
+            mapped = tonemap_agx(exposed);
+        }
+        default: {}
+    }
+    return vec4<f32>(mapped, color.a);
+}
+
+// Parameters: gamma
+@fragment
+fn fs_gamma(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = sample_input(get_uv(in));
+    return vec4<f32>(pow(max(color.rgb, vec3<f32>(0.0)), vec3<f32>(1.0 / effect.parameters[0].x)), color.a);
+}
+
+// Parameters: intensity, radius, smoothness
+@fragment
+fn fs_vignette(in: VertexOutput) -> @location(0) vec4<f32> {
+    let uv = get_uv(in);
+    let color = sample_input(uv);
+    let params = effect.parameters[0];
+    // Distance from the center, 1 in the corners
+    let center_distance = length(uv - 0.5) * sqrt(2.0);
+    let vignette = smoothstep(params.y, params.y - params.z, center_distance);
+    return vec4<f32>(color.rgb * mix(1.0, vignette, params.x), color.a);
+}
+
+// Parameters: LUT size, strength
+// The LUT is a strip of `size` slices of `size` by `size` texels, blue selecting the slice
+@fragment
+fn fs_color_grading(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = sample_input(get_uv(in));
+    let size = effect.parameters[0].x;
+    // The LUT is indexed and stored in sRGB, sampling it converts the result to linear
+    let index = linear_to_srgb(clamp(color.rgb, vec3<f32>(0.0), vec3<f32>(1.0))) * (size - 1.0);
+    let slice = floor(index.b);
+    let next_slice = min(slice + 1.0, size - 1.0);
+    let x = (index.r + 0.5) / (size * size);
+    let y = (index.g + 0.5) / size;
+    let graded = mix(
+        textureSampleLevel(t_aux, s_input, vec2<f32>(x + slice / size, y), 0.0).rgb,
+        textureSampleLevel(t_aux, s_input, vec2<f32>(x + next_slice / size, y), 0.0).rgb,
+        index.b - slice,
+    );
+    return vec4<f32>(mix(color.rgb, graded, effect.parameters[0].y), color.a);
+}
+
+// Parameters: span max, reduce multiplier, reduce minimum
+// Expects tonemapped input
+@fragment
+fn fs_fxaa(in: VertexOutput) -> @location(0) vec4<f32> {
+    let uv = get_uv(in);
+    let texel = 1.0 / vec2<f32>(textureDimensions(t_input));
+    let params = effect.parameters[0];
+
+    let color = sample_input(uv);
+    let luma_nw = luma(sample_input(uv + vec2<f32>(-1.0, -1.0) * texel).rgb);
+    let luma_ne = luma(sample_input(uv + vec2<f32>(1.0, -1.0) * texel).rgb);
+    let luma_sw = luma(sample_input(uv + vec2<f32>(-1.0, 1.0) * texel).rgb);
+    let luma_se = luma(sample_input(uv + vec2<f32>(1.0, 1.0) * texel).rgb);
+    let luma_m = luma(color.rgb);
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    var direction = vec2<f32>(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se),
+    );
+    let direction_reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * params.y, params.z);
+    let inverse_direction_min = 1.0 / (min(abs(direction.x), abs(direction.y)) + direction_reduce);
+    direction = clamp(direction * inverse_direction_min, vec2<f32>(-params.x), vec2<f32>(params.x)) * texel;
+
+    let color_a = 0.5 * (
+        sample_input(uv + direction * (1.0 / 3.0 - 0.5)).rgb
+        + sample_input(uv + direction * (2.0 / 3.0 - 0.5)).rgb
+    );
+    let color_b = color_a * 0.5 + 0.25 * (
+        sample_input(uv - direction * 0.5).rgb
+        + sample_input(uv + direction * 0.5).rgb
+    );
+    let luma_b = luma(color_b);
+    let result = select(color_b, color_a, luma_b < luma_min || luma_b > luma_max);
+    return vec4<f32>(result, color.a);
+}
+
+// Parameters: threshold, intensity, radius in pixels
+@fragment
+fn fs_bloom(in: VertexOutput) -> @location(0) vec4<f32> {
+    let uv = get_uv(in);
+    let texel = 1.0 / vec2<f32>(textureDimensions(t_input));
+    let params = effect.parameters[0];
+    let color = sample_input(uv);
+
+    // Two rings of samples around the pixel, the inner one weighted more
+    var bloom = vec3<f32>(0.0);
+    var total_weight = 0.0;
+    for (var ring = 1; ring <= 2; ring++) {
+        let ring_radius = params.z * f32(ring) / 2.0;
+        let weight = 1.0 / f32(ring);
+        for (var i = 0; i < 8; i++) {
+            let angle = (f32(i) + f32(ring) * 0.5) * 0.785398;
+            let tap = sample_input(uv + vec2<f32>(cos(angle), sin(angle)) * ring_radius * texel).rgb;
+            bloom += max(tap - params.x, vec3<f32>(0.0)) * weight;
+            total_weight += weight;
+        }
+    }
+    return vec4<f32>(color.rgb + bloom / total_weight * params.y, color.a);
+}
@@ -41,10 +41,6 @@ impl CameraController {
         }
     }
 
-    pub fn get_camera(&self) -> &Camera {
-        &self.camera
-    }
-
     pub fn get_camera_mut(&mut self) -> &mut Camera {
         &mut self.camera
     }
@@ -61,16 +57,16 @@ impl CameraController {
         );
 
         #[cfg(not(target_arch = "wasm32"))]
-        {
+        if let Some(window) = viewport.get_window() {
             if input_state.mouse_right_just_pressed {
-                viewport.get_window().set_cursor_visible(false);
+                window.set_cursor_visible(false);
                 // Set the cursor position to the center of the viewport
                 self.set_window_mouse_pos(viewport, viewport_center);
                 input_state.mouse_curr_pos = viewport_center;
                 input_state.mouse_prev_pos = input_state.mouse_curr_pos;
             }
             else if input_state.mouse_right_just_released {
-                viewport.get_window().set_cursor_visible(true);
+                window.set_cursor_visible(true);
                 // Reset the cursor position to the position where the right mouse button was pressed
                 self.set_window_mouse_pos(viewport, input_state.mouse_right_just_pressed_pos);
                 input_state.mouse_curr_pos = input_state.mouse_right_just_pressed_pos;
@@ -187,7 +183,10 @@ impl CameraController {
         viewport: &Viewport,
         pos: Vec2,
     ) {
-        viewport.get_window()
+        let Some(window) = viewport.get_window() else {
+            return;
+        };
+        window
             .set_cursor_position(PhysicalPosition::new(
                 pos.x as f64,
                 pos.y as f64,
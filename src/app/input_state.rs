@@ -20,6 +20,13 @@ pub struct InputState {
 impl InputState {
     pub fn process_window_events(&mut self, event: &WindowEvent) {
         match event {
+            WindowEvent::MouseInput {
+                state,
+                button: MouseButton::Left,
+                ..
+            } => {
+                self.mouse_left_down = *state == ElementState::Pressed;
+            }
             WindowEvent::MouseInput {
                 state,
                 button: MouseButton::Right,
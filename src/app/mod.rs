@@ -6,7 +6,7 @@ use std::time::Instant;
 #[cfg(target_arch = "wasm32")]
 use web_time::Instant;
 
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{eyre, Result};
 use winit::{
     event::*,
     event_loop::{ControlFlow, EventLoop},
@@ -16,15 +16,58 @@ use winit::{
 use crate::app::camera_controller::CameraController;
 use crate::app::input_state::InputState;
 use crate::renderer::Renderer;
+use crate::renderer::playground::{Playground, PlaygroundChannel};
+use crate::renderer::resources::file;
 use crate::renderer::scene::Scene;
 
+/// What the app renders
+pub enum AppMode {
+    /// The demo scene
+    Scene,
+    /// A fragment shader playground. Buffer passes are named A to D in order,
+    /// and every pass reads the buffers as channels 0 to 3.
+    Playground {
+        image_filepath: String,
+        buffer_filepaths: Vec<String>,
+    },
+}
+
+impl AppMode {
+    const PLAYGROUND_BUFFER_NAMES: [&'static str; 4] = ["A", "B", "C", "D"];
+
+    /// Parses `--playground <image.wgsl> [<buffer.wgsl>...]`, or selects the demo scene without arguments.
+    pub fn from_args(mut args: impl Iterator<Item = String>) -> Result<Self> {
+        match args.next().as_deref() {
+            None => Ok(Self::Scene),
+            Some("--playground") => {
+                let image_filepath = args
+                    .next()
+                    .ok_or_else(|| eyre!("Usage: --playground <image.wgsl> [<buffer.wgsl>...]"))?;
+                let buffer_filepaths = args.collect::<Vec<_>>();
+                if buffer_filepaths.len() > Self::PLAYGROUND_BUFFER_NAMES.len() {
+                    return Err(eyre!(
+                        "At most {} playground buffers are supported",
+                        Self::PLAYGROUND_BUFFER_NAMES.len(),
+                    ));
+                }
+                Ok(Self::Playground {
+                    image_filepath,
+                    buffer_filepaths,
+                })
+            }
+            Some(arg) => Err(eyre!("Unknown argument: {arg}")),
+        }
+    }
+}
+
 pub struct App {
     event_loop: EventLoop<()>,
     window: Window,
+    mode: AppMode,
 }
 
 impl App {
-    pub fn new() -> Result<Self> {
+    pub fn new(mode: AppMode) -> Result<Self> {
         let event_loop = EventLoop::new()?;
         event_loop.set_control_flow(ControlFlow::Poll);
 
@@ -58,30 +101,41 @@ impl App {
         Ok(Self {
             event_loop,
             window,
+            mode,
         })
     }
 
     pub async fn run(self) -> Result<()> {
-        let mut renderer = Renderer::new(&self.window).await?;
+        let window = &self.window;
+        let mut renderer = Renderer::new(window).await?;
         let mut camera_ctrl = CameraController::new(renderer.create_camera());
         let mut scene = renderer.create_scene();
         let mut input_state = InputState::default();
 
         Self::configure_renderer(&mut renderer)?;
-        Self::configure_scene(&mut scene, &renderer)?;
+        let mut playground = match &self.mode {
+            AppMode::Scene => {
+                Self::configure_scene(&mut scene, &renderer)?;
+                None
+            }
+            AppMode::Playground { image_filepath, buffer_filepaths } => {
+                Some(Self::create_playground(&renderer, image_filepath, buffer_filepaths).await?)
+            }
+        };
 
         let mut request_redraws = true;
         let mut close_requested = false;
 
         let mut prev_frame_time = Instant::now();
         let mut delta_time = 0.0;
+        let mut prev_redraw_time = Instant::now();
 
         self.event_loop.run(move |event, elwt| {
             match event {
                 Event::WindowEvent {
                     window_id,
                     ref event,
-                } if window_id == renderer.get_window().id() => {
+                } if window_id == window.id() => {
                     let curr_frame_time = Instant::now();
                     delta_time = curr_frame_time.duration_since(prev_frame_time).as_secs_f32();
                     prev_frame_time = curr_frame_time;
@@ -93,11 +147,27 @@ impl App {
                             close_requested = true;
                         }
                         WindowEvent::RedrawRequested => {
-                            renderer.get_window().pre_present_notify();
-                            match renderer.render(
-                                camera_ctrl.get_camera_mut(),
-                                &scene,
-                            ) {
+                            window.pre_present_notify();
+                            let result = match playground.as_mut() {
+                                Some(playground) => {
+                                    let redraw_time = Instant::now();
+                                    playground.advance(
+                                        redraw_time.duration_since(prev_redraw_time).as_secs_f32()
+                                    );
+                                    prev_redraw_time = redraw_time;
+                                    playground.set_mouse(
+                                        input_state.mouse_curr_pos,
+                                        input_state.mouse_left_down,
+                                        input_state.mouse_right_down,
+                                    );
+                                    renderer.render_playground(playground)
+                                }
+                                None => {
+                                    renderer.set_mouse_position(input_state.mouse_curr_pos);
+                                    renderer.render(camera_ctrl.get_camera_mut(), &scene)
+                                }
+                            };
+                            match result {
                                 Ok(_) => {}
                                 Err(report) => {
                                     log::error!("{report}");
@@ -147,7 +217,7 @@ impl App {
                 }
                 Event::AboutToWait => {
                     if request_redraws {
-                        renderer.get_window().request_redraw();
+                        window.request_redraw();
                     }
 
                     if close_requested {
@@ -167,9 +237,36 @@ impl App {
 
     fn configure_renderer(renderer: &mut Renderer) -> Result<()> {
         renderer.set_vsync(false);
+        // Edited shaders are picked up without restarting during development
+        #[cfg(not(target_arch = "wasm32"))]
+        if cfg!(debug_assertions) {
+            renderer.enable_shader_hot_reload("shaders")?;
+        }
         Ok(())
     }
 
+    async fn create_playground(
+        renderer: &Renderer<'_>,
+        image_filepath: &str,
+        buffer_filepaths: &[String],
+    ) -> Result<Playground> {
+        let buffer_names = &AppMode::PLAYGROUND_BUFFER_NAMES[..buffer_filepaths.len()];
+        let channels = buffer_names
+            .iter()
+            .map(|name| PlaygroundChannel::Buffer((*name).to_owned()))
+            .collect::<Vec<_>>();
+
+        let mut builder = Playground::builder();
+        for (name, filepath) in buffer_names.iter().zip(buffer_filepaths) {
+            let source = String::from_utf8(file::load_bytes(filepath).await?)?;
+            builder = builder.with_buffer_pass(name, &source, &channels);
+        }
+        let source = String::from_utf8(file::load_bytes(image_filepath).await?)?;
+        builder
+            .with_image_pass(&source, &channels)
+            .build(renderer)
+    }
+
     fn configure_scene(scene: &mut Scene, renderer: &Renderer) -> Result<()> {
         scene.add_render_object("basic", "tree", "triangle")?;
         let vp_size = renderer.get_viewport_size();
@@ -1,5 +1,5 @@
 mod app;
-mod renderer;
+pub mod renderer;
 
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
@@ -7,12 +7,18 @@ use wasm_bindgen::prelude::*;
 pub fn run() {
     #[cfg(not(target_arch = "wasm32"))]
     {
-        pollster::block_on(run_async());
+        let mode = app::AppMode::from_args(std::env::args().skip(1)).unwrap();
+        pollster::block_on(run_with_mode(mode));
     }
 }
 
-#[cfg_attr(target_arch = "wasm32", wasm_bindgen(start))]
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen(start)]
 async fn run_async() {
+    run_with_mode(app::AppMode::Scene).await;
+}
+
+async fn run_with_mode(mode: app::AppMode) {
     color_eyre::install().unwrap();
 
     cfg_if::cfg_if! {
@@ -24,6 +30,6 @@ async fn run_async() {
         }
     }
 
-    let app = app::App::new().unwrap();
+    let app = app::App::new(mode).unwrap();
     app.run().await.unwrap();
 }
\ No newline at end of file
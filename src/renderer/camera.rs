@@ -1,9 +1,7 @@
 use super::resources::shader_data::ShaderCameraUniform;
-use crate::renderer::resources::Resources;
 use crate::renderer::utils;
 use crate::renderer::viewport::Viewport;
 use glam::{Mat4, Vec3};
-use wgpu::util::DeviceExt;
 
 pub struct Camera {
     position: Vec3,
@@ -15,38 +13,12 @@ pub struct Camera {
     near: f32,
     far: f32,
     pivot: Vec3,
-
-    camera_uniform_buffer: wgpu::Buffer,
-    camera_bind_group: wgpu::BindGroup,
-    dirty: bool,
 }
 
 impl Camera {
     const DEFAULT_FOV_Y_DEG: f32 = 45.0;
 
-    pub fn new(
-        device: &wgpu::Device,
-        resources: &Resources,
-    ) -> Self {
-        let camera_uniform_buffer = device.create_buffer(
-            &wgpu::BufferDescriptor {
-                label: Some("Camera Uniform Buffer"),
-                size: size_of::<ShaderCameraUniform>() as wgpu::BufferAddress,
-                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-                mapped_at_creation: false,
-            }
-        );
-        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: resources.get_bind_group_layout("camera").unwrap(),
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: camera_uniform_buffer.as_entire_binding(),
-                }
-            ],
-            label: Some("Camera Bind Group"),
-        });
-
+    pub fn new() -> Self {
         Self {
             position: Vec3::new(0.0, 0.0, 5.0),
             forward: Vec3::NEG_Z,
@@ -57,9 +29,6 @@ impl Camera {
             near: 0.1,
             far: 100.0,
             pivot: Vec3::ZERO,
-            camera_uniform_buffer,
-            camera_bind_group,
-            dirty: true,
         }
     }
 
@@ -76,7 +45,6 @@ impl Camera {
         self.forward = (target - self.position).normalize();
         self.right = self.forward.cross(self.world_up).normalize();
         self.up = self.right.cross(self.forward).normalize();
-        self.dirty = true;
     }
 
     pub fn get_viewproj_mat(
@@ -128,6 +96,12 @@ impl Camera {
         self.far
     }
 
+    /// Sets the near and far clip planes, which also define the range of the depth buffer.
+    pub fn set_clip_planes(&mut self, near: f32, far: f32) {
+        self.near = near;
+        self.far = far;
+    }
+
     pub fn get_pivot(&self) -> Vec3 {
         self.pivot
     }
@@ -140,53 +114,19 @@ impl Camera {
         utils::calculate_pitch(self.forward)
     }
 
-    pub fn get_bind_group_layout<'a>(&self, resources: &'a Resources) -> &'a wgpu::BindGroupLayout {
-        resources.get_bind_group_layout("camera").unwrap()
-    }
-
-    pub fn get_bind_group(
-        &mut self,
-        viewport: &Viewport,
-        device: &wgpu::Device,
-        queue: &wgpu::Queue,
-    ) -> &wgpu::BindGroup {
-        if self.dirty {
-            self.update_uniform_buffer(viewport, device, queue);
-            self.dirty = false;
-        }
-        &self.camera_bind_group
-    }
-
-    fn update_uniform_buffer(
-        &self,
-        viewport: &Viewport,
-        device: &wgpu::Device,
-        queue: &wgpu::Queue,
-    ) {
-        let camera_uniform_data = ShaderCameraUniform {
+    /// Camera data for the shaders of the frame being rendered into `viewport`
+    pub fn get_uniform(&self, viewport: &Viewport) -> ShaderCameraUniform {
+        ShaderCameraUniform {
             viewproj: self.get_viewproj_mat(viewport),
             near: self.near,
             far: self.far,
             _padding: [0.0; 2],
-        };
-
-        let staging_buffer = device
-            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Camera Uniform Staging Buffer"),
-                contents: bytemuck::cast_slice(&[camera_uniform_data]),
-                usage: wgpu::BufferUsages::COPY_SRC,
-            });
-        let mut encoder = device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Camera Uniform Buffer Update Encoder"),
-            });
-        encoder.copy_buffer_to_buffer(
-            &staging_buffer,
-            0,
-            &self.camera_uniform_buffer,
-            0,
-            size_of::<ShaderCameraUniform>() as wgpu::BufferAddress,
-        );
-        queue.submit(Some(encoder.finish()));
-    }
-}
\ No newline at end of file
+        }
+    }
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,14 +1,56 @@
-use color_eyre::eyre::OptionExt;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use color_eyre::eyre::{eyre, OptionExt};
 use color_eyre::Result;
 use crate::renderer::resources::Resources;
 use crate::renderer::resources::texture::Texture;
 
+/// How many workgroups a compute object dispatches.
+#[derive(Debug, Clone)]
+pub enum ComputeDispatch {
+    /// One invocation per texel of the output texture
+    OutputTexture,
+    /// Enough workgroups to cover this many invocations along each axis,
+    /// e.g. `[count, 1, 1]` for a 1D dispatch
+    Invocations([u32; 3]),
+    /// Exactly this many workgroups along each axis
+    Workgroups([u32; 3]),
+    /// Workgroup counts read from `buffer` at `offset` when the dispatch executes,
+    /// laid out as `wgpu::util::DispatchIndirectArgs`. The buffer needs the `INDIRECT` usage.
+    Indirect {
+        buffer: Rc<wgpu::Buffer>,
+        offset: wgpu::BufferAddress,
+    },
+}
+
+impl ComputeDispatch {
+    fn get_workgroup_count(invocations: [u32; 3], workgroup_size: [u32; 3]) -> [u32; 3] {
+        std::array::from_fn(|axis| invocations[axis].div_ceil(workgroup_size[axis].max(1)))
+    }
+}
+
 pub struct ComputeObject {
     compute_material_name: String,
     output_texture: Option<Texture>,
+    dispatch: ComputeDispatch,
+    // Bind groups set by the user, keyed by their group index
+    bind_groups: BTreeMap<u32, wgpu::BindGroup>,
 }
 
 impl ComputeObject {
+    /// Creates a compute object without an output texture. Its shader's resources
+    /// are bound with `set_bindings` or `set_bind_group`.
+    pub fn new(compute_material_name: String, dispatch: ComputeDispatch) -> Self {
+        Self {
+            compute_material_name,
+            output_texture: None,
+            dispatch,
+            bind_groups: BTreeMap::new(),
+        }
+    }
+
+    /// Creates a compute object writing to a storage texture bound at group 0,
+    /// dispatching one invocation per texel.
     pub fn new_with_output_texture(
         compute_material_name: String,
         texture_width: u32,
@@ -26,9 +68,74 @@ impl ComputeObject {
         Ok(Self {
             compute_material_name,
             output_texture: Some(output_texture),
+            dispatch: ComputeDispatch::OutputTexture,
+            bind_groups: BTreeMap::new(),
         })
     }
 
+    pub fn get_compute_material_name(&self) -> &str {
+        &self.compute_material_name
+    }
+
+    pub fn get_dispatch(&self) -> &ComputeDispatch {
+        &self.dispatch
+    }
+
+    pub fn set_dispatch(&mut self, dispatch: ComputeDispatch) {
+        self.dispatch = dispatch;
+    }
+
+    /// Binds `bind_group` at `index` for every dispatch, replacing the one bound there before.
+    pub fn set_bind_group(&mut self, index: u32, bind_group: wgpu::BindGroup) {
+        self.bind_groups.insert(index, bind_group);
+    }
+
+    /// Creates a bind group for `index` from the material's layout, with `resources[i]` at binding `i`.
+    /// Storage buffers and textures are bound with `wgpu::Buffer::as_entire_binding`
+    /// and `wgpu::BindingResource::TextureView`.
+    pub fn set_bindings(
+        &mut self,
+        index: u32,
+        bindings: &[wgpu::BindingResource],
+        device: &wgpu::Device,
+        resources: &Resources,
+    ) -> Result<()> {
+        let material = resources.get_compute_material(&self.compute_material_name)?;
+        if index >= material.get_bind_group_count() {
+            return Err(eyre!(
+                "Group {index} is out of range, {} has {} bind groups",
+                self.compute_material_name,
+                material.get_bind_group_count(),
+            ));
+        }
+        if self.output_texture.is_some() && index == 0 {
+            return Err(eyre!("Group 0 holds the output texture of the compute object"));
+        }
+        if material.get_frame_bind_group_index() == Some(index) {
+            return Err(eyre!("Group {index} holds the frame uniform of {}", self.compute_material_name));
+        }
+        let entries = bindings
+            .iter()
+            .enumerate()
+            .map(|(binding, resource)| wgpu::BindGroupEntry {
+                binding: binding as u32,
+                resource: resource.clone(),
+            })
+            .collect::<Vec<_>>();
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Compute Object Bind Group"),
+            layout: &material.get_pipeline().get_bind_group_layout(index),
+            entries: &entries,
+        });
+        // Resolves right away, since validation happens when the bind group is created
+        if let Some(error) = pollster::block_on(device.pop_error_scope()) {
+            return Err(eyre!("Bindings do not match group {index} of {}: {error}", self.compute_material_name));
+        }
+        self.set_bind_group(index, bind_group);
+        Ok(())
+    }
+
     pub fn resize_output_texture(
         &mut self,
         width: u32,
@@ -61,7 +168,7 @@ impl ComputeObject {
             });
         encoder.copy_texture_to_texture(
             wgpu::ImageCopyTexture {
-                texture: &old_output_texture.get_texture(),
+                texture: old_output_texture.get_texture(),
                 mip_level: 0,
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
@@ -85,25 +192,40 @@ impl ComputeObject {
         &self,
         compute_pass: &mut wgpu::ComputePass,
         resources: &Resources,
+        frame_bind_group: &wgpu::BindGroup,
     ) -> Result<()> {
         let material = resources.get_compute_material(&self.compute_material_name)?;
-
-        let mut work_groups_x = 16;
-        let mut work_groups_y = 16;
+        let workgroup_size = material.get_workgroup_size();
 
         compute_pass.set_pipeline(material.get_pipeline());
+        compute_pass.insert_debug_marker(&self.compute_material_name);
         if let Some(texture) = &self.output_texture {
             compute_pass.set_bind_group(0, texture.get_bind_group(), &[]);
-            compute_pass.insert_debug_marker(&self.compute_material_name);
-            work_groups_x = (texture.get_width() as f64 / 16.0).ceil() as u32;
-            work_groups_y = (texture.get_height() as f64 / 16.0).ceil() as u32;
+        }
+        if let Some(index) = material.get_frame_bind_group_index() {
+            compute_pass.set_bind_group(index, frame_bind_group, &[]);
+        }
+        for (index, bind_group) in &self.bind_groups {
+            compute_pass.set_bind_group(*index, bind_group, &[]);
         }
 
-        compute_pass.dispatch_workgroups(
-            work_groups_x,
-            work_groups_y,
-            1,
-        );
+        let [x, y, z] = match &self.dispatch {
+            ComputeDispatch::OutputTexture => {
+                let texture = self.output_texture
+                    .as_ref()
+                    .ok_or_eyre("Compute object dispatched per output texel has no output texture")?;
+                ComputeDispatch::get_workgroup_count([texture.get_width(), texture.get_height(), 1], workgroup_size)
+            }
+            ComputeDispatch::Invocations(invocations) => {
+                ComputeDispatch::get_workgroup_count(*invocations, workgroup_size)
+            }
+            ComputeDispatch::Workgroups(workgroups) => *workgroups,
+            ComputeDispatch::Indirect { buffer, offset } => {
+                compute_pass.dispatch_workgroups_indirect(buffer, *offset);
+                return Ok(());
+            }
+        };
+        compute_pass.dispatch_workgroups(x, y, z);
 
         Ok(())
     }
@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use std::ops::{AddAssign, Range};
+use std::rc::Rc;
+use color_eyre::eyre::Result;
+use glam::Vec3;
+use crate::renderer::Camera;
+use crate::renderer::render_object::RenderObject;
+use crate::renderer::resources::Resources;
+use crate::renderer::resources::model::MeshRange;
+use crate::renderer::resources::shader_data::ShaderPushConstants;
+use crate::renderer::scene::Scene;
+
+/// Counts of the commands recorded to draw a scene
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DrawStatistics {
+    pub draw_calls: u32,
+    /// Instances drawn by all draw calls, one for each draw of a non-instanced object
+    pub instances: u32,
+    pub pipeline_changes: u32,
+    pub bind_group_changes: u32,
+    pub vertex_buffer_changes: u32,
+    pub index_buffer_changes: u32,
+    pub push_constant_changes: u32,
+    /// State changes left out because the state was already set
+    pub skipped_state_changes: u32,
+}
+
+impl AddAssign for DrawStatistics {
+    fn add_assign(&mut self, other: Self) {
+        self.draw_calls += other.draw_calls;
+        self.instances += other.instances;
+        self.pipeline_changes += other.pipeline_changes;
+        self.bind_group_changes += other.bind_group_changes;
+        self.vertex_buffer_changes += other.vertex_buffer_changes;
+        self.index_buffer_changes += other.index_buffer_changes;
+        self.push_constant_changes += other.push_constant_changes;
+        self.skipped_state_changes += other.skipped_state_changes;
+    }
+}
+
+/// The state a render object is drawn with, resolved from its resource names.
+/// Render objects keep it until the resources change.
+pub(crate) struct ResolvedDraw {
+    pub vertex_buffer: Rc<wgpu::Buffer>,
+    pub index_buffer: Option<Rc<wgpu::Buffer>>,
+    /// One for each mesh of the model
+    pub meshes: Vec<ResolvedMesh>,
+}
+
+/// The state a mesh of a render object is drawn with
+pub(crate) struct ResolvedMesh {
+    pub pipeline: Rc<wgpu::RenderPipeline>,
+    pub texture_bind_group: Rc<wgpu::BindGroup>,
+    pub frame_bind_group_index: Option<u32>,
+    pub alpha_cutoff: f32,
+    pub transparent: bool,
+    pub range: MeshRange,
+    /// Center of the mesh's bounds in model space, which transparent meshes are sorted by
+    pub bounds_center: Vec3,
+}
+
+/// A mesh of a render object
+struct DrawItem<'a> {
+    render_object: &'a RenderObject,
+    resolved: Rc<ResolvedDraw>,
+    mesh_index: usize,
+}
+
+impl DrawItem<'_> {
+    fn get_mesh(&self) -> &ResolvedMesh {
+        &self.resolved.meshes[self.mesh_index]
+    }
+}
+
+/// Numbers resources in the order they are first drawn with
+#[derive(Default)]
+struct FirstUseRanks(HashMap<*const (), usize>);
+
+impl FirstUseRanks {
+    fn get<T>(&mut self, resource: &Rc<T>) -> usize {
+        let next = self.0.len();
+        *self.0.entry(Rc::as_ptr(resource).cast()).or_insert(next)
+    }
+
+    /// Draws sharing a pipeline, then a texture, then a model get adjacent keys.
+    /// Ranks rather than addresses keep the order the same from run to run.
+    fn get_sort_key(&mut self, item: &DrawItem) -> (usize, usize, usize) {
+        (
+            self.get(&item.get_mesh().pipeline),
+            self.get(&item.get_mesh().texture_bind_group),
+            self.get(&item.resolved.vertex_buffer),
+        )
+    }
+}
+
+/// The meshes of a scene's visible render objects in the order they are drawn:
+/// opaque ones grouped by state, then transparent ones back-to-front so they blend over what is behind.
+pub(crate) struct DrawList<'a> {
+    items: Vec<DrawItem<'a>>,
+}
+
+impl<'a> DrawList<'a> {
+    pub fn new(scene: &'a Scene, camera: &Camera, resources: &Resources) -> Result<Self> {
+        let mut opaque = Vec::new();
+        let mut transparent = Vec::new();
+        let mut ranks = FirstUseRanks::default();
+        let camera_position = camera.get_position();
+        for (_, render_object) in scene.get_visible_render_objects() {
+            // Models still loading are not drawn at all
+            let Some(resolved) = render_object.resolve(resources)? else {
+                continue;
+            };
+            for mesh_index in 0..resolved.meshes.len() {
+                let item = DrawItem { render_object, resolved: resolved.clone(), mesh_index };
+                if item.get_mesh().transparent {
+                    let center = render_object.get_transform().get_matrix().transform_point3(item.get_mesh().bounds_center);
+                    transparent.push((center.distance_squared(camera_position), item));
+                } else {
+                    opaque.push((ranks.get_sort_key(&item), item));
+                }
+            }
+        }
+
+        // Stable, so objects sharing all state keep their insertion order
+        opaque.sort_by_key(|(key, _)| *key);
+        // Instances of a mesh are drawn together, so they are sorted as a whole by the mesh's bounds
+        transparent.sort_by(|(distance_a, _), (distance_b, _)| distance_b.total_cmp(distance_a));
+        let items = opaque
+            .into_iter()
+            .map(|(_, item)| item)
+            .chain(transparent.into_iter().map(|(_, item)| item))
+            .collect();
+        Ok(Self { items })
+    }
+
+    pub fn draw(
+        &self,
+        state: &mut DrawState,
+        camera_bind_group: &wgpu::BindGroup,
+        frame_bind_group: &wgpu::BindGroup,
+        push_constants: &ShaderPushConstants,
+    ) {
+        for item in &self.items {
+            let DrawItem { render_object, resolved, .. } = item;
+            let mesh = item.get_mesh();
+            state.set_pipeline(&mesh.pipeline);
+            state.set_push_constants(&ShaderPushConstants {
+                model: render_object.get_transform().get_matrix(),
+                alpha_cutoff: mesh.alpha_cutoff,
+                ..*push_constants
+            });
+            state.set_bind_group(0, &mesh.texture_bind_group);
+            state.set_bind_group(1, camera_bind_group);
+            if let Some(index) = mesh.frame_bind_group_index {
+                state.set_bind_group(index, frame_bind_group);
+            }
+            state.set_vertex_buffer(0, &resolved.vertex_buffer);
+            let instances = match render_object.get_instances() {
+                Some(instances) => {
+                    state.set_vertex_buffer(1, instances.get_buffer());
+                    0..instances.get_count()
+                }
+                None => 0..1,
+            };
+            match &resolved.index_buffer {
+                Some(index_buffer) => {
+                    state.set_index_buffer(index_buffer);
+                    state.draw_indexed(mesh.range.get_elements(), mesh.range.base_vertex, instances);
+                }
+                None => state.draw(mesh.range.get_elements(), instances),
+            }
+        }
+    }
+}
+
+/// Records commands into a render pass, leaving out state changes to what is already set.
+/// Bound objects are told apart by address, so they must outlive the pass.
+pub(crate) struct DrawState<'pass, 'encoder> {
+    render_pass: &'pass mut wgpu::RenderPass<'encoder>,
+    pipeline: Option<*const wgpu::RenderPipeline>,
+    bind_groups: Vec<Option<*const wgpu::BindGroup>>,
+    vertex_buffers: Vec<Option<*const wgpu::Buffer>>,
+    index_buffer: Option<*const wgpu::Buffer>,
+    push_constants: Option<ShaderPushConstants>,
+    statistics: DrawStatistics,
+}
+
+impl<'pass, 'encoder> DrawState<'pass, 'encoder> {
+    pub fn new(render_pass: &'pass mut wgpu::RenderPass<'encoder>) -> Self {
+        Self {
+            render_pass,
+            pipeline: None,
+            bind_groups: Vec::new(),
+            vertex_buffers: Vec::new(),
+            index_buffer: None,
+            push_constants: None,
+            statistics: DrawStatistics::default(),
+        }
+    }
+
+    pub fn get_statistics(&self) -> DrawStatistics {
+        self.statistics
+    }
+
+    pub fn set_pipeline(&mut self, pipeline: &wgpu::RenderPipeline) {
+        if self.update(|state| &mut state.pipeline, pipeline) {
+            self.render_pass.set_pipeline(pipeline);
+            self.statistics.pipeline_changes += 1;
+            // Push constants are reset when the pipeline layout changes
+            self.push_constants = None;
+        }
+    }
+
+    pub fn set_bind_group(&mut self, index: u32, bind_group: &wgpu::BindGroup) {
+        if self.update(|state| get_slot(&mut state.bind_groups, index), bind_group) {
+            self.render_pass.set_bind_group(index, bind_group, &[]);
+            self.statistics.bind_group_changes += 1;
+        }
+    }
+
+    /// Binds all of `buffer` to the vertex buffer `slot`
+    pub fn set_vertex_buffer(&mut self, slot: u32, buffer: &wgpu::Buffer) {
+        if self.update(|state| get_slot(&mut state.vertex_buffers, slot), buffer) {
+            self.render_pass.set_vertex_buffer(slot, buffer.slice(..));
+            self.statistics.vertex_buffer_changes += 1;
+        }
+    }
+
+    /// Binds all of `buffer` as `u32` indices
+    pub fn set_index_buffer(&mut self, buffer: &wgpu::Buffer) {
+        if self.update(|state| &mut state.index_buffer, buffer) {
+            self.render_pass.set_index_buffer(buffer.slice(..), wgpu::IndexFormat::Uint32);
+            self.statistics.index_buffer_changes += 1;
+        }
+    }
+
+    pub fn set_push_constants(&mut self, push_constants: &ShaderPushConstants) {
+        let unchanged = self.push_constants
+            .as_ref()
+            .is_some_and(|bound| bytemuck::bytes_of(bound) == bytemuck::bytes_of(push_constants));
+        if unchanged {
+            self.statistics.skipped_state_changes += 1;
+            return;
+        }
+        self.render_pass.set_push_constants(
+            wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+            0,
+            bytemuck::bytes_of(push_constants),
+        );
+        self.push_constants = Some(*push_constants);
+        self.statistics.push_constant_changes += 1;
+    }
+
+    pub fn draw(&mut self, vertices: Range<u32>, instances: Range<u32>) {
+        self.statistics.draw_calls += 1;
+        self.statistics.instances += instances.len() as u32;
+        self.render_pass.draw(vertices, instances);
+    }
+
+    pub fn draw_indexed(&mut self, indices: Range<u32>, base_vertex: i32, instances: Range<u32>) {
+        self.statistics.draw_calls += 1;
+        self.statistics.instances += instances.len() as u32;
+        self.render_pass.draw_indexed(indices, base_vertex, instances);
+    }
+
+    /// Points the bound state selected by `get_bound` at `value`, returns whether it changed
+    fn update<T>(&mut self, get_bound: impl FnOnce(&mut Self) -> &mut Option<*const T>, value: &T) -> bool {
+        let bound = get_bound(self);
+        let value = std::ptr::from_ref(value);
+        if *bound == Some(value) {
+            self.statistics.skipped_state_changes += 1;
+            return false;
+        }
+        *bound = Some(value);
+        true
+    }
+}
+
+fn get_slot<T>(slots: &mut Vec<Option<T>>, index: u32) -> &mut Option<T> {
+    let index = index as usize;
+    if slots.len() <= index {
+        slots.resize_with(index + 1, || None);
+    }
+    &mut slots[index]
+}
@@ -1,32 +1,133 @@
-use std::marker::PhantomData;
-use super::resources::shader_data::{ShaderCameraUniform, ShaderSceneUniform};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
 use color_eyre::eyre::Result;
+use glam::{Vec2, Vec3};
+use super::resources::{Resources, CAMERA_BIND_GROUP_LAYOUT_NAME, FRAME_BIND_GROUP_LAYOUT_NAME};
+use super::resources::shader_data::{ShaderCameraUniform, ShaderFrameUniform};
+use super::uploader::Uploader;
 
-// Contains everything needed to draw a frame
-pub struct FrameDrawContext<'a> {
-    phantom_data: PhantomData<&'a ()>,
+/// Keeps the values of the frame uniform from one frame to the next.
+#[derive(Debug, Default)]
+pub struct FrameClock {
+    uniform: ShaderFrameUniform,
+    start_time: Option<Instant>,
+    prev_time: Option<Instant>,
 }
 
-#[derive(Debug)]
+impl FrameClock {
+    /// Values of the frame being rendered, or of the last one between frames
+    pub fn get_uniform(&self) -> &ShaderFrameUniform {
+        &self.uniform
+    }
+
+    pub fn set_mouse_position(&mut self, position: Vec2) {
+        self.uniform.mouse = position;
+    }
+
+    /// Advances the time and frame counter for the next frame.
+    pub fn advance(&mut self, resolution: Vec2, camera_position: Vec3) -> &ShaderFrameUniform {
+        let now = Instant::now();
+        let start_time = *self.start_time.get_or_insert(now);
+        if let Some(prev_time) = self.prev_time {
+            self.uniform.frame += 1;
+            self.uniform.delta_time = now.duration_since(prev_time).as_secs_f32();
+        }
+        self.prev_time = Some(now);
+
+        self.uniform.time = now.duration_since(start_time).as_secs_f32();
+        self.uniform.resolution = resolution;
+        self.uniform.camera_position = camera_position;
+        &self.uniform
+    }
+}
+
+/// GPU data owned by one frame in flight, so the CPU can prepare a frame
+/// while the GPU still reads the data of the previous ones.
 pub struct Frame {
-    uniform_buffer: wgpu::Buffer,
+    frame_uniform_buffer: wgpu::Buffer,
+    frame_bind_group: wgpu::BindGroup,
+    camera_uniform_buffer: wgpu::Buffer,
+    camera_bind_group: wgpu::BindGroup,
+    // The last submission that used this frame's data
+    submission: Option<wgpu::SubmissionIndex>,
 }
 
 impl Frame {
-    pub fn new(device: &wgpu::Device) -> Self {
-        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Uniform Buffer"),
-            size: (size_of::<ShaderSceneUniform>() + size_of::<ShaderCameraUniform>()) as wgpu::BufferAddress,
-            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-
-        Self {
-            uniform_buffer,
+    pub fn new(device: &wgpu::Device, resources: &Resources) -> Result<Self> {
+        let (frame_uniform_buffer, frame_bind_group) = create_uniform_binding::<ShaderFrameUniform>(
+            "Frame",
+            resources.get_bind_group_layout(FRAME_BIND_GROUP_LAYOUT_NAME)?,
+            device,
+        );
+        let (camera_uniform_buffer, camera_bind_group) = create_uniform_binding::<ShaderCameraUniform>(
+            "Camera",
+            resources.get_bind_group_layout(CAMERA_BIND_GROUP_LAYOUT_NAME)?,
+            device,
+        );
+
+        Ok(Self {
+            frame_uniform_buffer,
+            frame_bind_group,
+            camera_uniform_buffer,
+            camera_bind_group,
+            submission: None,
+        })
+    }
+
+    pub fn get_frame_bind_group(&self) -> &wgpu::BindGroup {
+        &self.frame_bind_group
+    }
+
+    pub fn get_camera_bind_group(&self) -> &wgpu::BindGroup {
+        &self.camera_bind_group
+    }
+
+    /// Blocks until the GPU has finished the last submission that used this frame's data.
+    pub fn wait_until_available(&mut self, device: &wgpu::Device) {
+        if let Some(submission) = self.submission.take() {
+            device.poll(wgpu::Maintain::WaitForSubmissionIndex(submission));
         }
     }
 
-    pub fn draw(&self, ctx: FrameDrawContext) -> Result<()> {
-        Ok(())
+    pub fn set_submission(&mut self, submission: wgpu::SubmissionIndex) {
+        self.submission = Some(submission);
     }
-}
\ No newline at end of file
+
+    pub fn upload(
+        &self,
+        frame_uniform: &ShaderFrameUniform,
+        camera_uniform: &ShaderCameraUniform,
+        encoder: &mut wgpu::CommandEncoder,
+        uploader: &mut Uploader,
+        device: &wgpu::Device,
+    ) {
+        uploader.upload(encoder, &self.frame_uniform_buffer, 0, bytemuck::bytes_of(frame_uniform), device);
+        uploader.upload(encoder, &self.camera_uniform_buffer, 0, bytemuck::bytes_of(camera_uniform), device);
+    }
+}
+
+fn create_uniform_binding<T>(
+    name: &str,
+    layout: &wgpu::BindGroupLayout,
+    device: &wgpu::Device,
+) -> (wgpu::Buffer, wgpu::BindGroup) {
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(&format!("{name} Uniform Buffer")),
+        size: size_of::<T>() as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }
+        ],
+        label: Some(&format!("{name} Bind Group")),
+    });
+    (buffer, bind_group)
+}
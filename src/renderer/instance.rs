@@ -0,0 +1,109 @@
+use color_eyre::eyre::{eyre, Result};
+use glam::Vec4;
+use crate::renderer::Transform;
+use crate::renderer::resources::shader_data::ShaderInstance;
+
+/// One copy of an instanced render object
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Instance {
+    /// Relative to the render object's transform
+    pub transform: Transform,
+    pub tint: Vec4,
+    /// Passed to shaders as is
+    pub custom_data: Vec4,
+}
+
+impl Instance {
+    pub fn new(transform: Transform) -> Self {
+        Self {
+            transform,
+            tint: Vec4::ONE,
+            custom_data: Vec4::ZERO,
+        }
+    }
+
+    pub fn with_tint(mut self, tint: Vec4) -> Self {
+        self.tint = tint;
+        self
+    }
+
+    pub fn with_custom_data(mut self, custom_data: Vec4) -> Self {
+        self.custom_data = custom_data;
+        self
+    }
+
+    pub fn as_shader_data(&self) -> ShaderInstance {
+        ShaderInstance {
+            model: self.transform.get_matrix(),
+            tint: self.tint,
+            custom_data: self.custom_data,
+        }
+    }
+}
+
+/// GPU buffer of the instances of a render object.
+/// Grows when more instances are set than fit, otherwise instances are overwritten in place.
+#[derive(Debug)]
+pub struct InstanceBuffer {
+    buffer: wgpu::Buffer,
+    count: u32,
+    capacity: u32,
+}
+
+impl InstanceBuffer {
+    pub fn new(instances: &[Instance], device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let capacity = (instances.len() as u32).max(1);
+        let mut result = Self {
+            buffer: create_buffer(capacity, device),
+            count: 0,
+            capacity,
+        };
+        result.set(instances, device, queue);
+        result
+    }
+
+    pub fn get_buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+
+    pub fn get_count(&self) -> u32 {
+        self.count
+    }
+
+    /// Replaces all instances
+    pub fn set(&mut self, instances: &[Instance], device: &wgpu::Device, queue: &wgpu::Queue) {
+        let count = instances.len() as u32;
+        if count > self.capacity {
+            // Room to grow, so adding instances one by one does not recreate the buffer every time
+            self.capacity = count.next_power_of_two();
+            self.buffer = create_buffer(self.capacity, device);
+        }
+        self.count = count;
+        write_instances(&self.buffer, 0, instances, queue);
+    }
+
+    /// Overwrites the instances from `first` on, which must already exist
+    pub fn update(&mut self, first: u32, instances: &[Instance], queue: &wgpu::Queue) -> Result<()> {
+        let end = first as usize + instances.len();
+        if end > self.count as usize {
+            return Err(eyre!("Instances {first}..{end} out of range, there are {} instances", self.count));
+        }
+        write_instances(&self.buffer, first, instances, queue);
+        Ok(())
+    }
+}
+
+fn create_buffer(capacity: u32, device: &wgpu::Device) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Instance Buffer"),
+        size: (capacity as usize * size_of::<ShaderInstance>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
+
+fn write_instances(buffer: &wgpu::Buffer, first: u32, instances: &[Instance], queue: &wgpu::Queue) {
+    let data = instances.iter().map(Instance::as_shader_data).collect::<Vec<_>>();
+    let offset = (first as usize * size_of::<ShaderInstance>()) as wgpu::BufferAddress;
+    queue.write_buffer(buffer, offset, bytemuck::cast_slice(&data));
+}
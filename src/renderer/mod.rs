@@ -1,4 +1,5 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::path::Path;
 use std::rc::Rc;
 use color_eyre::eyre::{eyre, OptionExt, Result};
 use winit::{dpi::PhysicalSize, window::Window};
@@ -6,37 +7,74 @@ use winit::{dpi::PhysicalSize, window::Window};
 pub mod viewport;
 pub mod utils;
 pub mod scene;
-mod resources;
-//mod frame;
+pub mod playground;
+pub mod resources;
+pub mod uploader;
+pub mod render_graph;
+pub mod post_process;
+pub mod particles;
+mod frame;
 mod camera;
 mod render_object;
+mod instance;
+mod draw_list;
 mod compute_object;
+mod object_pool;
+mod readback;
+mod transform;
 
 pub use camera::Camera;
+pub use compute_object::{ComputeDispatch, ComputeObject};
+pub use draw_list::DrawStatistics;
+pub use instance::Instance;
+pub use transform::Transform;
 use scene::Scene;
+use frame::{Frame, FrameClock};
+use uploader::Uploader;
+use render_graph::{FrameContext, RenderGraph};
+use post_process::PostProcessStack;
+use playground::Playground;
 use viewport::Viewport;
-use resources::Resources;
-use crate::renderer::resources::shader_data::ShaderPushConstants;
+use resources::{AssetHandles, Resources};
+use resources::registry::NameCollision;
+use resources::gltf_loader::GltfAsset;
+use resources::obj_loader::ObjAsset;
+use resources::asset_loader::{AssetState, LoadProgress};
+#[cfg(not(target_arch = "wasm32"))]
+use resources::shader_watcher::ShaderWatcher;
+use crate::renderer::resources::shader_data::{ShaderFrameUniform, ShaderPushConstants};
 
 pub struct Renderer<'window> {
     viewport: Viewport<'window>,
     device: Rc<wgpu::Device>,
     queue: Rc<wgpu::Queue>,
     resources: Rc<RefCell<Resources>>,
+    frames: Vec<Frame>,
+    frame_index: usize,
+    frame_clock: FrameClock,
+    draw_statistics: DrawStatistics,
+    uploader: RefCell<Uploader>,
+    render_graph: RenderGraph,
+    post_process: Rc<RefCell<PostProcessStack>>,
+    capture_next_frame: bool,
+    captured_frame: Option<image::RgbaImage>,
+    #[cfg(not(target_arch = "wasm32"))]
+    shader_watcher: Option<ShaderWatcher>,
 }
 
 impl<'window> Renderer<'window> {
-    pub async fn new(window: &'window Window) -> Result<Renderer<'window>> {
-        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
-            #[cfg(not(target_arch = "wasm32"))]
-            backends: wgpu::util::backend_bits_from_env()
-                .unwrap_or(wgpu::Backends::PRIMARY),
-            #[cfg(target_arch = "wasm32")]
-            // NOTE: WebGPU is supported, but does not yet work in release version of Firefox
-            backends: wgpu::Backends::BROWSER_WEBGPU,
-            ..Default::default()
-        });
+    const DEFAULT_BACKGROUND: wgpu::Color = wgpu::Color {
+        r: 0.1,
+        g: 0.2,
+        b: 0.3,
+        a: 1.0,
+    };
+    const HEADLESS_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+    /// Number of frames the CPU may prepare before waiting for the GPU to finish the oldest one
+    pub const FRAMES_IN_FLIGHT: usize = 2;
 
+    pub async fn new(window: &'window Window) -> Result<Renderer<'window>> {
+        let instance = create_instance(wgpu::Backends::PRIMARY);
         let surface = instance.create_surface(window)?;
 
         let adapter = instance
@@ -47,54 +85,79 @@ impl<'window> Renderer<'window> {
             })
             .await
             .ok_or_eyre("Failed to find an appropriate adapter")?;
+        let (device, queue) = request_device(&adapter).await?;
 
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    required_features: wgpu::Features::PUSH_CONSTANTS,
-                    required_limits: if cfg!(target_arch = "wasm32") {
-                        wgpu::Limits {
-                            max_texture_dimension_1d: 8192,
-                            max_texture_dimension_2d: 8192,
-                            max_texture_dimension_3d: 2048,
-                            max_texture_array_layers: 256,
-                            max_bind_groups: 4,
-                            max_buffer_size: 256 * 1024 * 1024, // 256 MB
-                            max_vertex_buffers: 8,
-                            max_vertex_attributes: 16,
-                            max_vertex_buffer_array_stride: 2048,
-                            max_push_constant_size: 128, // Typical browser support
-                            max_storage_buffer_binding_size: 128 * 1024 * 1024, // 128 MB
-                            ..Default::default()
-                        }
-                    } else {
-                        wgpu::Limits {
-                            max_push_constant_size: size_of::<ShaderPushConstants>() as u32,
-                            ..Default::default()
-                        }
-                    },
-                    label: None,
-                    memory_hints: Default::default(),
-                },
-                None,
-            )
+        let viewport = Viewport::new(window, Self::DEFAULT_BACKGROUND, surface, &adapter, &device)?;
+        Self::new_with_viewport(viewport, device, queue).await
+    }
+
+    /// Creates a renderer without a window that draws into an offscreen texture.
+    /// Set `force_fallback_adapter` to request a software adapter
+    /// on machines without a GPU.
+    pub async fn new_headless(
+        size: PhysicalSize<u32>,
+        force_fallback_adapter: bool,
+    ) -> Result<Renderer<'window>> {
+        Self::new_headless_with_format(size, Self::HEADLESS_FORMAT, force_fallback_adapter).await
+    }
+
+    /// Like `new_headless`, but draws into a texture of `format`, e.g. to match a window surface.
+    pub async fn new_headless_with_format(
+        size: PhysicalSize<u32>,
+        format: wgpu::TextureFormat,
+        force_fallback_adapter: bool,
+    ) -> Result<Renderer<'window>> {
+        // Software rasterizers are often only exposed through secondary backends such as GL
+        let instance = create_instance(wgpu::Backends::all());
+
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: None,
+                force_fallback_adapter,
+            })
             .await
-            .map_err(|e| eyre!(e.to_string()))?;
+            .ok_or_eyre("Failed to find an appropriate adapter")?;
+        let (device, queue) = request_device(&adapter).await?;
 
-        let background = wgpu::Color {
-            r: 0.1,
-            g: 0.2,
-            b: 0.3,
-            a: 1.0,
-        };
-        let viewport = Viewport::new(window, background, surface, &adapter)?;
-        let resources = Rc::new(RefCell::new(Resources::new(&device, &queue, &viewport).await?));
+        let viewport = Viewport::new_offscreen(
+            size,
+            Self::DEFAULT_BACKGROUND,
+            format,
+            &adapter,
+            &device,
+        )?;
+        Self::new_with_viewport(viewport, device, queue).await
+    }
+
+    async fn new_with_viewport(
+        viewport: Viewport<'window>,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+    ) -> Result<Renderer<'window>> {
+        let resources = Resources::new(&device, &queue, &viewport).await?;
+        let frames = (0..Self::FRAMES_IN_FLIGHT)
+            .map(|_| Frame::new(&device, &resources))
+            .collect::<Result<Vec<_>>>()?;
+        let post_process = Rc::new(RefCell::new(PostProcessStack::new(&device, &resources)?));
+        let render_graph = RenderGraph::new_with_scene_passes(post_process.clone())?;
 
         Ok(Self {
             viewport,
             device: Rc::new(device),
             queue: Rc::new(queue),
-            resources,
+            resources: Rc::new(RefCell::new(resources)),
+            frames,
+            frame_index: 0,
+            frame_clock: FrameClock::default(),
+            draw_statistics: DrawStatistics::default(),
+            uploader: RefCell::new(Uploader::new()),
+            render_graph,
+            post_process,
+            capture_next_frame: false,
+            captured_frame: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            shader_watcher: None,
         })
     }
 
@@ -106,11 +169,39 @@ impl<'window> Renderer<'window> {
         &mut self.viewport
     }
 
+    pub fn get_device(&self) -> &wgpu::Device {
+        &self.device
+    }
+
+    pub fn get_queue(&self) -> &wgpu::Queue {
+        &self.queue
+    }
+
+    /// Shared with every scene, so resources registered here are available to all of them.
+    pub fn get_resources(&self) -> &Rc<RefCell<Resources>> {
+        &self.resources
+    }
+
+    /// Values of the frame uniform as of the last call to `render`
+    pub fn get_frame_uniform(&self) -> &ShaderFrameUniform {
+        self.frame_clock.get_uniform()
+    }
+
+    /// Commands recorded to draw the scene in the last call to `render`
+    pub fn get_draw_statistics(&self) -> DrawStatistics {
+        self.draw_statistics
+    }
+
+    /// Sets the cursor position passed to shaders, in pixels from the top left corner.
+    pub fn set_mouse_position(&mut self, position: glam::Vec2) {
+        self.frame_clock.set_mouse_position(position);
+    }
+
     pub fn get_viewport_size(&self) -> PhysicalSize<u32> {
         self.viewport.get_size()
     }
 
-    pub fn get_window(&self) -> &Window {
+    pub fn get_window(&self) -> Option<&Window> {
         self.viewport.get_window()
     }
 
@@ -122,10 +213,93 @@ impl<'window> Renderer<'window> {
         self.resources
             .borrow_mut()
             .get_fullscreen_quad_mut()
-            .resize_to_viewport(&self.viewport, &self.device, &self.queue);
+            .resize_to_viewport(&self.viewport);
+    }
+
+    /// Sets the samples per pixel of the scene pass, 1 to disable multisampling.
+    /// Fails for counts missing from `Viewport::get_supported_sample_counts`.
+    pub fn set_sample_count(&mut self, sample_count: u32) -> Result<()> {
+        self.viewport.set_sample_count(sample_count, &self.device)?;
+        self.resources
+            .try_borrow_mut()?
+            .set_viewport_sample_count(sample_count, &self.device);
+        Ok(())
     }
 
     pub fn render(&mut self, camera: &mut Camera, scene: &Scene) -> Result<()> {
+        self.process_loaded_assets()?;
+        #[cfg(not(target_arch = "wasm32"))]
+        self.reload_changed_shaders()?;
+
+        let size = self.get_viewport_size();
+        let frame_uniform = *self.frame_clock.advance(
+            glam::Vec2::new(size.width as f32, size.height as f32),
+            camera.get_position(),
+        );
+        let camera_uniform = camera.get_uniform(&self.viewport);
+        // The graph is moved out while `render_frame` borrows the renderer
+        let mut render_graph = std::mem::take(&mut self.render_graph);
+        let draw_statistics = Cell::new(DrawStatistics::default());
+        let result = self.render_frame(|renderer, encoder, output| {
+            {
+                // Released before the passes, which record uploads of their own
+                let uploader = &mut *renderer.uploader.try_borrow_mut()?;
+                renderer.frames[renderer.frame_index].upload(
+                    &frame_uniform,
+                    &camera_uniform,
+                    encoder,
+                    uploader,
+                    &renderer.device,
+                );
+                renderer.resources
+                    .try_borrow_mut()?
+                    .get_fullscreen_quad_mut()
+                    .upload_pending(encoder, uploader, &renderer.device);
+            }
+
+            let output_view = output.create_view(&wgpu::TextureViewDescriptor::default());
+            let resources = renderer.resources.try_borrow()?;
+            let frame_context = FrameContext {
+                output,
+                output_view: &output_view,
+                scene,
+                camera,
+                resources: &resources,
+                frame: &renderer.frames[renderer.frame_index],
+                viewport: &renderer.viewport,
+                device: &renderer.device,
+                queue: &renderer.queue,
+                draw_statistics: &draw_statistics,
+                uploader: &renderer.uploader,
+            };
+            render_graph.execute(&frame_context, encoder)
+        });
+        self.render_graph = render_graph;
+        self.draw_statistics = draw_statistics.get();
+        result
+    }
+
+    /// Renders a frame of the playground instead of a scene.
+    /// Unlike `render`, this does not reload changed shaders, as playground passes are not loaded from files.
+    pub fn render_playground(&mut self, playground: &mut Playground) -> Result<()> {
+        self.process_loaded_assets()?;
+
+        self.render_frame(|renderer, encoder, output| {
+            let resources = renderer.resources.try_borrow()?;
+            let uploader = &mut *renderer.uploader.try_borrow_mut()?;
+            playground.encode(encoder, output, &resources, uploader, &renderer.device)
+        })
+    }
+
+    /// Acquires the next viewport texture, lets `encode` record the frame and its uploads into it,
+    /// then submits, captures and presents the frame.
+    /// Waits first if the GPU has not finished the frame that last used the same frame data.
+    fn render_frame(
+        &mut self,
+        encode: impl FnOnce(&Self, &mut wgpu::CommandEncoder, &wgpu::Texture) -> Result<()>,
+    ) -> Result<()> {
+        self.frames[self.frame_index].wait_until_available(&self.device);
+
         let output = match self.viewport.get_current_texture() {
             Ok(output) => output,
             Err(wgpu::SurfaceError::Lost) => {
@@ -147,89 +321,46 @@ impl<'window> Renderer<'window> {
                 label: Some("Command Encoder"),
             });
 
-        {
-            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: Some("Compute Pass"),
-                timestamp_writes: None,
-            });
+        let result = encode(self, &mut encoder, output.get_texture());
 
-            for compute_object in scene.get_compute_objects() {
-                compute_object.dispatch(
-                    &mut compute_pass,
-                    &self.resources.borrow(),
-                )?;
-            }
-        }
+        // The staging memory is reclaimed even if the frame failed and its encoder is dropped
+        self.uploader.get_mut().finish();
+        let submission = result.map(|()| self.queue.submit(std::iter::once(encoder.finish())));
+        self.uploader.get_mut().recall();
+        let submission = submission?;
+        self.frames[self.frame_index].set_submission(submission);
+        self.frame_index = (self.frame_index + 1) % Self::FRAMES_IN_FLIGHT;
 
-        let compute_texture = scene.get_compute_objects()[0].get_output_texture().unwrap();
-        let copy_size = wgpu::Extent3d {
-            width: output.texture.width().min(compute_texture.get_width()),
-            height: output.texture.height().min(compute_texture.get_height()),
-            depth_or_array_layers: 1,
-        };
-        encoder.copy_texture_to_texture(
-            wgpu::ImageCopyTexture {
-                texture: scene.get_compute_objects()[0].get_output_texture().unwrap().get_texture(),
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            wgpu::ImageCopyTexture {
-                texture: &output.texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All,
-            },
-            copy_size,
-        );
-
-        {
-            let view = output
-                .texture
-                .create_view(&wgpu::TextureViewDescriptor::default());
-            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        //load: wgpu::LoadOp::Clear(self.viewport.get_background()),
-                        load: wgpu::LoadOp::Load,
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                occlusion_query_set: None,
-                timestamp_writes: None,
-            });
-            
-            // Set push constants
-            let push_constants = ShaderPushConstants {
-                flipv: 1,
-                gamma_correct: if self.viewport.get_surface_format().is_srgb() { 0 } else { 1 },
-            };
-            for render_object in scene.get_render_objects() {
-                render_object.draw(
-                    &mut render_pass,
-                    camera,
-                    &self.resources.borrow(),
-                    &self.viewport,
-                    &self.device,
-                    &self.queue,
-                    Some(&push_constants),
-                )?;
-
-            }
+        if self.capture_next_frame {
+            self.capture_next_frame = false;
+            self.captured_frame = Some(readback::read_texture_to_image(
+                output.get_texture(),
+                &self.device,
+                &self.queue,
+            )?);
         }
 
-        self.queue.submit(std::iter::once(encoder.finish()));
         output.present();
 
         Ok(())
     }
 
+    /// The passes `render` executes each frame. Passes can be added to change the frame's structure.
+    pub fn get_render_graph(&self) -> &RenderGraph {
+        &self.render_graph
+    }
+
+    pub fn get_render_graph_mut(&mut self) -> &mut RenderGraph {
+        &mut self.render_graph
+    }
+
+    /// The effects applied to the scene before it is written to the viewport
+    pub fn get_post_process(&self) -> &Rc<RefCell<PostProcessStack>> {
+        &self.post_process
+    }
+
     pub fn create_camera(&self) -> Camera {
-        Camera::new(&self.device, &self.resources.borrow())
+        Camera::new()
     }
 
     pub fn create_scene(&self) -> Scene {
@@ -243,4 +374,202 @@ impl<'window> Renderer<'window> {
     pub fn set_vsync(&mut self, enable: bool) {
         self.viewport.set_vsync(enable);
     }
-}
\ No newline at end of file
+
+    /// Loads a glTF 2.0 file and registers it as a model named `name`.
+    /// Its images become textures named `<model name>/<image index>`,
+    /// and meshes are drawn with their material's base color texture.
+    /// Paths are relative to the crate root, like `request_gltf_load`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn load_gltf_from_file(
+        &mut self,
+        name: &str,
+        filepath: &str,
+        on_collision: NameCollision,
+    ) -> Result<AssetHandles> {
+        let asset = GltfAsset::new_from_file(resources::file::get_native_path(filepath))?;
+        self.resources
+            .try_borrow_mut()?
+            .add_gltf_asset(name, asset, on_collision, &self.device, &self.queue)
+    }
+
+    /// Loads a glTF 2.0 file with embedded buffers and images from memory
+    /// and registers it as a model named `name`.
+    pub fn load_gltf_from_bytes(
+        &mut self,
+        name: &str,
+        bytes: &[u8],
+        on_collision: NameCollision,
+    ) -> Result<AssetHandles> {
+        let asset = GltfAsset::new_from_bytes(bytes)?;
+        self.resources
+            .try_borrow_mut()?
+            .add_gltf_asset(name, asset, on_collision, &self.device, &self.queue)
+    }
+
+    /// Loads a Wavefront OBJ file and its MTL material libraries and registers it as a model
+    /// named `name`, with one mesh per material. Diffuse maps become textures named
+    /// `<model name>/<material>`, which the meshes of their material are drawn with.
+    /// Paths are relative to the crate root on native and the page on web.
+    pub async fn load_obj_from_file(
+        &mut self,
+        name: &str,
+        filepath: &str,
+        on_collision: NameCollision,
+    ) -> Result<AssetHandles> {
+        let asset = ObjAsset::new_from_file(filepath).await?;
+        self.resources
+            .try_borrow_mut()?
+            .add_obj_asset(name, asset, on_collision, &self.device, &self.queue)
+    }
+
+    /// Starts loading an image in the background to become the texture `name`.
+    /// Render objects can use it right away and show a placeholder until it is loaded.
+    pub fn request_texture_load(&mut self, name: &str, filepath: &str) -> Result<()> {
+        self.resources.try_borrow_mut()?.get_asset_loader_mut().load_texture(name, filepath);
+        Ok(())
+    }
+
+    /// Starts loading a Wavefront OBJ file in the background to become the model `name`.
+    /// Render objects can use it right away and are skipped until it is loaded.
+    pub fn request_obj_load(&mut self, name: &str, filepath: &str) -> Result<()> {
+        self.resources.try_borrow_mut()?.get_asset_loader_mut().load_obj(name, filepath);
+        Ok(())
+    }
+
+    /// Starts loading a glTF 2.0 file in the background to become the model `name`.
+    /// Render objects can use it right away and are skipped until it is loaded.
+    pub fn request_gltf_load(&mut self, name: &str, filepath: &str) -> Result<()> {
+        self.resources.try_borrow_mut()?.get_asset_loader_mut().load_gltf(name, filepath);
+        Ok(())
+    }
+
+    pub fn get_asset_state(&self, name: &str) -> Result<Option<AssetState>> {
+        Ok(self.resources.try_borrow()?.get_asset_loader().get_state(name).cloned())
+    }
+
+    pub fn get_load_progress(&self) -> Result<LoadProgress> {
+        Ok(self.resources.try_borrow()?.get_asset_loader().get_progress())
+    }
+
+    /// Registers the assets that finished loading in the background.
+    /// Called by `render`, so this is only needed to pick up assets without rendering.
+    pub fn process_loaded_assets(&mut self) -> Result<()> {
+        self.resources
+            .try_borrow_mut()?
+            .process_loaded_assets(&self.device, &self.queue);
+        Ok(())
+    }
+
+    /// Watches `directory` for changes to WGSL files and rebuilds the materials using them
+    /// before each frame. Precompiled shaders are matched to their WGSL source by file name.
+    /// Playground passes are not reloaded, see `render_playground`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn enable_shader_hot_reload(&mut self, directory: &str) -> Result<()> {
+        self.shader_watcher = Some(ShaderWatcher::new(directory)?);
+        log::info!("Watching {directory} for shader changes");
+        Ok(())
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn disable_shader_hot_reload(&mut self) {
+        self.shader_watcher = None;
+    }
+
+    /// Rebuilds the materials whose shaders changed on disk, if hot reloading is enabled.
+    /// Called by `render`, so this is only needed to pick up changes without rendering.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn reload_changed_shaders(&mut self) -> Result<()> {
+        let Some(shader_watcher) = self.shader_watcher.as_mut() else {
+            return Ok(());
+        };
+        let changed_paths = shader_watcher.poll_changed();
+        if !changed_paths.is_empty() {
+            self.resources
+                .try_borrow_mut()?
+                .reload_shaders(&changed_paths, &self.device);
+        }
+        Ok(())
+    }
+
+    /// Reads the offscreen render target of a headless renderer back into an image.
+    /// Windowed renderers must use `request_frame_capture` instead,
+    /// since surface textures are gone once presented.
+    pub fn read_frame(&self) -> Result<image::RgbaImage> {
+        let texture = self.viewport
+            .get_offscreen_texture()
+            .ok_or_eyre("Only offscreen viewports can be read directly, use request_frame_capture")?;
+        readback::read_texture_to_image(texture, &self.device, &self.queue)
+    }
+
+    /// Reads a GPU buffer with the `COPY_SRC` usage back, e.g. the results of a compute object.
+    pub fn read_buffer(&self, buffer: &wgpu::Buffer) -> Result<Vec<u8>> {
+        readback::read_buffer(buffer, &self.device, &self.queue)
+    }
+
+    /// Reads the offscreen render target back and writes it to a PNG file.
+    pub fn save_frame_png(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.read_frame()?
+            .save_with_format(path, image::ImageFormat::Png)?;
+        Ok(())
+    }
+
+    /// Copies the next rendered frame into an image before it is presented.
+    /// The image can be retrieved with `take_captured_frame`.
+    pub fn request_frame_capture(&mut self) {
+        self.capture_next_frame = true;
+    }
+
+    pub fn take_captured_frame(&mut self) -> Option<image::RgbaImage> {
+        self.captured_frame.take()
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+fn create_instance(default_backends: wgpu::Backends) -> wgpu::Instance {
+    wgpu::Instance::new(wgpu::InstanceDescriptor {
+        #[cfg(not(target_arch = "wasm32"))]
+        backends: wgpu::util::backend_bits_from_env()
+            .unwrap_or(default_backends),
+        #[cfg(target_arch = "wasm32")]
+        // NOTE: WebGPU is supported, but does not yet work in release version of Firefox
+        backends: wgpu::Backends::BROWSER_WEBGPU,
+        ..Default::default()
+    })
+}
+
+async fn request_device(adapter: &wgpu::Adapter) -> Result<(wgpu::Device, wgpu::Queue)> {
+    adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                // Adapter specific format features allow sample counts other than 1 and 4
+                required_features: wgpu::Features::PUSH_CONSTANTS
+                    | (adapter.features() & wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES),
+                required_limits: if cfg!(target_arch = "wasm32") {
+                    wgpu::Limits {
+                        max_texture_dimension_1d: 8192,
+                        max_texture_dimension_2d: 8192,
+                        max_texture_dimension_3d: 2048,
+                        max_texture_array_layers: 256,
+                        max_bind_groups: 4,
+                        max_buffer_size: 256 * 1024 * 1024, // 256 MB
+                        max_vertex_buffers: 8,
+                        max_vertex_attributes: 16,
+                        max_vertex_buffer_array_stride: 2048,
+                        max_push_constant_size: 128, // Typical browser support
+                        max_storage_buffer_binding_size: 128 * 1024 * 1024, // 128 MB
+                        ..Default::default()
+                    }
+                } else {
+                    wgpu::Limits {
+                        max_push_constant_size: size_of::<ShaderPushConstants>() as u32,
+                        ..Default::default()
+                    }
+                },
+                label: None,
+                memory_hints: Default::default(),
+            },
+            None,
+        )
+        .await
+        .map_err(|e| eyre!(e.to_string()))
+}
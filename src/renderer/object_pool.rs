@@ -0,0 +1,189 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+
+/// Typed handle to an object stored in an `ObjectPool`.
+/// The generation makes handles to removed objects stale, even if their slot gets reused.
+pub struct ObjectId<T> {
+    index: u32,
+    generation: u32,
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> ObjectId<T> {
+    fn new(index: u32, generation: u32) -> Self {
+        Self {
+            index,
+            generation,
+            phantom: PhantomData,
+        }
+    }
+}
+
+// Implemented manually since deriving would require `T` to implement these traits as well
+impl<T> Clone for ObjectId<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ObjectId<T> {}
+
+impl<T> PartialEq for ObjectId<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index && self.generation == other.generation
+    }
+}
+
+impl<T> Eq for ObjectId<T> {}
+
+impl<T> Hash for ObjectId<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+        self.generation.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for ObjectId<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ObjectId({}v{})", self.index, self.generation)
+    }
+}
+
+struct Slot<T> {
+    generation: u32,
+    entry: Option<Entry<T>>,
+}
+
+struct Entry<T> {
+    object: T,
+    visible: bool,
+}
+
+/// Storage for scene objects that hands out generation-checked handles.
+/// Iteration follows insertion order regardless of slot reuse.
+pub struct ObjectPool<T> {
+    slots: Vec<Slot<T>>,
+    free_indices: Vec<u32>,
+    order: Vec<u32>,
+}
+
+impl<T> ObjectPool<T> {
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free_indices: Vec::new(),
+            order: Vec::new(),
+        }
+    }
+
+    pub fn insert(&mut self, object: T) -> ObjectId<T> {
+        let entry = Some(Entry {
+            object,
+            visible: true,
+        });
+        let index = match self.free_indices.pop() {
+            Some(index) => {
+                self.slots[index as usize].entry = entry;
+                index
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    entry,
+                });
+                (self.slots.len() - 1) as u32
+            }
+        };
+        self.order.push(index);
+        ObjectId::new(index, self.slots[index as usize].generation)
+    }
+
+    pub fn remove(&mut self, id: ObjectId<T>) -> Option<T> {
+        let slot = self.slots.get_mut(id.index as usize)?;
+        if slot.generation != id.generation {
+            return None;
+        }
+        let entry = slot.entry.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free_indices.push(id.index);
+        self.order.retain(|&index| index != id.index);
+        Some(entry.object)
+    }
+
+    pub fn get(&self, id: ObjectId<T>) -> Option<&T> {
+        self.get_entry(id).map(|entry| &entry.object)
+    }
+
+    pub fn get_mut(&mut self, id: ObjectId<T>) -> Option<&mut T> {
+        self.get_entry_mut(id).map(|entry| &mut entry.object)
+    }
+
+    pub fn is_visible(&self, id: ObjectId<T>) -> Option<bool> {
+        self.get_entry(id).map(|entry| entry.visible)
+    }
+
+    /// Returns `false` if the handle is stale.
+    pub fn set_visible(&mut self, id: ObjectId<T>, visible: bool) -> bool {
+        match self.get_entry_mut(id) {
+            Some(entry) => {
+                entry.visible = visible;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Iterates over all objects in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = (ObjectId<T>, &T)> {
+        self.iter_entries().map(|(id, entry)| (id, &entry.object))
+    }
+
+    /// Iterates over visible objects in insertion order.
+    pub fn iter_visible(&self) -> impl Iterator<Item = (ObjectId<T>, &T)> {
+        self.iter_entries()
+            .filter(|(_, entry)| entry.visible)
+            .map(|(id, entry)| (id, &entry.object))
+    }
+
+    /// Iterates mutably over all objects. The order is unspecified.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (ObjectId<T>, &mut T)> {
+        self.slots
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, slot)| {
+                let generation = slot.generation;
+                slot.entry
+                    .as_mut()
+                    .map(|entry| (ObjectId::new(index as u32, generation), &mut entry.object))
+            })
+    }
+
+    fn iter_entries(&self) -> impl Iterator<Item = (ObjectId<T>, &Entry<T>)> {
+        self.order.iter().map(|&index| {
+            let slot = &self.slots[index as usize];
+            let entry = slot.entry.as_ref().expect("Ordered slot must be occupied");
+            (ObjectId::new(index, slot.generation), entry)
+        })
+    }
+
+    fn get_entry(&self, id: ObjectId<T>) -> Option<&Entry<T>> {
+        self.slots
+            .get(id.index as usize)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.entry.as_ref())
+    }
+
+    fn get_entry_mut(&mut self, id: ObjectId<T>) -> Option<&mut Entry<T>> {
+        self.slots
+            .get_mut(id.index as usize)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.entry.as_mut())
+    }
+}
+
+impl<T> Default for ObjectPool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,339 @@
+use color_eyre::eyre::{eyre, Result};
+use glam::{Vec3, Vec4};
+use crate::renderer::Camera;
+use crate::renderer::draw_list::DrawState;
+use crate::renderer::resources::{Resources, PARTICLE_EMITTER_BIND_GROUP_LAYOUT_NAME, PARTICLE_SIMULATION_BIND_GROUP_LAYOUT_NAME};
+use crate::renderer::resources::shader_data::{ShaderParticle, ShaderParticleEmitterUniform, ShaderPushConstants};
+
+/* GPU particles.
+ * A particle system keeps its particles in a storage buffer. Every frame the compute pass
+ * spawns and simulates them with the particle simulation material, then the scene pass draws
+ * every slot of the same buffer as an instance of a camera-facing quad, dead particles included,
+ * which the vertex shader discards. See `shaders/particle_simulation.wgsl` and `shaders/particles.wgsl`.
+ * Particle systems are drawn after the transparent render objects, their particles are not sorted. */
+
+/// Alpha blended particles
+pub const PARTICLES_MATERIAL_NAME: &str = "particles";
+/// Additively blended particles, for fire and sparks
+pub const ADDITIVE_PARTICLES_MATERIAL_NAME: &str = "particles additive";
+pub const PARTICLE_SIMULATION_MATERIAL_NAME: &str = "particle simulation";
+
+/// The volume particles spawn in, centered on the emitter's position
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmitterShape {
+    Point,
+    Sphere {
+        radius: f32,
+    },
+    Box {
+        half_extents: Vec3,
+    },
+}
+
+/// Settings of a particle system, in world space
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParticleEmitter {
+    max_particles: u32,
+    material_name: String,
+    shape: EmitterShape,
+    position: Vec3,
+    rate: f32,
+    min_lifetime: f32,
+    max_lifetime: f32,
+    velocity: Vec3,
+    velocity_spread: f32,
+    force: Vec3,
+    drag: f32,
+    // Height and bounciness of the ground plane particles collide with
+    ground: Option<(f32, f32)>,
+    start_color: Vec4,
+    end_color: Vec4,
+    start_size: f32,
+    end_size: f32,
+}
+
+impl ParticleEmitter {
+    /// An emitter of up to `max_particles` alive particles, spawning white particles
+    /// at the origin that rise and fade out.
+    /// Once all particles are alive, new ones replace the oldest,
+    /// so `max_particles` should cover the rate times the maximum lifetime.
+    pub fn new(max_particles: u32) -> Self {
+        Self {
+            max_particles,
+            material_name: PARTICLES_MATERIAL_NAME.to_owned(),
+            shape: EmitterShape::Point,
+            position: Vec3::ZERO,
+            rate: 10.0,
+            min_lifetime: 1.0,
+            max_lifetime: 2.0,
+            velocity: Vec3::Y,
+            velocity_spread: 0.5,
+            force: Vec3::ZERO,
+            drag: 0.0,
+            ground: None,
+            start_color: Vec4::ONE,
+            end_color: Vec4::new(1.0, 1.0, 1.0, 0.0),
+            start_size: 0.1,
+            end_size: 0.1,
+        }
+    }
+
+    /// The render material drawing the particles, `PARTICLES_MATERIAL_NAME` by default
+    pub fn with_material(mut self, material_name: &str) -> Self {
+        self.material_name = material_name.to_owned();
+        self
+    }
+
+    pub fn with_shape(mut self, shape: EmitterShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    pub fn with_position(mut self, position: Vec3) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Particles spawned per second
+    pub fn with_rate(mut self, rate: f32) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    /// Particles live for a random number of seconds between `min` and `max`
+    pub fn with_lifetime(mut self, min: f32, max: f32) -> Self {
+        self.min_lifetime = min;
+        self.max_lifetime = max;
+        self
+    }
+
+    /// Particles start with `velocity` plus a random velocity of up to `spread` in any direction
+    pub fn with_velocity(mut self, velocity: Vec3, spread: f32) -> Self {
+        self.velocity = velocity;
+        self.velocity_spread = spread;
+        self
+    }
+
+    /// Constant acceleration, e.g. gravity
+    pub fn with_force(mut self, force: Vec3) -> Self {
+        self.force = force;
+        self
+    }
+
+    /// Fraction of the velocity lost per second
+    pub fn with_drag(mut self, drag: f32) -> Self {
+        self.drag = drag;
+        self
+    }
+
+    /// Makes particles bounce off the horizontal plane at `height`,
+    /// keeping `bounciness` of their vertical speed.
+    pub fn with_ground(mut self, height: f32, bounciness: f32) -> Self {
+        self.ground = Some((height, bounciness.max(0.0)));
+        self
+    }
+
+    /// Linear colors with alpha, interpolated over the particles' lifetime
+    pub fn with_color_over_life(mut self, start: Vec4, end: Vec4) -> Self {
+        self.start_color = start;
+        self.end_color = end;
+        self
+    }
+
+    /// World space widths, interpolated over the particles' lifetime
+    pub fn with_size_over_life(mut self, start: f32, end: f32) -> Self {
+        self.start_size = start;
+        self.end_size = end;
+        self
+    }
+
+    pub fn get_max_particles(&self) -> u32 {
+        self.max_particles
+    }
+
+    pub fn get_material_name(&self) -> &str {
+        &self.material_name
+    }
+
+    pub fn get_shape(&self) -> EmitterShape {
+        self.shape
+    }
+
+    pub fn get_position(&self) -> Vec3 {
+        self.position
+    }
+
+    pub fn get_rate(&self) -> f32 {
+        self.rate
+    }
+
+    /// Height and bounciness of the ground plane, if particles collide with one
+    pub fn get_ground(&self) -> Option<(f32, f32)> {
+        self.ground
+    }
+
+    fn get_uniform(&self, camera: &Camera) -> ShaderParticleEmitterUniform {
+        let (shape, shape_size) = match self.shape {
+            EmitterShape::Point => (0, Vec3::ZERO),
+            EmitterShape::Sphere { radius } => (1, Vec3::splat(radius)),
+            EmitterShape::Box { half_extents } => (2, half_extents),
+        };
+        let (ground_height, bounciness) = self.ground.unwrap_or((0.0, -1.0));
+        ShaderParticleEmitterUniform {
+            start_color: self.start_color,
+            end_color: self.end_color,
+            position: self.position,
+            shape,
+            shape_size,
+            rate: self.rate,
+            velocity: self.velocity,
+            velocity_spread: self.velocity_spread,
+            force: self.force,
+            drag: self.drag,
+            camera_right: camera.get_right(),
+            start_size: self.start_size,
+            camera_up: camera.get_up(),
+            end_size: self.end_size,
+            min_lifetime: self.min_lifetime,
+            max_lifetime: self.max_lifetime,
+            ground_height,
+            bounciness,
+            max_particles: self.max_particles,
+            _padding: [0; 3],
+        }
+    }
+}
+
+/// Particles simulated on the GPU, added to a scene with `Scene::add_particle_system`
+pub struct ParticleSystem {
+    emitter: ParticleEmitter,
+    particle_buffer: wgpu::Buffer,
+    emitter_buffer: wgpu::Buffer,
+    simulation_bind_group: wgpu::BindGroup,
+    emitter_bind_group: wgpu::BindGroup,
+}
+
+impl ParticleSystem {
+    pub fn new(emitter: ParticleEmitter, device: &wgpu::Device, resources: &Resources) -> Result<Self> {
+        if emitter.max_particles == 0 {
+            return Err(eyre!("Particle systems need room for at least one particle"));
+        }
+        resources.get_render_material(&emitter.material_name)?;
+        resources.get_compute_material(PARTICLE_SIMULATION_MATERIAL_NAME)?;
+
+        // All slots start out dead, with an age and lifetime of 0
+        let particle_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Particle Buffer"),
+            size: (emitter.max_particles as usize * size_of::<ShaderParticle>()) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::VERTEX
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let emitter_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Particle Emitter Buffer"),
+            size: size_of::<ShaderParticleEmitterUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let simulation_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Particle Simulation Bind Group"),
+            layout: resources.get_bind_group_layout(PARTICLE_SIMULATION_BIND_GROUP_LAYOUT_NAME)?,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: particle_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: emitter_buffer.as_entire_binding(),
+                },
+            ],
+        });
+        let emitter_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Particle Emitter Bind Group"),
+            layout: resources.get_bind_group_layout(PARTICLE_EMITTER_BIND_GROUP_LAYOUT_NAME)?,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: emitter_buffer.as_entire_binding(),
+            }],
+        });
+
+        Ok(Self {
+            emitter,
+            particle_buffer,
+            emitter_buffer,
+            simulation_bind_group,
+            emitter_bind_group,
+        })
+    }
+
+    pub fn get_emitter(&self) -> &ParticleEmitter {
+        &self.emitter
+    }
+
+    /// Changes the emitter settings, alive particles keep their state.
+    /// The maximum number of particles is fixed, create a new system to change it.
+    pub fn set_emitter(&mut self, emitter: ParticleEmitter) -> Result<()> {
+        if emitter.max_particles != self.emitter.max_particles {
+            return Err(eyre!(
+                "Cannot change the maximum number of particles from {} to {}",
+                self.emitter.max_particles,
+                emitter.max_particles,
+            ));
+        }
+        self.emitter = emitter;
+        Ok(())
+    }
+
+    /// The `ShaderParticle`s of all slots, usable as a storage or vertex buffer
+    pub fn get_particle_buffer(&self) -> &wgpu::Buffer {
+        &self.particle_buffer
+    }
+
+    /// Uploads the emitter settings and spawns and simulates the particles for this frame.
+    pub fn dispatch(
+        &self,
+        compute_pass: &mut wgpu::ComputePass,
+        camera: &Camera,
+        resources: &Resources,
+        frame_bind_group: &wgpu::BindGroup,
+        queue: &wgpu::Queue,
+    ) -> Result<()> {
+        let material = resources.get_compute_material(PARTICLE_SIMULATION_MATERIAL_NAME)?;
+        queue.write_buffer(&self.emitter_buffer, 0, bytemuck::bytes_of(&self.emitter.get_uniform(camera)));
+
+        compute_pass.set_pipeline(material.get_pipeline());
+        compute_pass.insert_debug_marker(PARTICLE_SIMULATION_MATERIAL_NAME);
+        compute_pass.set_bind_group(0, &self.simulation_bind_group, &[]);
+        if let Some(index) = material.get_frame_bind_group_index() {
+            compute_pass.set_bind_group(index, frame_bind_group, &[]);
+        }
+        let workgroup_size = material.get_workgroup_size()[0];
+        compute_pass.dispatch_workgroups(self.emitter.max_particles.div_ceil(workgroup_size), 1, 1);
+        Ok(())
+    }
+
+    pub(crate) fn draw(
+        &self,
+        state: &mut DrawState,
+        resources: &Resources,
+        camera_bind_group: &wgpu::BindGroup,
+        frame_bind_group: &wgpu::BindGroup,
+        push_constants: &ShaderPushConstants,
+    ) -> Result<()> {
+        let material = resources.get_render_material(&self.emitter.material_name)?;
+
+        state.set_pipeline(material.get_pipeline());
+        state.set_push_constants(push_constants);
+        state.set_bind_group(0, &self.emitter_bind_group);
+        state.set_bind_group(1, camera_bind_group);
+        if let Some(index) = material.get_frame_bind_group_index() {
+            state.set_bind_group(index, frame_bind_group);
+        }
+        state.set_vertex_buffer(0, &self.particle_buffer);
+        state.draw(0..6, 0..self.emitter.max_particles);
+        Ok(())
+    }
+}
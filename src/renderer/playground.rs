@@ -0,0 +1,445 @@
+use color_eyre::eyre::{eyre, OptionExt, Result};
+use glam::{Vec2, Vec4};
+use crate::renderer::Renderer;
+use crate::renderer::resources::Resources;
+use crate::renderer::resources::shader::Shader;
+use crate::renderer::resources::shader_data::ShaderPlaygroundUniform;
+use crate::renderer::uploader::Uploader;
+use crate::renderer::viewport::OutputTransform;
+
+/* Shadertoy-style fragment shader playground.
+ * Every pass runs a user WGSL function `fn main_image(frag_coord: vec2<f32>) -> vec4<f32>`
+ * over the whole viewport, with `frag_coord` in pixels from the top left.
+ * The prelude in `playground_prelude.wgsl` provides the `playground` uniform
+ * and the textures `channel0` to `channel3` with `channel_sampler`.
+ * Buffer passes render into textures that the following frames can sample,
+ * the image pass renders to the viewport.
+ * Passes are built from source strings rather than files, so shader hot reloading
+ * does not apply to them: build a new playground to change a pass. */
+
+const PRELUDE: &str = include_str!("playground_prelude.wgsl");
+const MAX_CHANNELS: usize = 4;
+const BUFFER_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+/// Input of a playground pass, bound to `channel0` to `channel3` in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlaygroundChannel {
+    /// Latest output of a buffer pass: this frame's if the buffer pass runs earlier,
+    /// otherwise the previous frame's, which lets passes feed back into themselves
+    Buffer(String),
+    /// A texture registered in `Resources`
+    Texture(String),
+}
+
+pub struct Playground {
+    buffer_passes: Vec<PlaygroundPass>,
+    image_pass: PlaygroundPass,
+    uniform: ShaderPlaygroundUniform,
+    uniform_buffer: wgpu::Buffer,
+    uniform_bind_group: wgpu::BindGroup,
+    channel_bind_group_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    // Bound to unused channels
+    empty_channel: wgpu::TextureView,
+}
+
+struct PlaygroundPass {
+    name: String,
+    pipeline: wgpu::RenderPipeline,
+    channels: Vec<PlaygroundChannel>,
+    // Buffer passes render into one texture while the other holds their latest output
+    targets: Vec<wgpu::Texture>,
+    latest_target: usize,
+}
+
+impl Playground {
+    pub fn builder() -> PlaygroundBuilder {
+        PlaygroundBuilder::new()
+    }
+
+    /// Advances the time by `delta_time` seconds.
+    pub fn advance(&mut self, delta_time: f32) {
+        self.uniform.time += delta_time;
+        self.uniform.delta_time = delta_time;
+    }
+
+    pub fn get_time(&self) -> f32 {
+        self.uniform.time
+    }
+
+    pub fn set_time(&mut self, time: f32) {
+        self.uniform.time = time;
+    }
+
+    /// Number of frames rendered since creation or the last reset
+    pub fn get_frame(&self) -> u32 {
+        self.uniform.frame
+    }
+
+    /// `position` is in pixels from the top left of the viewport.
+    pub fn set_mouse(&mut self, position: Vec2, left_down: bool, right_down: bool) {
+        self.uniform.mouse = Vec4::new(
+            position.x,
+            position.y,
+            if left_down { 1.0 } else { 0.0 },
+            if right_down { 1.0 } else { 0.0 },
+        );
+    }
+
+    /// Restarts time and frame count at zero and clears all buffers.
+    pub fn reset(&mut self) {
+        self.uniform.time = 0.0;
+        self.uniform.delta_time = 0.0;
+        self.uniform.frame = 0;
+        for pass in &mut self.buffer_passes {
+            pass.targets.clear();
+        }
+    }
+
+    /// Records all passes, rendering the image pass into `output`.
+    /// Buffers are (re)created at the size of `output` when needed.
+    pub fn encode(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        output: &wgpu::Texture,
+        resources: &Resources,
+        uploader: &mut Uploader,
+        device: &wgpu::Device,
+    ) -> Result<()> {
+        let size = wgpu::Extent3d {
+            width: output.width(),
+            height: output.height(),
+            depth_or_array_layers: 1,
+        };
+        for pass in &mut self.buffer_passes {
+            if pass.targets.first().map(|target| target.size()) != Some(size) {
+                pass.targets = (0..2)
+                    .map(|_| create_buffer_texture(&pass.name, size, device))
+                    .collect();
+                pass.latest_target = 0;
+            }
+        }
+
+        self.uniform.resolution = Vec2::new(size.width as f32, size.height as f32);
+        uploader.upload(encoder, &self.uniform_buffer, 0, bytemuck::bytes_of(&self.uniform), device);
+
+        for index in 0..self.buffer_passes.len() {
+            let channel_bind_group = self.create_channel_bind_group(
+                &self.buffer_passes[index],
+                resources,
+                device,
+            )?;
+            let pass = &self.buffer_passes[index];
+            let target = pass.targets[1 - pass.latest_target]
+                .create_view(&wgpu::TextureViewDescriptor::default());
+            self.encode_pass(encoder, pass, &target, &channel_bind_group);
+            self.buffer_passes[index].latest_target ^= 1;
+        }
+
+        let channel_bind_group = self.create_channel_bind_group(&self.image_pass, resources, device)?;
+        let target = output.create_view(&wgpu::TextureViewDescriptor::default());
+        self.encode_pass(encoder, &self.image_pass, &target, &channel_bind_group);
+
+        self.uniform.frame += 1;
+        Ok(())
+    }
+
+    fn encode_pass(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        pass: &PlaygroundPass,
+        target: &wgpu::TextureView,
+        channel_bind_group: &wgpu::BindGroup,
+    ) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some(&format!("Playground {} Pass", pass.name)),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+        render_pass.set_pipeline(&pass.pipeline);
+        render_pass.set_bind_group(0, &self.uniform_bind_group, &[]);
+        render_pass.set_bind_group(1, channel_bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    fn create_channel_bind_group(
+        &self,
+        pass: &PlaygroundPass,
+        resources: &Resources,
+        device: &wgpu::Device,
+    ) -> Result<wgpu::BindGroup> {
+        let mut buffer_views = Vec::new();
+        for channel in &pass.channels {
+            if let PlaygroundChannel::Buffer(name) = channel {
+                let buffer_pass = self.buffer_passes
+                    .iter()
+                    .find(|buffer_pass| buffer_pass.name == *name)
+                    .ok_or_eyre(format!("Playground buffer not found: {name}"))?;
+                buffer_views.push(buffer_pass.targets[buffer_pass.latest_target]
+                    .create_view(&wgpu::TextureViewDescriptor::default()));
+            }
+        }
+
+        let mut buffer_views = buffer_views.iter();
+        let mut views = Vec::with_capacity(MAX_CHANNELS);
+        for channel in &pass.channels {
+            views.push(match channel {
+                PlaygroundChannel::Buffer(_) => buffer_views.next().expect("Buffer view per buffer channel"),
+                PlaygroundChannel::Texture(name) => resources.get_texture_or_placeholder(name)?.get_view(),
+            });
+        }
+        views.resize(MAX_CHANNELS, &self.empty_channel);
+
+        let mut entries = views
+            .iter()
+            .enumerate()
+            .map(|(binding, view)| wgpu::BindGroupEntry {
+                binding: binding as u32,
+                resource: wgpu::BindingResource::TextureView(view),
+            })
+            .collect::<Vec<_>>();
+        entries.push(wgpu::BindGroupEntry {
+            binding: MAX_CHANNELS as u32,
+            resource: wgpu::BindingResource::Sampler(&self.sampler),
+        });
+
+        Ok(device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some(&format!("Playground {} Channel Bind Group", pass.name)),
+            layout: &self.channel_bind_group_layout,
+            entries: &entries,
+        }))
+    }
+}
+
+pub struct PlaygroundBuilder {
+    buffer_passes: Vec<(String, String, Vec<PlaygroundChannel>)>,
+    image_pass: Option<(String, Vec<PlaygroundChannel>)>,
+}
+
+impl PlaygroundBuilder {
+    fn new() -> Self {
+        Self {
+            buffer_passes: Vec::new(),
+            image_pass: None,
+        }
+    }
+
+    /// Adds a buffer pass named `name`. Buffer passes run in the order they are added.
+    pub fn with_buffer_pass(mut self, name: &str, source: &str, channels: &[PlaygroundChannel]) -> Self {
+        self.buffer_passes.push((name.to_owned(), source.to_owned(), channels.into()));
+        self
+    }
+
+    /// Sets the pass that renders to the viewport, after all buffer passes.
+    pub fn with_image_pass(mut self, source: &str, channels: &[PlaygroundChannel]) -> Self {
+        self.image_pass = Some((source.to_owned(), channels.into()));
+        self
+    }
+
+    pub fn build(self, renderer: &Renderer) -> Result<Playground> {
+        let device = renderer.get_device();
+        let (image_source, image_channels) = self.image_pass.ok_or_eyre("No image pass provided")?;
+
+        let passes = self.buffer_passes
+            .iter()
+            .map(|(name, _, channels)| (name.as_str(), channels))
+            .chain(std::iter::once(("image", &image_channels)));
+        for (name, channels) in passes {
+            if channels.len() > MAX_CHANNELS {
+                return Err(eyre!("Playground pass {name} has more than {MAX_CHANNELS} channels"));
+            }
+            for channel in channels {
+                if let PlaygroundChannel::Buffer(buffer) = channel {
+                    if !self.buffer_passes.iter().any(|(name, _, _)| name == buffer) {
+                        return Err(eyre!("Playground pass {name} reads unknown buffer {buffer}"));
+                    }
+                }
+            }
+        }
+
+        let uniform_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Playground Uniform Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let mut channel_entries = (0..MAX_CHANNELS as u32)
+            .map(|binding| wgpu::BindGroupLayoutEntry {
+                binding,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                },
+                count: None,
+            })
+            .collect::<Vec<_>>();
+        channel_entries.push(wgpu::BindGroupLayoutEntry {
+            binding: MAX_CHANNELS as u32,
+            visibility: wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+            count: None,
+        });
+        let channel_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Playground Channel Bind Group Layout"),
+            entries: &channel_entries,
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Playground Pipeline Layout"),
+            bind_group_layouts: &[&uniform_bind_group_layout, &channel_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let buffer_passes = self.buffer_passes
+            .iter()
+            .map(|(name, source, channels)| {
+                let pipeline = create_pipeline(
+                    name, source, BUFFER_FORMAT, "playground_fs_main", &pipeline_layout, device,
+                )?;
+                Ok(PlaygroundPass {
+                    name: name.clone(),
+                    pipeline,
+                    channels: channels.clone(),
+                    targets: Vec::new(),
+                    latest_target: 0,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let output_format = *renderer.get_viewport().get_surface_format();
+        let fragment_entry_point = match renderer.get_viewport().get_output_transform() {
+            OutputTransform::SrgbFormat => "playground_fs_main",
+            OutputTransform::SrgbShader => "playground_fs_main_srgb",
+        };
+        let image_pass = PlaygroundPass {
+            name: "image".to_owned(),
+            pipeline: create_pipeline(
+                "image", &image_source, output_format, fragment_entry_point, &pipeline_layout, device,
+            )?,
+            channels: image_channels,
+            targets: Vec::new(),
+            latest_target: 0,
+        };
+
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Playground Uniform Buffer"),
+            size: size_of::<ShaderPlaygroundUniform>() as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let uniform_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Playground Uniform Bind Group"),
+            layout: &uniform_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Playground Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+        let empty_channel = create_buffer_texture(
+            "empty channel",
+            wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            device,
+        ).create_view(&wgpu::TextureViewDescriptor::default());
+
+        Ok(Playground {
+            buffer_passes,
+            image_pass,
+            uniform: ShaderPlaygroundUniform::default(),
+            uniform_buffer,
+            uniform_bind_group,
+            channel_bind_group_layout,
+            sampler,
+            empty_channel,
+        })
+    }
+}
+
+fn create_pipeline(
+    name: &str,
+    source: &str,
+    format: wgpu::TextureFormat,
+    fragment_entry_point: &str,
+    pipeline_layout: &wgpu::PipelineLayout,
+    device: &wgpu::Device,
+) -> Result<wgpu::RenderPipeline> {
+    let label = format!("playground {name}");
+    let source = format!("{PRELUDE}\n{source}");
+    // Validated up front where naga is available, since the device treats invalid shaders as fatal
+    #[cfg(not(target_arch = "wasm32"))]
+    let shader = Shader::new_from_wgsl(&source, &label, device)?;
+    #[cfg(target_arch = "wasm32")]
+    let shader = Shader::new_from_descriptor(wgpu::ShaderModuleDescriptor {
+        label: Some(&label),
+        source: wgpu::ShaderSource::Wgsl(source.as_str().into()),
+    }, device);
+
+    Ok(device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(&format!("Playground {name} Pipeline")),
+        layout: Some(pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: shader.get_module(),
+            entry_point: Some("playground_vs_main"),
+            buffers: &[],
+            compilation_options: Default::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader.get_module(),
+            entry_point: Some(fragment_entry_point),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: None,
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: Default::default(),
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    }))
+}
+
+fn create_buffer_texture(name: &str, size: wgpu::Extent3d, device: &wgpu::Device) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(&format!("Playground {name} Buffer")),
+        size,
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: BUFFER_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    })
+}
@@ -0,0 +1,52 @@
+// Prepended to the source of every playground pass.
+// The pass source must define `fn main_image(frag_coord: vec2<f32>) -> vec4<f32>`.
+
+struct PlaygroundUniform {
+    resolution: vec2<f32>,
+    time: f32,
+    delta_time: f32,
+    // xy: cursor position in pixels, z: left button down, w: right button down
+    mouse: vec4<f32>,
+    frame: u32,
+}
+
+struct PlaygroundVertexOutput {
+    @builtin(position) position: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> playground: PlaygroundUniform;
+
+@group(1) @binding(0)
+var channel0: texture_2d<f32>;
+@group(1) @binding(1)
+var channel1: texture_2d<f32>;
+@group(1) @binding(2)
+var channel2: texture_2d<f32>;
+@group(1) @binding(3)
+var channel3: texture_2d<f32>;
+@group(1) @binding(4)
+var channel_sampler: sampler;
+
+@vertex
+fn playground_vs_main(@builtin(vertex_index) index: u32) -> PlaygroundVertexOutput {
+    // A single triangle covering the whole viewport
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: PlaygroundVertexOutput;
+    out.position = vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+    return out;
+}
+
+@fragment
+fn playground_fs_main(in: PlaygroundVertexOutput) -> @location(0) vec4<f32> {
+    return main_image(in.position.xy);
+}
+
+// Used to output to non-sRGB surfaces, which expect sRGB-encoded colors
+@fragment
+fn playground_fs_main_srgb(in: PlaygroundVertexOutput) -> @location(0) vec4<f32> {
+    let color = clamp(main_image(in.position.xy), vec4<f32>(0.0), vec4<f32>(1.0));
+    let low = color.rgb * 12.92;
+    let high = 1.055 * pow(color.rgb, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return vec4<f32>(select(high, low, color.rgb <= vec3<f32>(0.0031308)), color.a);
+}
@@ -0,0 +1,440 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use color_eyre::eyre::{eyre, OptionExt, Result};
+use wgpu::util::DeviceExt;
+use crate::renderer::Renderer;
+use crate::renderer::render_graph::{PassContext, OUTPUT, POST_PROCESS_TARGETS, SCENE_COLOR};
+use crate::renderer::resources::{Resources, PLACEHOLDER_TEXTURE_NAME, POST_EFFECT_BIND_GROUP_LAYOUT_NAME, POST_PROCESS_INPUT_BIND_GROUP_LAYOUT_NAME};
+use crate::renderer::resources::texture::Texture;
+use crate::renderer::resources::shader_data::{ShaderPostEffectUniform, ShaderPushConstants};
+use crate::renderer::viewport::OutputTransform;
+
+/* Post-processing stack.
+ * The scene is rendered into the HDR `SCENE_COLOR` target, then every enabled effect draws
+ * the fullscreen quad with its render material, reading the previous result and writing into
+ * the other of two ping-pong targets. A final pass clamps the result and writes it to the viewport,
+ * encoding it to sRGB according to the viewport's `OutputTransform`.
+ * Effect materials are built with the post-process input, post effect and frame bind group
+ * layouts, see `shaders/post_process.wgsl`. */
+
+pub const OUTPUT_MATERIAL_NAME: &str = "post output";
+pub const COMPUTE_BLIT_MATERIAL_NAME: &str = "post compute blit";
+pub const TONEMAP_MATERIAL_NAME: &str = "post tonemap";
+pub const GAMMA_MATERIAL_NAME: &str = "post gamma";
+pub const VIGNETTE_MATERIAL_NAME: &str = "post vignette";
+pub const COLOR_GRADING_MATERIAL_NAME: &str = "post color grading";
+pub const FXAA_MATERIAL_NAME: &str = "post fxaa";
+pub const BLOOM_MATERIAL_NAME: &str = "post bloom";
+
+/// Curve mapping HDR colors into the displayable range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tonemapper {
+    /// Only applies the exposure, the output pass clips colors above 1
+    None,
+    Reinhard,
+    /// Stephen Hill's fit of the ACES reference rendering and output transforms
+    AcesFitted,
+    /// Approximation of Troy Sobotka's AgX
+    AgX,
+}
+
+impl Tonemapper {
+    /// The value of the tonemap effect's second parameter selecting this tonemapper
+    pub fn get_parameter(self) -> f32 {
+        match self {
+            Self::None => 0.0,
+            Self::Reinhard => 1.0,
+            Self::AcesFitted => 2.0,
+            Self::AgX => 3.0,
+        }
+    }
+}
+
+/// A fullscreen effect of the post-processing stack
+pub struct PostEffect {
+    name: String,
+    material_name: String,
+    aux_texture_name: String,
+    uniform: ShaderPostEffectUniform,
+    uniform_buffer: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    enabled: bool,
+    // Whether the parameters changed since they were uploaded
+    dirty: bool,
+}
+
+impl PostEffect {
+    pub const MAX_PARAMETERS: usize = 8;
+
+    /// An effect drawing the render material `material_name` with up to 8 parameters.
+    pub fn new(
+        name: &str,
+        material_name: &str,
+        parameters: &[f32],
+        renderer: &Renderer,
+    ) -> Result<Self> {
+        if parameters.len() > Self::MAX_PARAMETERS {
+            return Err(eyre!("Post effects have at most {} parameters", Self::MAX_PARAMETERS));
+        }
+        let resources = renderer.get_resources().try_borrow()?;
+        resources.get_render_material(material_name)?;
+
+        let mut uniform = ShaderPostEffectUniform::default();
+        uniform.parameters[..parameters.len()].copy_from_slice(parameters);
+        let (uniform_buffer, bind_group) = create_parameters_binding(&uniform, renderer.get_device(), &resources)?;
+
+        Ok(Self {
+            name: name.to_owned(),
+            material_name: material_name.to_owned(),
+            aux_texture_name: PLACEHOLDER_TEXTURE_NAME.to_owned(),
+            uniform,
+            uniform_buffer,
+            bind_group,
+            enabled: true,
+            dirty: false,
+        })
+    }
+
+    /// Scales HDR colors by `exposure`, then maps them with `tonemapper`.
+    /// Parameters: exposure, `Tonemapper::get_parameter`.
+    pub fn new_tonemap(tonemapper: Tonemapper, exposure: f32, renderer: &Renderer) -> Result<Self> {
+        Self::new("tonemap", TONEMAP_MATERIAL_NAME, &[exposure, tonemapper.get_parameter()], renderer)
+    }
+
+    /// Raises colors to the power of `1 / gamma`
+    pub fn new_gamma(gamma: f32, renderer: &Renderer) -> Result<Self> {
+        Self::new("gamma", GAMMA_MATERIAL_NAME, &[gamma], renderer)
+    }
+
+    /// Darkens the corners. `radius` and `smoothness` are relative to the distance
+    /// from the center to the corners.
+    pub fn new_vignette(intensity: f32, radius: f32, smoothness: f32, renderer: &Renderer) -> Result<Self> {
+        Self::new("vignette", VIGNETTE_MATERIAL_NAME, &[intensity, radius, smoothness], renderer)
+    }
+
+    /// Maps colors through the LUT texture `lut_texture_name`, a strip of N slices
+    /// of N by N texels with blue selecting the slice. Expects tonemapped input.
+    pub fn new_color_grading(lut_texture_name: &str, strength: f32, renderer: &Renderer) -> Result<Self> {
+        let lut_size = renderer.get_resources().try_borrow()?.get_texture(lut_texture_name)?.get_height();
+        Ok(Self::new("color grading", COLOR_GRADING_MATERIAL_NAME, &[lut_size as f32, strength], renderer)?
+            .with_aux_texture(lut_texture_name))
+    }
+
+    /// Fast approximate anti-aliasing. Expects tonemapped input.
+    pub fn new_fxaa(renderer: &Renderer) -> Result<Self> {
+        Self::new("fxaa", FXAA_MATERIAL_NAME, &[8.0, 1.0 / 8.0, 1.0 / 128.0], renderer)
+    }
+
+    /// Adds the blurred parts brighter than `threshold` to the image.
+    /// Belongs before tonemapping, where colors are still HDR.
+    pub fn new_bloom(threshold: f32, intensity: f32, radius: f32, renderer: &Renderer) -> Result<Self> {
+        Self::new("bloom", BLOOM_MATERIAL_NAME, &[threshold, intensity, radius], renderer)
+    }
+
+    /// Renames the effect, so the stack can hold several effects with the same material.
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_owned();
+        self
+    }
+
+    /// Texture bound next to the input, the placeholder texture by default
+    pub fn with_aux_texture(mut self, texture_name: &str) -> Self {
+        self.aux_texture_name = texture_name.to_owned();
+        self
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_material_name(&self) -> &str {
+        &self.material_name
+    }
+
+    pub fn get_aux_texture_name(&self) -> &str {
+        &self.aux_texture_name
+    }
+
+    pub fn get_parameter(&self, index: usize) -> Option<f32> {
+        self.uniform.parameters.get(index).copied()
+    }
+
+    pub fn set_parameter(&mut self, index: usize, value: f32) -> Result<()> {
+        let parameter = self.uniform.parameters
+            .get_mut(index)
+            .ok_or_eyre(format!("Post effects have at most {} parameters", Self::MAX_PARAMETERS))?;
+        *parameter = value;
+        self.dirty = true;
+        Ok(())
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Disabled effects are skipped.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+}
+
+/// The ordered effects applied to the scene before it is written to the viewport
+pub struct PostProcessStack {
+    effects: Vec<PostEffect>,
+    // Parameters of the output pass, also bound by the compute blit which has none
+    output_parameters: ShaderPostEffectUniform,
+    output_parameters_buffer: wgpu::Buffer,
+    output_parameters_bind_group: wgpu::BindGroup,
+    input_bind_groups: InputBindGroupCache,
+}
+
+impl PostProcessStack {
+    pub fn new(device: &wgpu::Device, resources: &Resources) -> Result<Self> {
+        let output_parameters = ShaderPostEffectUniform::default();
+        let (output_parameters_buffer, output_parameters_bind_group) =
+            create_parameters_binding(&output_parameters, device, resources)?;
+        Ok(Self {
+            effects: Vec::new(),
+            output_parameters,
+            output_parameters_buffer,
+            output_parameters_bind_group,
+            input_bind_groups: InputBindGroupCache::default(),
+        })
+    }
+
+    /// Adds an effect after the existing ones. Fails if an effect of the same name exists.
+    pub fn add_effect(&mut self, effect: PostEffect) -> Result<()> {
+        let index = self.effects.len();
+        self.insert_effect(index, effect)
+    }
+
+    pub fn insert_effect(&mut self, index: usize, effect: PostEffect) -> Result<()> {
+        if self.get_effect(&effect.name).is_some() {
+            return Err(eyre!("Post effect already exists: {}", effect.name));
+        }
+        if index > self.effects.len() {
+            return Err(eyre!("Post effect index out of range: {index}"));
+        }
+        self.effects.insert(index, effect);
+        Ok(())
+    }
+
+    pub fn remove_effect(&mut self, name: &str) -> Option<PostEffect> {
+        let index = self.effects.iter().position(|effect| effect.name == name)?;
+        Some(self.effects.remove(index))
+    }
+
+    pub fn get_effect(&self, name: &str) -> Option<&PostEffect> {
+        self.effects.iter().find(|effect| effect.name == name)
+    }
+
+    pub fn get_effect_mut(&mut self, name: &str) -> Option<&mut PostEffect> {
+        self.effects.iter_mut().find(|effect| effect.name == name)
+    }
+
+    /// The effects in the order they are applied
+    pub fn get_effects(&self) -> &[PostEffect] {
+        &self.effects
+    }
+
+    /// Records the enabled effects and the final pass into `OUTPUT`.
+    pub(super) fn encode(&mut self, ctx: &PassContext, encoder: &mut wgpu::CommandEncoder) -> Result<()> {
+        let output_transform = self.record(ctx, encoder)?;
+        // The staged uploads are lost if recording fails, so they only count once it succeeded
+        for effect in self.effects.iter_mut().filter(|effect| effect.enabled) {
+            effect.dirty = false;
+        }
+        self.output_parameters.parameters[0] = output_transform;
+        Ok(())
+    }
+
+    /// Records the effects and the final pass, returning the output transform parameter it uploaded.
+    fn record(&mut self, ctx: &PassContext, encoder: &mut wgpu::CommandEncoder) -> Result<f32> {
+        let resources = ctx.get_resources();
+        let mut input = SCENE_COLOR;
+        for (index, effect) in self.effects.iter().filter(|effect| effect.enabled).enumerate() {
+            if effect.dirty {
+                ctx.upload(encoder, &effect.uniform_buffer, 0, bytemuck::bytes_of(&effect.uniform));
+            }
+            let output = POST_PROCESS_TARGETS[index % 2];
+            let aux = resources.get_texture_or_placeholder(&effect.aux_texture_name)?.get_view();
+            draw_fullscreen(ctx, encoder, FullscreenDraw {
+                label: &effect.name,
+                material_name: &effect.material_name,
+                inputs: self.input_bind_groups.get_or_create(ctx, ctx.get_texture_view(input)?, aux)?,
+                parameters: &effect.bind_group,
+                target: ctx.get_texture_view(output)?,
+                load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+            })?;
+            input = output;
+        }
+
+        let encode_srgb = ctx.get_viewport().get_output_transform() == OutputTransform::SrgbShader;
+        let output_transform = if encode_srgb { 1.0 } else { 0.0 };
+        if self.output_parameters.parameters[0] != output_transform {
+            let mut output_parameters = self.output_parameters;
+            output_parameters.parameters[0] = output_transform;
+            ctx.upload(encoder, &self.output_parameters_buffer, 0, bytemuck::bytes_of(&output_parameters));
+        }
+
+        draw_fullscreen(ctx, encoder, FullscreenDraw {
+            label: "Post Process Output",
+            material_name: OUTPUT_MATERIAL_NAME,
+            inputs: self.input_bind_groups.get_or_create(
+                ctx,
+                ctx.get_texture_view(input)?,
+                resources.get_texture(PLACEHOLDER_TEXTURE_NAME)?.get_view(),
+            )?,
+            parameters: &self.output_parameters_bind_group,
+            target: ctx.get_texture_view(OUTPUT)?,
+            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+        })?;
+        Ok(output_transform)
+    }
+
+    /// Draws the top left of `texture` into `target` after clearing it.
+    pub(super) fn blit_compute_output(
+        &self,
+        ctx: &PassContext,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &Texture,
+        target: &str,
+    ) -> Result<()> {
+        // Compute outputs belong to the scene and can be replaced at any time, so they are not cached
+        let aux = ctx.get_resources().get_texture(PLACEHOLDER_TEXTURE_NAME)?.get_view();
+        draw_fullscreen(ctx, encoder, FullscreenDraw {
+            label: "Compute Output Blit",
+            material_name: COMPUTE_BLIT_MATERIAL_NAME,
+            inputs: &create_input_bind_group(ctx, texture.get_view(), aux)?,
+            parameters: &self.output_parameters_bind_group,
+            target: ctx.get_texture_view(target)?,
+            load: wgpu::LoadOp::Clear(ctx.get_viewport().get_background()),
+        })
+    }
+}
+
+/// Input bind groups of the effects and the output pass. Graph targets and resource textures
+/// stay at the same address while their generations stay the same, so the bind groups
+/// are keyed by the addresses of their input and aux views.
+#[derive(Default)]
+struct InputBindGroupCache {
+    // Resources and graph textures generations the bind groups were created in
+    generations: (u64, u64),
+    bind_groups: HashMap<(usize, usize), wgpu::BindGroup>,
+}
+
+impl InputBindGroupCache {
+    fn get_or_create(
+        &mut self,
+        ctx: &PassContext,
+        input: &wgpu::TextureView,
+        aux: &wgpu::TextureView,
+    ) -> Result<&wgpu::BindGroup> {
+        let generations = (ctx.get_resources().get_generation(), ctx.get_textures_generation());
+        if self.generations != generations {
+            self.bind_groups.clear();
+            self.generations = generations;
+        }
+        let key = (std::ptr::from_ref(input) as usize, std::ptr::from_ref(aux) as usize);
+        Ok(match self.bind_groups.entry(key) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(create_input_bind_group(ctx, input, aux)?),
+        })
+    }
+}
+
+struct FullscreenDraw<'a> {
+    label: &'a str,
+    material_name: &'a str,
+    inputs: &'a wgpu::BindGroup,
+    parameters: &'a wgpu::BindGroup,
+    target: &'a wgpu::TextureView,
+    load: wgpu::LoadOp<wgpu::Color>,
+}
+
+fn create_input_bind_group(
+    ctx: &PassContext,
+    input: &wgpu::TextureView,
+    aux: &wgpu::TextureView,
+) -> Result<wgpu::BindGroup> {
+    let resources = ctx.get_resources();
+    Ok(ctx.get_device().create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: resources.get_bind_group_layout(POST_PROCESS_INPUT_BIND_GROUP_LAYOUT_NAME)?,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(input),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(resources.get_sampler("linear")?),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::TextureView(aux),
+            },
+        ],
+        label: Some("Post Process Input Bind Group"),
+    }))
+}
+
+fn draw_fullscreen(ctx: &PassContext, encoder: &mut wgpu::CommandEncoder, draw: FullscreenDraw) -> Result<()> {
+    let resources = ctx.get_resources();
+    let material = resources.get_render_material(draw.material_name)?;
+    let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some(draw.label),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: draw.target,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load: draw.load,
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        occlusion_query_set: None,
+        timestamp_writes: None,
+    });
+    render_pass.set_pipeline(material.get_pipeline());
+    let push_constants = ShaderPushConstants {
+        model: glam::Mat4::IDENTITY,
+        flipv: 0,
+        alpha_cutoff: 0.0,
+        _padding: [0; 2],
+    };
+    render_pass.set_push_constants(
+        wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+        0,
+        bytemuck::bytes_of(&push_constants),
+    );
+    render_pass.set_bind_group(0, draw.inputs, &[]);
+    render_pass.set_bind_group(1, draw.parameters, &[]);
+    if let Some(index) = material.get_frame_bind_group_index() {
+        render_pass.set_bind_group(index, ctx.get_frame_bind_group(), &[]);
+    }
+    resources.get_fullscreen_quad().draw(&mut render_pass);
+    Ok(())
+}
+
+fn create_parameters_binding(
+    uniform: &ShaderPostEffectUniform,
+    device: &wgpu::Device,
+    resources: &Resources,
+) -> Result<(wgpu::Buffer, wgpu::BindGroup)> {
+    let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Post Effect Uniform Buffer"),
+        contents: bytemuck::bytes_of(uniform),
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+    });
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: resources.get_bind_group_layout(POST_EFFECT_BIND_GROUP_LAYOUT_NAME)?,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }
+        ],
+        label: Some("Post Effect Bind Group"),
+    });
+    Ok((buffer, bind_group))
+}
@@ -0,0 +1,136 @@
+use std::sync::mpsc;
+use color_eyre::eyre::{eyre, Result};
+
+/* This module copies textures and buffers from the GPU back to the CPU. */
+
+/// Copies the first mip level of a 2D texture into a mapped buffer and
+/// converts it into an RGBA8 image.
+/// `Rgba16Float` values are clamped to [0, 1] without converting their color space.
+/// Blocks until the GPU has finished the copy.
+pub fn read_texture_to_image(
+    texture: &wgpu::Texture,
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+) -> Result<image::RgbaImage> {
+    let format = texture.format();
+    let (swap_red_blue, bytes_per_pixel) = match format {
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => (false, 4),
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => (true, 4),
+        wgpu::TextureFormat::Rgba16Float => (false, 8),
+        _ => {
+            return Err(eyre!("Unsupported texture format for readback: {format:?}"));
+        }
+    };
+
+    let width = texture.width();
+    let height = texture.height();
+    let unpadded_bytes_per_row = width * bytes_per_pixel;
+    let padded_bytes_per_row = padded_bytes_per_row(unpadded_bytes_per_row);
+
+    let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Readback Buffer"),
+        size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Readback Encoder"),
+        });
+    encoder.copy_texture_to_buffer(
+        wgpu::ImageCopyTexture {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::ImageCopyBuffer {
+            buffer: &readback_buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(height),
+            },
+        },
+        wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+    );
+    queue.submit(Some(encoder.finish()));
+
+    // Wait for the buffer to be mapped
+    let buffer_slice = readback_buffer.slice(..);
+    let (sender, receiver) = mpsc::channel();
+    buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    device.poll(wgpu::Maintain::Wait);
+    receiver.recv()??;
+
+    // Strip the row padding
+    let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+    {
+        let mapped = buffer_slice.get_mapped_range();
+        for row in mapped.chunks_exact(padded_bytes_per_row as usize) {
+            pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+        }
+    }
+    readback_buffer.unmap();
+
+    if format == wgpu::TextureFormat::Rgba16Float {
+        pixels = pixels
+            .chunks_exact(2)
+            .map(|bytes| {
+                let value = half::f16::from_le_bytes([bytes[0], bytes[1]]).to_f32();
+                (value.clamp(0.0, 1.0) * 255.0).round() as u8
+            })
+            .collect();
+    }
+    if swap_red_blue {
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+    }
+
+    image::RgbaImage::from_raw(width, height, pixels)
+        .ok_or_else(|| eyre!("Readback buffer does not match the texture size"))
+}
+
+/// Copies the whole `buffer`, which needs the `COPY_SRC` usage, into CPU memory.
+/// Blocks until the GPU has finished the copy.
+pub fn read_buffer(buffer: &wgpu::Buffer, device: &wgpu::Device, queue: &wgpu::Queue) -> Result<Vec<u8>> {
+    let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Readback Buffer"),
+        size: buffer.size(),
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Readback Encoder"),
+        });
+    encoder.copy_buffer_to_buffer(buffer, 0, &readback_buffer, 0, buffer.size());
+    queue.submit(Some(encoder.finish()));
+
+    let buffer_slice = readback_buffer.slice(..);
+    let (sender, receiver) = mpsc::channel();
+    buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    device.poll(wgpu::Maintain::Wait);
+    receiver.recv()??;
+
+    let bytes = buffer_slice.get_mapped_range().to_vec();
+    readback_buffer.unmap();
+    Ok(bytes)
+}
+
+/// Rows copied into a buffer must be aligned to `wgpu::COPY_BYTES_PER_ROW_ALIGNMENT`.
+fn padded_bytes_per_row(unpadded_bytes_per_row: u32) -> u32 {
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    unpadded_bytes_per_row.div_ceil(align) * align
+}
@@ -0,0 +1,797 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeSet, HashMap};
+use std::rc::Rc;
+use color_eyre::eyre::{eyre, OptionExt, Result};
+use crate::renderer::camera::Camera;
+use crate::renderer::draw_list::{DrawList, DrawState, DrawStatistics};
+use crate::renderer::frame::Frame;
+use crate::renderer::post_process::PostProcessStack;
+use crate::renderer::resources::Resources;
+use crate::renderer::resources::shader_data::ShaderPushConstants;
+use crate::renderer::scene::Scene;
+use crate::renderer::uploader::Uploader;
+use crate::renderer::viewport::Viewport;
+
+/* Render graph.
+ * Passes declare the resources they read and write by name. A pass runs after the passes
+ * writing the resources it reads, and passes writing the same resource run in the order
+ * they were added. Transient textures and buffers are allocated when their first pass runs
+ * and handed to later resources once their last pass is done, so their contents are undefined
+ * until written. The frame's output texture, the viewport's depth texture and the output
+ * textures of the scene's compute objects are imported as `OUTPUT`, `DEPTH` and `COMPUTE_OUTPUTS`. */
+
+pub const OUTPUT: &str = "output";
+pub const DEPTH: &str = "depth";
+pub const COMPUTE_OUTPUTS: &str = "compute outputs";
+const IMPORTED_RESOURCES: [&str; 3] = [OUTPUT, DEPTH, COMPUTE_OUTPUTS];
+
+/// HDR texture the scene is rendered into, in the viewport's scene format
+pub const SCENE_COLOR: &str = "scene color";
+/// Multisampled scene color, resolved into `SCENE_COLOR`. Only allocated while multisampling is enabled.
+pub const SCENE_COLOR_MULTISAMPLED: &str = "scene color multisampled";
+/// Ping-pong textures of the post-processing effects
+pub const POST_PROCESS_TARGETS: [&str; 2] = ["post process a", "post process b"];
+
+pub const COMPUTE_PASS_NAME: &str = "compute";
+pub const COMPUTE_COPY_PASS_NAME: &str = "compute copy";
+pub const SCENE_PASS_NAME: &str = "scene";
+pub const POST_PROCESS_PASS_NAME: &str = "post process";
+
+type ExecuteFn = Box<dyn FnMut(&PassContext, &mut wgpu::CommandEncoder) -> Result<()>>;
+
+/// A pass of the render graph that records its commands with `execute`.
+pub struct GraphPass {
+    name: String,
+    reads: Vec<String>,
+    writes: Vec<String>,
+    execute: ExecuteFn,
+}
+
+impl GraphPass {
+    pub fn new(
+        name: &str,
+        execute: impl FnMut(&PassContext, &mut wgpu::CommandEncoder) -> Result<()> + 'static,
+    ) -> Self {
+        Self {
+            name: name.to_owned(),
+            reads: Vec::new(),
+            writes: Vec::new(),
+            execute: Box::new(execute),
+        }
+    }
+
+    pub fn with_reads(mut self, names: &[&str]) -> Self {
+        self.reads.extend(names.iter().map(|name| (*name).to_owned()));
+        self
+    }
+
+    /// Resources the pass modifies. Passes that load previous contents only need to declare them here.
+    pub fn with_writes(mut self, names: &[&str]) -> Self {
+        self.writes.extend(names.iter().map(|name| (*name).to_owned()));
+        self
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_reads(&self) -> &[String] {
+        &self.reads
+    }
+
+    pub fn get_writes(&self) -> &[String] {
+        &self.writes
+    }
+
+    fn uses(&self, name: &str) -> bool {
+        self.reads.iter().chain(&self.writes).any(|used| used == name)
+    }
+}
+
+/// A texture that only lives while the passes using it run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransientTexture {
+    format: wgpu::TextureFormat,
+    size: Option<(u32, u32)>,
+    usage: wgpu::TextureUsages,
+    multisampled: bool,
+}
+
+impl TransientTexture {
+    /// A texture the size of the frame's output that can be rendered to, sampled and copied
+    pub fn new(format: wgpu::TextureFormat) -> Self {
+        Self {
+            format,
+            size: None,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::COPY_DST,
+            multisampled: false,
+        }
+    }
+
+    /// A texture the size of the frame's output with the viewport's sample count, to render
+    /// and resolve the scene with multisampling. It is not allocated while the sample count is 1.
+    pub fn new_multisampled(format: wgpu::TextureFormat) -> Self {
+        Self {
+            format,
+            size: None,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            multisampled: true,
+        }
+    }
+
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.size = Some((width, height));
+        self
+    }
+
+    pub fn with_usage(mut self, usage: wgpu::TextureUsages) -> Self {
+        self.usage = usage;
+        self
+    }
+}
+
+/// A buffer that only lives while the passes using it run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransientBuffer {
+    size: wgpu::BufferAddress,
+    usage: wgpu::BufferUsages,
+}
+
+impl TransientBuffer {
+    /// A storage buffer that can be copied to and from
+    pub fn new(size: wgpu::BufferAddress) -> Self {
+        Self {
+            size,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_SRC
+                | wgpu::BufferUsages::COPY_DST,
+        }
+    }
+
+    pub fn with_usage(mut self, usage: wgpu::BufferUsages) -> Self {
+        self.usage = usage;
+        self
+    }
+}
+
+pub struct RenderGraph {
+    passes: Vec<GraphPass>,
+    textures: HashMap<String, TransientTexture>,
+    buffers: HashMap<String, TransientBuffer>,
+    // Execution order as indices into `passes`, computed again when the graph changes
+    order: Option<Vec<usize>>,
+    texture_pool: ResourcePool<TextureKey, (wgpu::Texture, wgpu::TextureView)>,
+    buffer_pool: ResourcePool<TransientBuffer, wgpu::Buffer>,
+}
+
+impl RenderGraph {
+    pub fn new() -> Self {
+        Self {
+            passes: Vec::new(),
+            textures: HashMap::new(),
+            buffers: HashMap::new(),
+            order: None,
+            texture_pool: ResourcePool::new(),
+            buffer_pool: ResourcePool::new(),
+        }
+    }
+
+    /// The graph every renderer starts with: a compute pass dispatching the scene's compute objects,
+    /// a pass drawing the first compute output into `SCENE_COLOR`, a pass drawing the scene's render objects
+    /// into `SCENE_COLOR`, and a pass applying `post_process` and writing the result to `OUTPUT`.
+    /// With multisampling, the scene is drawn into `SCENE_COLOR_MULTISAMPLED` and resolved into `SCENE_COLOR`.
+    pub fn new_with_scene_passes(post_process: Rc<RefCell<PostProcessStack>>) -> Result<Self> {
+        let mut result = Self::new();
+        result.add_texture(SCENE_COLOR, TransientTexture::new(Viewport::SCENE_FORMAT))?;
+        result.add_texture(SCENE_COLOR_MULTISAMPLED, TransientTexture::new_multisampled(Viewport::SCENE_FORMAT))?;
+        for target in POST_PROCESS_TARGETS {
+            result.add_texture(target, TransientTexture::new(Viewport::SCENE_FORMAT))?;
+        }
+
+        let compute_post_process = post_process.clone();
+        result.passes = vec![
+            GraphPass::new(COMPUTE_PASS_NAME, |ctx, encoder| {
+                let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("Compute Pass"),
+                    timestamp_writes: None,
+                });
+                ctx.dispatch_compute_objects(&mut compute_pass)
+            })
+                .with_writes(&[COMPUTE_OUTPUTS]),
+            GraphPass::new(COMPUTE_COPY_PASS_NAME, move |ctx, encoder| {
+                // Draw the output of the first compute object with an output texture into the scene
+                let Some(compute_texture) = ctx.get_first_compute_output() else {
+                    return Ok(());
+                };
+                let target = if ctx.get_viewport().get_sample_count() > 1 {
+                    SCENE_COLOR_MULTISAMPLED
+                } else {
+                    SCENE_COLOR
+                };
+                compute_post_process.try_borrow()?.blit_compute_output(ctx, encoder, compute_texture, target)
+            })
+                .with_reads(&[COMPUTE_OUTPUTS])
+                .with_writes(&[SCENE_COLOR, SCENE_COLOR_MULTISAMPLED]),
+            GraphPass::new(SCENE_PASS_NAME, |ctx, encoder| {
+                let load = if ctx.get_first_compute_output().is_some() {
+                    wgpu::LoadOp::Load
+                } else {
+                    wgpu::LoadOp::Clear(ctx.get_viewport().get_background())
+                };
+                // With multisampling the samples are resolved into the scene color and then discarded
+                let color_attachment = if ctx.get_viewport().get_sample_count() > 1 {
+                    wgpu::RenderPassColorAttachment {
+                        view: ctx.get_texture_view(SCENE_COLOR_MULTISAMPLED)?,
+                        resolve_target: Some(ctx.get_texture_view(SCENE_COLOR)?),
+                        ops: wgpu::Operations {
+                            load,
+                            store: wgpu::StoreOp::Discard,
+                        },
+                    }
+                } else {
+                    wgpu::RenderPassColorAttachment {
+                        view: ctx.get_texture_view(SCENE_COLOR)?,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load,
+                            store: wgpu::StoreOp::Store,
+                        },
+                    }
+                };
+                let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Render Pass"),
+                    color_attachments: &[Some(color_attachment)],
+                    depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                        view: ctx.get_texture_view(DEPTH)?,
+                        depth_ops: Some(wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(1.0),
+                            store: wgpu::StoreOp::Store,
+                        }),
+                        stencil_ops: None,
+                    }),
+                    occlusion_query_set: None,
+                    timestamp_writes: None,
+                });
+                ctx.draw_render_objects(&mut render_pass)
+            })
+                .with_writes(&[SCENE_COLOR, SCENE_COLOR_MULTISAMPLED, DEPTH]),
+            GraphPass::new(POST_PROCESS_PASS_NAME, move |ctx, encoder| {
+                post_process.try_borrow_mut()?.encode(ctx, encoder)
+            })
+                .with_reads(&[SCENE_COLOR])
+                .with_writes(&[POST_PROCESS_TARGETS[0], POST_PROCESS_TARGETS[1], OUTPUT]),
+        ];
+        Ok(result)
+    }
+
+    /// Adds a pass after the existing ones. Fails if a pass of the same name exists.
+    pub fn add_pass(&mut self, pass: GraphPass) -> Result<()> {
+        let index = self.passes.len();
+        self.insert_pass(index, pass)
+    }
+
+    /// Adds a pass before the pass `before`, so it writes shared resources first.
+    pub fn insert_pass_before(&mut self, before: &str, pass: GraphPass) -> Result<()> {
+        let index = self.get_pass_index(before)?;
+        self.insert_pass(index, pass)
+    }
+
+    pub fn remove_pass(&mut self, name: &str) -> Option<GraphPass> {
+        let index = self.get_pass_index(name).ok()?;
+        self.order = None;
+        Some(self.passes.remove(index))
+    }
+
+    pub fn has_pass(&self, name: &str) -> bool {
+        self.passes.iter().any(|pass| pass.name == name)
+    }
+
+    /// Declares a transient texture that passes can use by name.
+    pub fn add_texture(&mut self, name: &str, texture: TransientTexture) -> Result<()> {
+        self.check_resource_name_free(name)?;
+        self.textures.insert(name.to_owned(), texture);
+        self.order = None;
+        Ok(())
+    }
+
+    /// Declares a transient buffer that passes can use by name.
+    pub fn add_buffer(&mut self, name: &str, buffer: TransientBuffer) -> Result<()> {
+        self.check_resource_name_free(name)?;
+        self.buffers.insert(name.to_owned(), buffer);
+        self.order = None;
+        Ok(())
+    }
+
+    /// The names of the passes in the order they are executed
+    pub fn get_pass_order(&mut self) -> Result<Vec<&str>> {
+        let order = self.compile()?.clone();
+        Ok(order.iter().map(|index| self.passes[*index].name.as_str()).collect())
+    }
+
+    /// Records all passes into `encoder` in dependency order.
+    pub(super) fn execute(&mut self, frame: &FrameContext, encoder: &mut wgpu::CommandEncoder) -> Result<()> {
+        let result = self.execute_passes(frame, encoder);
+        // Also after a failed pass, so its resources are available to the next frame
+        self.texture_pool.end_frame();
+        self.buffer_pool.end_frame();
+        result
+    }
+
+    fn execute_passes(&mut self, frame: &FrameContext, encoder: &mut wgpu::CommandEncoder) -> Result<()> {
+        let order = self.compile()?.clone();
+        let lifetimes = self.get_resource_lifetimes(&order);
+        let output_size = (frame.output.width(), frame.output.height());
+        let sample_count = frame.viewport.get_sample_count();
+
+        let mut allocated_textures = HashMap::new();
+        let mut allocated_buffers = HashMap::new();
+        for (position, pass_index) in order.iter().enumerate() {
+            for (name, (first, _)) in &lifetimes {
+                if *first != position {
+                    continue;
+                }
+                if let Some(texture) = self.textures.get(name) {
+                    if texture.multisampled && sample_count == 1 {
+                        continue;
+                    }
+                    let key = TextureKey::new(texture, output_size, sample_count);
+                    let index = self.texture_pool.acquire(key, |key| key.create(name, frame.device));
+                    allocated_textures.insert(name.as_str(), index);
+                } else if let Some(buffer) = self.buffers.get(name) {
+                    let index = self.buffer_pool.acquire(*buffer, |buffer| create_buffer(name, buffer, frame.device));
+                    allocated_buffers.insert(name.as_str(), index);
+                }
+            }
+
+            let pass = &mut self.passes[*pass_index];
+            let ctx = PassContext {
+                frame,
+                pass_name: &pass.name,
+                reads: &pass.reads,
+                writes: &pass.writes,
+                textures: allocated_textures
+                    .iter()
+                    .map(|(name, index)| (*name, self.texture_pool.get(*index)))
+                    .collect(),
+                buffers: allocated_buffers
+                    .iter()
+                    .map(|(name, index)| (*name, self.buffer_pool.get(*index)))
+                    .collect(),
+                textures_generation: self.texture_pool.generation,
+            };
+            encoder.push_debug_group(&pass.name);
+            let result = (pass.execute)(&ctx, encoder)
+                .map_err(|report| eyre!("Render graph pass {} failed: {report}", pass.name));
+            encoder.pop_debug_group();
+            result?;
+
+            for (name, (_, last)) in &lifetimes {
+                if *last != position {
+                    continue;
+                }
+                if let Some(index) = allocated_textures.remove(name.as_str()) {
+                    self.texture_pool.release(index);
+                }
+                if let Some(index) = allocated_buffers.remove(name.as_str()) {
+                    self.buffer_pool.release(index);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn insert_pass(&mut self, index: usize, pass: GraphPass) -> Result<()> {
+        if self.has_pass(&pass.name) {
+            return Err(eyre!("Render graph pass already exists: {}", pass.name));
+        }
+        self.passes.insert(index, pass);
+        self.order = None;
+        Ok(())
+    }
+
+    fn get_pass_index(&self, name: &str) -> Result<usize> {
+        self.passes
+            .iter()
+            .position(|pass| pass.name == name)
+            .ok_or_eyre(format!("Render graph pass not found: {name}"))
+    }
+
+    fn check_resource_name_free(&self, name: &str) -> Result<()> {
+        if IMPORTED_RESOURCES.contains(&name) || self.textures.contains_key(name) || self.buffers.contains_key(name) {
+            return Err(eyre!("Render graph resource already exists: {name}"));
+        }
+        Ok(())
+    }
+
+    /// Orders the passes so that every pass runs after the passes writing what it uses.
+    fn compile(&mut self) -> Result<&Vec<usize>> {
+        if self.order.is_none() {
+            self.order = Some(self.sort_passes()?);
+        }
+        Ok(self.order.as_ref().unwrap())
+    }
+
+    fn sort_passes(&self) -> Result<Vec<usize>> {
+        let mut writers: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (index, pass) in self.passes.iter().enumerate() {
+            for name in pass.reads.iter().chain(&pass.writes) {
+                let known = IMPORTED_RESOURCES.contains(&name.as_str())
+                    || self.textures.contains_key(name)
+                    || self.buffers.contains_key(name);
+                if !known {
+                    return Err(eyre!("Render graph pass {} uses unknown resource {name}", pass.name));
+                }
+            }
+            for name in &pass.writes {
+                writers.entry(name).or_default().push(index);
+            }
+        }
+
+        let mut dependencies = vec![BTreeSet::<usize>::new(); self.passes.len()];
+        for (index, pass) in self.passes.iter().enumerate() {
+            // Writers of the same resource keep the order they were added in
+            for name in &pass.writes {
+                let previous = writers[name.as_str()].iter().rev().find(|writer| **writer < index);
+                dependencies[index].extend(previous);
+            }
+            // Readers see the resource once all writers are done
+            for name in pass.reads.iter().filter(|name| !pass.writes.contains(name)) {
+                match writers.get(name.as_str()) {
+                    Some(name_writers) => dependencies[index].extend(name_writers),
+                    None if IMPORTED_RESOURCES.contains(&name.as_str()) => {}
+                    None => return Err(eyre!("Render graph pass {} reads {name}, which no pass writes", pass.name)),
+                }
+            }
+        }
+
+        // Kahn's algorithm, preferring the earliest added pass among the ready ones
+        let mut order = Vec::with_capacity(self.passes.len());
+        let mut done = vec![false; self.passes.len()];
+        while order.len() < self.passes.len() {
+            let next = (0..self.passes.len())
+                .find(|index| !done[*index] && dependencies[*index].iter().all(|dependency| done[*dependency]))
+                .ok_or_else(|| {
+                    let cycle = (0..self.passes.len())
+                        .filter(|index| !done[*index])
+                        .map(|index| self.passes[index].name.as_str())
+                        .collect::<Vec<_>>();
+                    eyre!("Render graph passes depend on each other: {}", cycle.join(", "))
+                })?;
+            done[next] = true;
+            order.push(next);
+        }
+        Ok(order)
+    }
+
+    /// The first and last position in `order` of the passes using each transient resource
+    fn get_resource_lifetimes(&self, order: &[usize]) -> HashMap<String, (usize, usize)> {
+        let mut result = HashMap::new();
+        let transient_names = self.textures.keys().chain(self.buffers.keys());
+        for name in transient_names {
+            let mut positions = order
+                .iter()
+                .enumerate()
+                .filter(|(_, pass_index)| self.passes[**pass_index].uses(name))
+                .map(|(position, _)| position);
+            if let Some(first) = positions.next() {
+                let last = positions.next_back().unwrap_or(first);
+                result.insert(name.clone(), (first, last));
+            }
+        }
+        result
+    }
+}
+
+impl Default for RenderGraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Everything about the frame being rendered that passes can access
+pub(super) struct FrameContext<'a> {
+    pub output: &'a wgpu::Texture,
+    pub output_view: &'a wgpu::TextureView,
+    pub scene: &'a Scene,
+    pub camera: &'a Camera,
+    pub resources: &'a Resources,
+    pub frame: &'a Frame,
+    pub viewport: &'a Viewport<'a>,
+    pub device: &'a wgpu::Device,
+    pub queue: &'a wgpu::Queue,
+    // Accumulated by the passes drawing the scene
+    pub draw_statistics: &'a Cell<DrawStatistics>,
+    // Borrowed by passes to record uploads
+    pub uploader: &'a RefCell<Uploader>,
+}
+
+/// What a pass can access while it records its commands.
+/// Graph resources must be declared by the pass to be accessed.
+pub struct PassContext<'a> {
+    frame: &'a FrameContext<'a>,
+    pass_name: &'a str,
+    reads: &'a [String],
+    writes: &'a [String],
+    textures: HashMap<&'a str, &'a (wgpu::Texture, wgpu::TextureView)>,
+    buffers: HashMap<&'a str, &'a wgpu::Buffer>,
+    textures_generation: u64,
+}
+
+impl PassContext<'_> {
+    pub fn get_pass_name(&self) -> &str {
+        self.pass_name
+    }
+
+    pub fn get_texture(&self, name: &str) -> Result<&wgpu::Texture> {
+        self.check_declared(name)?;
+        match name {
+            OUTPUT => Ok(self.frame.output),
+            DEPTH => Ok(self.frame.viewport.get_depth_texture()),
+            _ => self.get_transient_texture(name).map(|(texture, _)| texture),
+        }
+    }
+
+    pub fn get_texture_view(&self, name: &str) -> Result<&wgpu::TextureView> {
+        self.check_declared(name)?;
+        match name {
+            OUTPUT => Ok(self.frame.output_view),
+            DEPTH => Ok(self.frame.viewport.get_depth_view()),
+            _ => self.get_transient_texture(name).map(|(_, view)| view),
+        }
+    }
+
+    /// Changes whenever the graph creates or drops textures.
+    /// While it stays the same, graph textures are not moved in memory either.
+    pub(super) fn get_textures_generation(&self) -> u64 {
+        self.textures_generation
+    }
+
+    pub fn get_buffer(&self, name: &str) -> Result<&wgpu::Buffer> {
+        self.check_declared(name)?;
+        self.buffers
+            .get(name)
+            .copied()
+            .ok_or_eyre(format!("Render graph buffer not found: {name}"))
+    }
+
+    pub fn get_scene(&self) -> &Scene {
+        self.frame.scene
+    }
+
+    pub fn get_camera(&self) -> &Camera {
+        self.frame.camera
+    }
+
+    pub fn get_resources(&self) -> &Resources {
+        self.frame.resources
+    }
+
+    pub fn get_viewport(&self) -> &Viewport<'_> {
+        self.frame.viewport
+    }
+
+    pub fn get_device(&self) -> &wgpu::Device {
+        self.frame.device
+    }
+
+    pub fn get_queue(&self) -> &wgpu::Queue {
+        self.frame.queue
+    }
+
+    /// Records a copy of `data` into `target` at `offset`, executed in order with the commands around it.
+    /// See `Uploader::upload`.
+    pub fn upload(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::Buffer,
+        offset: wgpu::BufferAddress,
+        data: &[u8],
+    ) {
+        self.frame.uploader.borrow_mut().upload(encoder, target, offset, data, self.frame.device);
+    }
+
+    pub fn get_camera_bind_group(&self) -> &wgpu::BindGroup {
+        self.frame.frame.get_camera_bind_group()
+    }
+
+    pub fn get_frame_bind_group(&self) -> &wgpu::BindGroup {
+        self.frame.frame.get_frame_bind_group()
+    }
+
+    /// The output texture of the first visible compute object that has one
+    pub fn get_first_compute_output(&self) -> Option<&crate::renderer::resources::texture::Texture> {
+        self.frame.scene
+            .get_visible_compute_objects()
+            .find_map(|(_, compute_object)| compute_object.get_output_texture())
+    }
+
+    /// Dispatches the scene's visible compute objects, then simulates its visible particle systems.
+    /// Requires writing `COMPUTE_OUTPUTS`.
+    pub fn dispatch_compute_objects(&self, compute_pass: &mut wgpu::ComputePass) -> Result<()> {
+        self.check_declared(COMPUTE_OUTPUTS)?;
+        for (_, compute_object) in self.frame.scene.get_visible_compute_objects() {
+            compute_object.dispatch(
+                compute_pass,
+                self.frame.resources,
+                self.get_frame_bind_group(),
+            )?;
+        }
+        for (_, particle_system) in self.frame.scene.get_visible_particle_systems() {
+            particle_system.dispatch(
+                compute_pass,
+                self.frame.camera,
+                self.frame.resources,
+                self.get_frame_bind_group(),
+                self.frame.queue,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Draws the scene's visible render objects, opaque ones grouped by shared state and
+    /// transparent ones last and back-to-front, followed by the visible particle systems.
+    /// The render pass must target the viewport's scene format, `DEPTH`'s format and the
+    /// viewport's sample count, as materials are built for them.
+    pub fn draw_render_objects(&self, render_pass: &mut wgpu::RenderPass) -> Result<()> {
+        let push_constants = ShaderPushConstants {
+            model: glam::Mat4::IDENTITY,
+            flipv: 1,
+            alpha_cutoff: 0.0,
+            _padding: [0; 2],
+        };
+        let draw_list = DrawList::new(self.frame.scene, self.frame.camera, self.frame.resources)?;
+        let mut state = DrawState::new(render_pass);
+        draw_list.draw(
+            &mut state,
+            self.get_camera_bind_group(),
+            self.get_frame_bind_group(),
+            &push_constants,
+        );
+        for (_, particle_system) in self.frame.scene.get_visible_particle_systems() {
+            particle_system.draw(
+                &mut state,
+                self.frame.resources,
+                self.get_camera_bind_group(),
+                self.get_frame_bind_group(),
+                &push_constants,
+            )?;
+        }
+
+        let mut statistics = self.frame.draw_statistics.get();
+        statistics += state.get_statistics();
+        self.frame.draw_statistics.set(statistics);
+        Ok(())
+    }
+
+    fn check_declared(&self, name: &str) -> Result<()> {
+        if !self.reads.iter().chain(self.writes).any(|declared| declared == name) {
+            return Err(eyre!("Render graph pass {} does not declare {name}", self.pass_name));
+        }
+        Ok(())
+    }
+
+    fn get_transient_texture(&self, name: &str) -> Result<&(wgpu::Texture, wgpu::TextureView)> {
+        self.textures
+            .get(name)
+            .copied()
+            .ok_or_eyre(format!("Render graph texture not found or not allocated: {name}"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TextureKey {
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+    usage: wgpu::TextureUsages,
+    sample_count: u32,
+}
+
+impl TextureKey {
+    fn new(texture: &TransientTexture, output_size: (u32, u32), viewport_sample_count: u32) -> Self {
+        let (width, height) = texture.size.unwrap_or(output_size);
+        Self {
+            width,
+            height,
+            format: texture.format,
+            usage: texture.usage,
+            sample_count: if texture.multisampled { viewport_sample_count } else { 1 },
+        }
+    }
+
+    fn create(&self, name: &str, device: &wgpu::Device) -> (wgpu::Texture, wgpu::TextureView) {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(name),
+            size: wgpu::Extent3d {
+                width: self.width,
+                height: self.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: self.sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.format,
+            usage: self.usage,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        (texture, view)
+    }
+}
+
+fn create_buffer(name: &str, buffer: &TransientBuffer, device: &wgpu::Device) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some(name),
+        size: buffer.size,
+        usage: buffer.usage,
+        mapped_at_creation: false,
+    })
+}
+
+/// GPU resources kept between frames to back transient resources of the same description
+struct ResourcePool<K, R> {
+    entries: Vec<PoolEntry<K, R>>,
+    // Changes whenever entries are created or dropped, which may move the others in memory
+    generation: u64,
+}
+
+struct PoolEntry<K, R> {
+    key: K,
+    resource: R,
+    in_use: bool,
+    used_this_frame: bool,
+}
+
+impl<K: PartialEq, R> ResourcePool<K, R> {
+    fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            generation: 0,
+        }
+    }
+
+    fn acquire(&mut self, key: K, create: impl FnOnce(&K) -> R) -> usize {
+        let index = match self.entries.iter().position(|entry| !entry.in_use && entry.key == key) {
+            Some(index) => index,
+            None => {
+                let resource = create(&key);
+                self.entries.push(PoolEntry {
+                    key,
+                    resource,
+                    in_use: false,
+                    used_this_frame: false,
+                });
+                self.generation += 1;
+                self.entries.len() - 1
+            }
+        };
+        let entry = &mut self.entries[index];
+        entry.in_use = true;
+        entry.used_this_frame = true;
+        index
+    }
+
+    fn get(&self, index: usize) -> &R {
+        &self.entries[index].resource
+    }
+
+    fn release(&mut self, index: usize) {
+        self.entries[index].in_use = false;
+    }
+
+    /// Drops the resources no pass used this frame, such as those of the previous viewport size.
+    fn end_frame(&mut self) {
+        let count = self.entries.len();
+        self.entries.retain(|entry| entry.used_this_frame);
+        if self.entries.len() != count {
+            self.generation += 1;
+        }
+        for entry in &mut self.entries {
+            entry.in_use = false;
+            entry.used_this_frame = false;
+        }
+    }
+}
@@ -1,13 +1,29 @@
-use color_eyre::Result;
-use crate::renderer::Camera;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use color_eyre::eyre::{eyre, Result};
+use crate::renderer::Transform;
+use crate::renderer::draw_list::{ResolvedDraw, ResolvedMesh};
+use crate::renderer::instance::InstanceBuffer;
 use crate::renderer::resources::Resources;
-use crate::renderer::resources::shader_data::ShaderPushConstants;
-use crate::renderer::viewport::Viewport;
+
+/// Material and texture a mesh is drawn with instead of the render object's
+struct SubMaterial {
+    material_name: String,
+    texture_name: String,
+}
 
 pub struct RenderObject {
     material_name: String,
     texture_name: String,
     model_name: String,
+    // By mesh index
+    sub_materials: BTreeMap<usize, SubMaterial>,
+    transform: Transform,
+    // Instanced render objects draw one copy per instance
+    instances: Option<InstanceBuffer>,
+    // Resolved from the names above, together with the resources generation it was resolved in
+    resolved: RefCell<Option<(u64, Option<Rc<ResolvedDraw>>)>>,
 }
 
 impl RenderObject {
@@ -20,41 +36,134 @@ impl RenderObject {
             material_name,
             texture_name,
             model_name,
+            sub_materials: BTreeMap::new(),
+            transform: Transform::IDENTITY,
+            instances: None,
+            resolved: RefCell::new(None),
         }
     }
 
-    pub fn draw(
-        &self,
-        render_pass: &mut wgpu::RenderPass,
-        camera: &mut Camera,
-        resources: &Resources,
-        viewport: &Viewport,
-        device: &wgpu::Device,
-        queue: &wgpu::Queue,
-        push_constants: Option<&ShaderPushConstants>,
-    ) -> Result<()> {
-        let material = resources.get_render_material(&self.material_name)?;
-        let texture = resources.get_texture(&self.texture_name)?;
-        let model = resources.get_model(&self.model_name)?;
-
-        render_pass.set_pipeline(material.get_pipeline());
-
-        if let Some(push_constants) = push_constants {
-            render_pass.set_push_constants(
-                wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
-                0,
-                bytemuck::bytes_of(push_constants),
-            );
-        }
+    pub fn with_instances(mut self, instances: InstanceBuffer) -> Self {
+        self.instances = Some(instances);
+        self
+    }
+
+    /// The number of instances drawn, `None` if the object is not instanced
+    pub fn get_instance_count(&self) -> Option<u32> {
+        self.instances.as_ref().map(InstanceBuffer::get_count)
+    }
 
-        render_pass.set_bind_group(0, texture.get_bind_group(), &[]);
-        render_pass.set_bind_group(1, camera.get_bind_group(
-            viewport,
-            device,
-            queue,
-        ), &[]);
-        model.draw(render_pass);
+    pub(crate) fn get_instances(&self) -> Option<&InstanceBuffer> {
+        self.instances.as_ref()
+    }
+
+    pub(crate) fn get_instances_mut(&mut self) -> Option<&mut InstanceBuffer> {
+        self.instances.as_mut()
+    }
+
+    pub fn get_material_name(&self) -> &str {
+        &self.material_name
+    }
+
+    /// The material the model's mesh `mesh_index` is drawn with
+    pub fn get_mesh_material_name(&self, mesh_index: usize) -> &str {
+        self.sub_materials
+            .get(&mesh_index)
+            .map_or(&self.material_name, |sub_material| &sub_material.material_name)
+    }
 
-        Ok(())
+    /// The texture the model's mesh `mesh_index` is drawn with,
+    /// unless the model assigns the mesh a texture and no sub-material is set.
+    pub fn get_mesh_texture_name(&self, mesh_index: usize) -> &str {
+        self.sub_materials
+            .get(&mesh_index)
+            .map_or(&self.texture_name, |sub_material| &sub_material.texture_name)
     }
-}
\ No newline at end of file
+
+    /// Draws the model's mesh `mesh_index` with another material and texture than the rest.
+    pub fn set_mesh_material(&mut self, mesh_index: usize, material_name: String, texture_name: String) {
+        self.sub_materials.insert(mesh_index, SubMaterial { material_name, texture_name });
+        *self.resolved.get_mut() = None;
+    }
+
+    /// Draws the model's mesh `mesh_index` with the render object's material and texture again.
+    pub fn clear_mesh_material(&mut self, mesh_index: usize) {
+        self.sub_materials.remove(&mesh_index);
+        *self.resolved.get_mut() = None;
+    }
+
+    pub fn get_transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    pub fn get_transform_mut(&mut self) -> &mut Transform {
+        &mut self.transform
+    }
+
+    pub fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+
+    /// The state the object is drawn with, `None` while its model is loading.
+    /// It is resolved again only after the resources change.
+    pub(crate) fn resolve(&self, resources: &Resources) -> Result<Option<Rc<ResolvedDraw>>> {
+        if let Some((generation, resolved)) = self.resolved.borrow().as_ref() {
+            if *generation == resources.get_generation() {
+                return Ok(resolved.clone());
+            }
+        }
+
+        // Missing resources are reported even while the model is loading
+        resources.get_render_material(&self.material_name)?;
+        resources.get_texture_or_placeholder(&self.texture_name)?;
+        // Models still loading are not drawn at all
+        let Some(model) = resources.get_model_if_loaded(&self.model_name)? else {
+            *self.resolved.borrow_mut() = Some((resources.get_generation(), None));
+            return Ok(None);
+        };
+        let mesh_count = model.get_mesh_ranges().len();
+        if let Some(mesh_index) = self.sub_materials.keys().find(|&&mesh_index| mesh_index >= mesh_count) {
+            return Err(eyre!(
+                "Render object sets a material for mesh {mesh_index}, but model {} has {mesh_count} meshes",
+                self.model_name,
+            ));
+        }
+        let meshes = model.get_mesh_ranges()
+            .iter()
+            .enumerate()
+            .map(|(mesh_index, range)| {
+                let material_name = self.get_mesh_material_name(mesh_index);
+                let material = resources.get_render_material(material_name)?;
+                let texture_name = match self.sub_materials.get(&mesh_index) {
+                    Some(sub_material) => &sub_material.texture_name,
+                    None => model.get_mesh_texture_name(mesh_index).unwrap_or(&self.texture_name),
+                };
+                let texture = resources.get_texture_or_placeholder(texture_name)?;
+                if material.is_instanced() != self.instances.is_some() {
+                    return Err(eyre!(
+                        "Material {material_name} must be instanced exactly if the render object is instanced",
+                    ));
+                }
+                Ok(ResolvedMesh {
+                    pipeline: material.get_shared_pipeline().clone(),
+                    texture_bind_group: texture.get_shared_bind_group().clone(),
+                    frame_bind_group_index: material.get_frame_bind_group_index(),
+                    alpha_cutoff: material.get_alpha_cutoff(),
+                    transparent: material.is_transparent(),
+                    range: *range,
+                    bounds_center: {
+                        let (min, max) = model.get_meshes()[mesh_index].get_bounds();
+                        (min + max) * 0.5
+                    },
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let resolved = Some(Rc::new(ResolvedDraw {
+            vertex_buffer: model.get_shared_vertex_buffer().clone(),
+            index_buffer: model.get_shared_index_buffer().cloned(),
+            meshes,
+        }));
+        *self.resolved.borrow_mut() = Some((resources.get_generation(), resolved.clone()));
+        Ok(resolved)
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::mpsc;
+use color_eyre::eyre::Result;
+use super::file;
+use super::gltf_loader::GltfAsset;
+use super::obj_loader::ObjAsset;
+
+/// Load state of an asset requested from an `AssetLoader`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetState {
+    Pending,
+    Loaded,
+    /// Holds the error message
+    Failed(String),
+}
+
+/// Number of requested assets in each state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LoadProgress {
+    pub pending: usize,
+    pub loaded: usize,
+    pub failed: usize,
+}
+
+impl LoadProgress {
+    pub fn get_total(&self) -> usize {
+        self.pending + self.loaded + self.failed
+    }
+
+    /// Fraction of requested assets that are no longer pending, 1 if nothing was requested.
+    pub fn get_fraction(&self) -> f32 {
+        match self.get_total() {
+            0 => 1.0,
+            total => (self.loaded + self.failed) as f32 / total as f32,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.pending == 0
+    }
+}
+
+/// CPU-side data of a loaded asset, waiting to be uploaded to the GPU.
+pub enum DecodedAsset {
+    Texture(image::DynamicImage),
+    Obj(ObjAsset),
+    Gltf(GltfAsset),
+}
+
+type LoadResult = (String, Result<DecodedAsset>);
+// Loads are numbered, so only the result of the latest request for a name is kept
+type LoadMessage = (u64, LoadResult);
+
+/// Fetches and decodes assets concurrently in the background,
+/// on a thread per asset on native and as browser tasks on web.
+/// Decoded assets are handed back through `take_finished` for the GPU upload,
+/// which has to happen where the device lives.
+pub struct AssetLoader {
+    states: HashMap<String, AssetState>,
+    // Number of the latest request for each name
+    requests: HashMap<String, u64>,
+    next_request: u64,
+    sender: mpsc::Sender<LoadMessage>,
+    receiver: mpsc::Receiver<LoadMessage>,
+}
+
+impl AssetLoader {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            states: HashMap::new(),
+            requests: HashMap::new(),
+            next_request: 0,
+            sender,
+            receiver,
+        }
+    }
+
+    /// Loads a PNG or JPEG image to become the texture `name`.
+    pub fn load_texture(&mut self, name: &str, filepath: &str) {
+        let filepath = filepath.to_owned();
+        self.spawn(name, async move {
+            let bytes = file::load_bytes(&filepath).await?;
+            Ok(DecodedAsset::Texture(image::load_from_memory(&bytes)?))
+        });
+    }
+
+    /// Loads a Wavefront OBJ file to become the model `name`, see `Resources::add_obj_asset`.
+    pub fn load_obj(&mut self, name: &str, filepath: &str) {
+        let filepath = filepath.to_owned();
+        self.spawn(name, async move {
+            Ok(DecodedAsset::Obj(ObjAsset::new_from_file(&filepath).await?))
+        });
+    }
+
+    /// Loads a glTF 2.0 file to become the model `name`, see `Resources::add_gltf_asset`.
+    /// On web, buffers and images must be embedded.
+    pub fn load_gltf(&mut self, name: &str, filepath: &str) {
+        let filepath = filepath.to_owned();
+        self.spawn(name, async move {
+            #[cfg(not(target_arch = "wasm32"))]
+            let asset = GltfAsset::new_from_file(file::get_native_path(&filepath))?;
+            #[cfg(target_arch = "wasm32")]
+            let asset = GltfAsset::new_from_bytes(&file::load_bytes(&filepath).await?)?;
+            Ok(DecodedAsset::Gltf(asset))
+        });
+    }
+
+    pub fn get_state(&self, name: &str) -> Option<&AssetState> {
+        self.states.get(name)
+    }
+
+    pub fn get_progress(&self) -> LoadProgress {
+        let mut progress = LoadProgress::default();
+        for state in self.states.values() {
+            match state {
+                AssetState::Pending => progress.pending += 1,
+                AssetState::Loaded => progress.loaded += 1,
+                AssetState::Failed(_) => progress.failed += 1,
+            }
+        }
+        progress
+    }
+
+    /// Whether `name` refers to a requested asset that is pending or failed,
+    /// or to a resource inside one, such as the texture `<asset>/<material>`.
+    pub fn is_unavailable(&self, name: &str) -> bool {
+        self.states
+            .iter()
+            .filter(|(_, state)| **state != AssetState::Loaded)
+            .any(|(asset, _)| {
+                name.strip_prefix(asset.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+    }
+
+    /// Returns the assets that finished loading since the last call, without blocking.
+    /// Results of requests that were superseded by a later request for the same name are dropped.
+    pub fn take_finished(&mut self) -> Vec<LoadResult> {
+        self.receiver
+            .try_iter()
+            .filter(|(request, (name, _))| self.requests.get(name) == Some(request))
+            .map(|(_, result)| result)
+            .collect()
+    }
+
+    pub(super) fn set_state(&mut self, name: &str, state: AssetState) {
+        self.states.insert(name.to_owned(), state);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn spawn(&mut self, name: &str, load: impl Future<Output = Result<DecodedAsset>> + Send + 'static) {
+        let (request, name, sender) = self.start(name);
+        std::thread::spawn(move || {
+            // Sending only fails once the loader is dropped, so the result is no longer wanted
+            let _ = sender.send((request, (name, pollster::block_on(load))));
+        });
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn spawn(&mut self, name: &str, load: impl Future<Output = Result<DecodedAsset>> + 'static) {
+        let (request, name, sender) = self.start(name);
+        wasm_bindgen_futures::spawn_local(async move {
+            // Sending only fails once the loader is dropped, so the result is no longer wanted
+            let _ = sender.send((request, (name, load.await)));
+        });
+    }
+
+    fn start(&mut self, name: &str) -> (u64, String, mpsc::Sender<LoadMessage>) {
+        let request = self.next_request;
+        self.next_request += 1;
+        self.requests.insert(name.to_owned(), request);
+        self.set_state(name, AssetState::Pending);
+        (request, name.to_owned(), self.sender.clone())
+    }
+}
+
+impl Default for AssetLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
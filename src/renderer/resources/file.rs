@@ -0,0 +1,56 @@
+use color_eyre::Result;
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::{Path, PathBuf};
+#[cfg(target_arch = "wasm32")]
+use reqwest::Url;
+
+/* Loading of resource files, shared by shaders and asset loaders. */
+
+/// Reads a file relative to the crate root on native,
+/// or fetches it relative to the page's base URL on web.
+pub async fn load_bytes(filepath: &str) -> Result<Vec<u8>> {
+    #[cfg(not(target_arch = "wasm32"))]
+    let bytes = std::fs::read(get_native_path(filepath))?;
+    #[cfg(target_arch = "wasm32")]
+    let bytes = fetch_file(filepath).await?;
+
+    Ok(bytes)
+}
+
+/// Returns the path `load_bytes` reads `filepath` from on native.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get_native_path(filepath: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join(filepath)
+}
+
+/// Resolves `relative` against the directory containing `base_filepath`,
+/// e.g. to find the material library referenced by a model file.
+pub fn resolve_relative(base_filepath: &str, relative: &str) -> String {
+    match base_filepath.rfind(['/', '\\']) {
+        Some(index) => format!("{}/{}", &base_filepath[..index], relative),
+        None => relative.to_owned(),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn fetch_file(filepath: &str) -> Result<Vec<u8>> {
+    let base_url = get_base_url();
+    let url = base_url.join(filepath)?;
+    log::info!("Fetching file from: {}", url);
+    let response = reqwest::get(url.as_str()).await?;
+    Ok(response.bytes().await?.to_vec())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn get_base_url() -> Url {
+    use winit::platform::web::WindowExtWebSys;
+    let window = web_sys::window().expect("No window");
+    let document = window.document().expect("No document");
+    let base_url = if let Ok(Some(base_uri)) = document.base_uri() {
+        base_uri
+    } else {
+        window.location().origin().expect("No origin")
+    };
+    Url::parse(&base_url)
+        .expect(&format!("Failed to parse base URL: {}", base_url))
+}
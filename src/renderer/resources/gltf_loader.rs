@@ -0,0 +1,195 @@
+use color_eyre::eyre::{eyre, OptionExt, Result};
+use glam::{Mat3, Mat4, Vec2, Vec3};
+use super::mesh::Mesh;
+use super::vertex::Vertex;
+
+/// Meshes and images loaded from a glTF 2.0 file.
+/// Node transforms are baked into the mesh vertices.
+pub struct GltfAsset {
+    pub meshes: Vec<Mesh>,
+    /// Index into `images` of each mesh's base color texture, `None` for primitives without one
+    pub mesh_images: Vec<Option<usize>>,
+    pub images: Vec<image::DynamicImage>,
+}
+
+impl GltfAsset {
+    /// Loads a `.gltf` or `.glb` file along with any external buffers and images it references.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new_from_file(path: impl AsRef<std::path::Path>) -> Result<Self> {
+        let (document, buffers, images) = gltf::import(path)?;
+        let images = images
+            .into_iter()
+            .map(convert_image)
+            .collect::<Result<Vec<_>>>()?;
+        Self::new_from_document(&document, &buffers, images)
+    }
+
+    /// Loads a `.gltf` or `.glb` file from memory.
+    /// Buffers and images must be embedded, since external files cannot be resolved.
+    pub fn new_from_bytes(bytes: &[u8]) -> Result<Self> {
+        let gltf::Gltf { document, blob } = gltf::Gltf::from_slice(bytes)?;
+        let buffers = gltf::import_buffers(&document, None, blob)?;
+        // Decoded here, since `gltf::import_slice` rejects images embedded as data URIs
+        let images = document
+            .images()
+            .map(|image| load_embedded_image(&image, &buffers))
+            .collect::<Result<Vec<_>>>()?;
+        Self::new_from_document(&document, &buffers, images)
+    }
+
+    fn new_from_document(
+        document: &gltf::Document,
+        buffers: &[gltf::buffer::Data],
+        images: Vec<image::DynamicImage>,
+    ) -> Result<Self> {
+        let mut meshes = Vec::new();
+        let mut mesh_images = Vec::new();
+        match document.default_scene().or_else(|| document.scenes().next()) {
+            Some(scene) => {
+                for node in scene.nodes() {
+                    load_node(&node, Mat4::IDENTITY, buffers, &mut meshes, &mut mesh_images)?;
+                }
+            }
+            // Files without scenes still contain meshes, so load them untransformed
+            None => {
+                for mesh in document.meshes() {
+                    load_mesh(&mesh, Mat4::IDENTITY, buffers, &mut meshes, &mut mesh_images)?;
+                }
+            }
+        }
+
+        if meshes.is_empty() {
+            return Err(eyre!("glTF file contains no triangle meshes"));
+        }
+
+        Ok(Self {
+            meshes,
+            mesh_images,
+            images,
+        })
+    }
+}
+
+fn load_node(
+    node: &gltf::Node,
+    parent_transform: Mat4,
+    buffers: &[gltf::buffer::Data],
+    meshes: &mut Vec<Mesh>,
+    mesh_images: &mut Vec<Option<usize>>,
+) -> Result<()> {
+    let transform = parent_transform * Mat4::from_cols_array_2d(&node.transform().matrix());
+    if let Some(mesh) = node.mesh() {
+        load_mesh(&mesh, transform, buffers, meshes, mesh_images)?;
+    }
+    for child in node.children() {
+        load_node(&child, transform, buffers, meshes, mesh_images)?;
+    }
+    Ok(())
+}
+
+fn load_mesh(
+    mesh: &gltf::Mesh,
+    transform: Mat4,
+    buffers: &[gltf::buffer::Data],
+    meshes: &mut Vec<Mesh>,
+    mesh_images: &mut Vec<Option<usize>>,
+) -> Result<()> {
+    let normal_matrix = Mat3::from_mat4(transform).inverse().transpose();
+
+    for primitive in mesh.primitives() {
+        if primitive.mode() != gltf::mesh::Mode::Triangles {
+            log::warn!(
+                "Skipping glTF primitive with unsupported mode {:?} in mesh {:?}",
+                primitive.mode(),
+                mesh.name(),
+            );
+            continue;
+        }
+
+        let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+        let positions = reader
+            .read_positions()
+            .ok_or_eyre("glTF primitive has no positions")?;
+        let mut normals = reader.read_normals();
+        let mut colors = reader.read_colors(0).map(|colors| colors.into_rgb_f32());
+        let mut texcoords = reader.read_tex_coords(0).map(|texcoords| texcoords.into_f32());
+
+        let vertices = positions
+            .map(|position| Vertex {
+                position: transform.transform_point3(position.into()),
+                normal: normals
+                    .as_mut()
+                    .and_then(Iterator::next)
+                    .map(|normal| (normal_matrix * Vec3::from(normal)).normalize_or_zero())
+                    .unwrap_or(Vec3::Z),
+                color: colors
+                    .as_mut()
+                    .and_then(Iterator::next)
+                    .map(Vec3::from)
+                    .unwrap_or(Vec3::ONE),
+                texcoord: texcoords
+                    .as_mut()
+                    .and_then(Iterator::next)
+                    .map(Vec2::from)
+                    .unwrap_or(Vec2::ZERO),
+            })
+            .collect::<Vec<Vertex>>();
+
+        // Non-indexed primitives get sequential indices, since all meshes of a model must agree
+        let indices = match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect(),
+            None => (0..vertices.len() as u32).collect(),
+        };
+
+        meshes.push(Mesh::new(vertices, Some(indices)));
+        mesh_images.push(
+            primitive
+                .material()
+                .pbr_metallic_roughness()
+                .base_color_texture()
+                .map(|info| info.texture().source().index()),
+        );
+    }
+
+    Ok(())
+}
+
+fn load_embedded_image(
+    image: &gltf::Image,
+    buffers: &[gltf::buffer::Data],
+) -> Result<image::DynamicImage> {
+    use base64::Engine;
+
+    let bytes = match image.source() {
+        gltf::image::Source::View { view, .. } => {
+            let buffer = &buffers[view.buffer().index()];
+            buffer[view.offset()..view.offset() + view.length()].to_vec()
+        }
+        gltf::image::Source::Uri { uri, .. } => {
+            let (_, data) = uri
+                .strip_prefix("data:")
+                .and_then(|rest| rest.split_once(";base64,"))
+                .ok_or_else(|| eyre!("External glTF image cannot be loaded from memory: {uri}"))?;
+            base64::engine::general_purpose::STANDARD.decode(data)?
+        }
+    };
+    Ok(image::load_from_memory(&bytes)?)
+}
+
+fn convert_image(data: gltf::image::Data) -> Result<image::DynamicImage> {
+    use gltf::image::Format;
+
+    let gltf::image::Data { pixels, format, width, height } = data;
+    let image = match format {
+        Format::R8 => image::GrayImage::from_raw(width, height, pixels)
+            .map(image::DynamicImage::ImageLuma8),
+        Format::R8G8B8 => image::RgbImage::from_raw(width, height, pixels)
+            .map(image::DynamicImage::ImageRgb8),
+        Format::R8G8B8A8 => image::RgbaImage::from_raw(width, height, pixels)
+            .map(image::DynamicImage::ImageRgba8),
+        other => {
+            return Err(eyre!("Unsupported glTF image format: {other:?}"));
+        }
+    };
+    image.ok_or_eyre("glTF image data does not match its size")
+}
@@ -1,10 +1,19 @@
-use color_eyre::eyre::OptionExt;
+use color_eyre::eyre::{eyre, OptionExt};
 use color_eyre::Result;
 use crate::renderer::resources::shader::Shader;
 use crate::renderer::resources::shader_data::ShaderPushConstants;
 
 pub struct ComputeMaterial {
     pipeline: wgpu::ComputePipeline,
+    // Kept to rebuild the pipeline with a new shader
+    pipeline_layout: wgpu::PipelineLayout,
+    bind_group_count: u32,
+    frame_bind_group_index: Option<u32>,
+    entry_point: String,
+    workgroup_size: [u32; 3],
+    // Whether the workgroup size was set explicitly instead of reflected from the shader
+    explicit_workgroup_size: bool,
+    shader_path: Option<String>,
 }
 
 impl ComputeMaterial {
@@ -15,11 +24,52 @@ impl ComputeMaterial {
     pub fn get_pipeline(&self) -> &wgpu::ComputePipeline {
         &self.pipeline
     }
+
+    /// The number of bind groups in the pipeline layout, including the frame bind group
+    pub fn get_bind_group_count(&self) -> u32 {
+        self.bind_group_count
+    }
+
+    /// The group the frame bind group is expected at, if the material uses it
+    pub fn get_frame_bind_group_index(&self) -> Option<u32> {
+        self.frame_bind_group_index
+    }
+
+    /// The invocations per workgroup along each axis, used to derive workgroup counts
+    pub fn get_workgroup_size(&self) -> [u32; 3] {
+        self.workgroup_size
+    }
+
+    /// The file the material's shader was loaded from, if any
+    pub fn get_shader_path(&self) -> Option<&str> {
+        self.shader_path.as_deref()
+    }
+
+    /// Rebuilds the pipeline with `shader`.
+    /// The current pipeline is kept if the device rejects the new one.
+    pub async fn rebuild_with_shader(&mut self, shader: &Shader, device: &wgpu::Device) -> Result<()> {
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let pipeline = create_pipeline(&self.pipeline_layout, shader, &self.entry_point, device);
+        if let Some(error) = device.pop_error_scope().await {
+            return Err(eyre!("Failed to rebuild compute pipeline: {error}"));
+        }
+        self.pipeline = pipeline;
+        if !self.explicit_workgroup_size {
+            if let Some(workgroup_size) = shader.get_workgroup_size(&self.entry_point) {
+                self.workgroup_size = workgroup_size;
+            }
+        }
+        self.shader_path = shader.get_source_path().map(str::to_owned);
+        Ok(())
+    }
 }
 
 pub struct ComputeMaterialBuilder<'a> {
     shader: Option<Shader>,
     bind_group_layouts: Vec<&'a wgpu::BindGroupLayout>,
+    frame_bind_group_layout: Option<&'a wgpu::BindGroupLayout>,
+    entry_point: String,
+    workgroup_size: Option<[u32; 3]>,
 }
 
 impl<'a> ComputeMaterialBuilder<'a> {
@@ -27,6 +77,9 @@ impl<'a> ComputeMaterialBuilder<'a> {
         Self {
             shader: None,
             bind_group_layouts: Vec::new(),
+            frame_bind_group_layout: None,
+            entry_point: "main".to_owned(),
+            workgroup_size: None,
         }
     }
 
@@ -40,8 +93,37 @@ impl<'a> ComputeMaterialBuilder<'a> {
         self
     }
 
+    /// Binds the frame uniform in the group after the other bind group layouts.
+    pub fn with_frame_bind_group_layout(mut self, frame_bind_group_layout: &'a wgpu::BindGroupLayout) -> Self {
+        self.frame_bind_group_layout = Some(frame_bind_group_layout);
+        self
+    }
+
+    /// Defaults to `main`.
+    pub fn with_entry_point(mut self, entry_point: &str) -> Self {
+        self.entry_point = entry_point.to_owned();
+        self
+    }
+
+    /// Overrides the workgroup size reflected from the shader.
+    /// Required for shaders created from a descriptor, which cannot be reflected.
+    pub fn with_workgroup_size(mut self, workgroup_size: [u32; 3]) -> Self {
+        self.workgroup_size = Some(workgroup_size);
+        self
+    }
+
     pub fn build(mut self, device: &wgpu::Device) -> Result<ComputeMaterial> {
         let shader = self.shader.take().ok_or_eyre("No shader provided")?;
+        let workgroup_size = self.workgroup_size
+            .or(shader.get_workgroup_size(&self.entry_point))
+            .ok_or_eyre(format!(
+                "Unknown workgroup size of compute entry point {}, set it with with_workgroup_size",
+                self.entry_point,
+            ))?;
+        let frame_bind_group_index = self.frame_bind_group_layout.map(|layout| {
+            self.bind_group_layouts.push(layout);
+            self.bind_group_layouts.len() as u32 - 1
+        });
         let pipeline_layout =
             device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 label: Some("Compute Pipeline Layout"),
@@ -51,16 +133,32 @@ impl<'a> ComputeMaterialBuilder<'a> {
                     range: 0..size_of::<ShaderPushConstants>() as u32,
                 }],
             });
-        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: Some("Compute Pipeline"),
-            layout: Some(&pipeline_layout),
-            module: shader.get_module(),
-            entry_point: Some("main"),
-            compilation_options: Default::default(),
-            cache: None,
-        });
+        let pipeline = create_pipeline(&pipeline_layout, &shader, &self.entry_point, device);
         Ok(ComputeMaterial {
-            pipeline
+            pipeline,
+            pipeline_layout,
+            bind_group_count: self.bind_group_layouts.len() as u32,
+            frame_bind_group_index,
+            explicit_workgroup_size: self.workgroup_size.is_some(),
+            entry_point: self.entry_point,
+            workgroup_size,
+            shader_path: shader.get_source_path().map(str::to_owned),
         })
     }
 }
+
+fn create_pipeline(
+    pipeline_layout: &wgpu::PipelineLayout,
+    shader: &Shader,
+    entry_point: &str,
+    device: &wgpu::Device,
+) -> wgpu::ComputePipeline {
+    device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some("Compute Pipeline"),
+        layout: Some(pipeline_layout),
+        module: shader.get_module(),
+        entry_point: Some(entry_point),
+        compilation_options: Default::default(),
+        cache: None,
+    })
+}
@@ -1,4 +1,3 @@
-use color_eyre::eyre::OptionExt;
 
 /* A material is an abstraction over a shader */
 
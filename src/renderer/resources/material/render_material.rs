@@ -1,11 +1,68 @@
-use color_eyre::eyre::OptionExt;
+use std::rc::Rc;
+use color_eyre::eyre::{eyre, OptionExt};
 use color_eyre::Result;
 use crate::renderer::resources::shader::Shader;
-use crate::renderer::resources::shader_data::{ShaderPushConstants, ShaderVertex};
+use crate::renderer::resources::shader_data::{ShaderInstance, ShaderPushConstants, ShaderVertex};
 use crate::renderer::viewport::Viewport;
 
+/// How a material's output is combined with the color target
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlendMode {
+    /// Replaces the target
+    #[default]
+    Opaque,
+    /// Mixes with the target by the output's alpha
+    Alpha,
+    /// Like `Alpha` for outputs whose color is already multiplied by their alpha
+    Premultiplied,
+    /// Adds the output, weighted by its alpha, to the target
+    Additive,
+    /// Multiplies the target by the output
+    Multiply,
+}
+
+impl BlendMode {
+    fn get_blend_state(self) -> wgpu::BlendState {
+        // The target's alpha is kept by the modes that do not cover it
+        let keep_alpha = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::Zero,
+            dst_factor: wgpu::BlendFactor::One,
+            operation: wgpu::BlendOperation::Add,
+        };
+        match self {
+            Self::Opaque => wgpu::BlendState::REPLACE,
+            Self::Alpha => wgpu::BlendState::ALPHA_BLENDING,
+            Self::Premultiplied => wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+            Self::Additive => wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::SrcAlpha,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: keep_alpha,
+            },
+            Self::Multiply => wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::Dst,
+                    dst_factor: wgpu::BlendFactor::Zero,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: keep_alpha,
+            },
+        }
+    }
+}
+
 pub struct RenderMaterial {
-    pipeline: wgpu::RenderPipeline,
+    // Shared with the draw state cached by render objects
+    pipeline: Rc<wgpu::RenderPipeline>,
+    // Kept to rebuild the pipeline with a new shader or sample count
+    pipeline_layout: wgpu::PipelineLayout,
+    shader: Shader,
+    frame_bind_group_index: Option<u32>,
+    alpha_cutoff: f32,
+    settings: PipelineSettings,
+    shader_path: Option<String>,
 }
 
 impl RenderMaterial {
@@ -16,12 +73,102 @@ impl RenderMaterial {
     pub fn get_pipeline(&self) -> &wgpu::RenderPipeline {
         &self.pipeline
     }
+
+    pub(crate) fn get_shared_pipeline(&self) -> &Rc<wgpu::RenderPipeline> {
+        &self.pipeline
+    }
+
+    /// The group the frame bind group is expected at, if the material uses it
+    pub fn get_frame_bind_group_index(&self) -> Option<u32> {
+        self.frame_bind_group_index
+    }
+
+    /// The file the material's shader was loaded from, if any
+    pub fn get_shader_path(&self) -> Option<&str> {
+        self.shader_path.as_deref()
+    }
+
+    /// Rebuilds the pipeline with `shader`, keeping all other settings.
+    /// The current pipeline is kept if the device rejects the new one.
+    pub async fn rebuild_with_shader(&mut self, shader: &Shader, device: &wgpu::Device) -> Result<()> {
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let pipeline = create_pipeline(&self.pipeline_layout, shader, &self.settings, device);
+        if let Some(error) = device.pop_error_scope().await {
+            return Err(eyre!("Failed to rebuild render pipeline: {error}"));
+        }
+        self.pipeline = Rc::new(pipeline);
+        self.shader = shader.clone();
+        self.shader_path = shader.get_source_path().map(str::to_owned);
+        Ok(())
+    }
+
+    pub fn get_blend_mode(&self) -> BlendMode {
+        self.settings.blend_mode
+    }
+
+    /// Transparent materials are drawn after opaque ones, sorted back-to-front.
+    pub fn is_transparent(&self) -> bool {
+        self.settings.blend_mode != BlendMode::Opaque
+    }
+
+    /// Whether the material reads per-instance vertex data, see `RenderMaterialBuilder::with_instances`
+    pub fn is_instanced(&self) -> bool {
+        self.settings.vertex_buffer_layouts
+            .iter()
+            .any(|layout| layout.step_mode == wgpu::VertexStepMode::Instance)
+    }
+
+    pub fn get_alpha_cutoff(&self) -> f32 {
+        self.alpha_cutoff
+    }
+
+    pub fn get_sample_count(&self) -> u32 {
+        self.settings.sample_count
+    }
+
+    /// Rebuilds the pipeline for the viewport's new sample count,
+    /// unless the material was built with a fixed one.
+    pub fn set_viewport_sample_count(&mut self, sample_count: u32, device: &wgpu::Device) {
+        if !self.settings.follows_viewport_samples || self.settings.sample_count == sample_count {
+            return;
+        }
+        self.settings.sample_count = sample_count;
+        self.pipeline = Rc::new(create_pipeline(&self.pipeline_layout, &self.shader, &self.settings, device));
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PipelineSettings {
+    vertex_entry_point: String,
+    vertex_buffer_layouts: Vec<wgpu::VertexBufferLayout<'static>>,
+    fragment_entry_point: String,
+    color_format: wgpu::TextureFormat,
+    depth_target: bool,
+    sample_count: u32,
+    follows_viewport_samples: bool,
+    blend_mode: BlendMode,
+    cull_mode: Option<wgpu::Face>,
+    depth_compare: wgpu::CompareFunction,
+    depth_write_enabled: bool,
+    depth_bias: wgpu::DepthBiasState,
 }
 
 pub struct RenderMaterialBuilder<'a> {
     shader: Option<Shader>,
     bind_group_layouts: Vec<&'a wgpu::BindGroupLayout>,
+    frame_bind_group_layout: Option<&'a wgpu::BindGroupLayout>,
+    vertex_entry_point: String,
+    vertex_buffer_layouts: Vec<wgpu::VertexBufferLayout<'static>>,
+    fragment_entry_point: String,
+    color_format: Option<wgpu::TextureFormat>,
+    depth_target: bool,
+    sample_count: Option<u32>,
+    blend_mode: BlendMode,
+    alpha_cutoff: f32,
     cull_mode: Option<wgpu::Face>,
+    depth_compare: wgpu::CompareFunction,
+    depth_write_enabled: bool,
+    depth_bias: wgpu::DepthBiasState,
 }
 
 impl<'a> RenderMaterialBuilder<'a> {
@@ -29,7 +176,21 @@ impl<'a> RenderMaterialBuilder<'a> {
         Self {
             shader: None,
             bind_group_layouts: Vec::new(),
+            frame_bind_group_layout: None,
+            vertex_entry_point: "vs_main".to_owned(),
+            vertex_buffer_layouts: vec![ShaderVertex::BUFFER_LAYOUT],
+            fragment_entry_point: "fs_main".to_owned(),
+            color_format: None,
+            depth_target: true,
+            sample_count: None,
+            blend_mode: BlendMode::Opaque,
+            alpha_cutoff: 0.0,
             cull_mode: None,
+            // The scene pass always has a depth attachment,
+            // so materials without depth testing still declare the depth format
+            depth_compare: wgpu::CompareFunction::Always,
+            depth_write_enabled: false,
+            depth_bias: wgpu::DepthBiasState::default(),
         }
     }
 
@@ -43,13 +204,90 @@ impl<'a> RenderMaterialBuilder<'a> {
         self
     }
 
+    /// Binds the frame uniform in the group after the other bind group layouts.
+    pub fn with_frame_bind_group_layout(mut self, frame_bind_group_layout: &'a wgpu::BindGroupLayout) -> Self {
+        self.frame_bind_group_layout = Some(frame_bind_group_layout);
+        self
+    }
+
+    /// Selects the vertex shader function, `vs_main` by default.
+    pub fn with_vertex_entry_point(mut self, entry_point: &str) -> Self {
+        self.vertex_entry_point = entry_point.to_owned();
+        self
+    }
+
+    /// Reads `ShaderInstance`s next to the model's vertices, for instanced render objects.
+    pub fn with_instances(self) -> Self {
+        self.with_vertex_buffer_layouts(&[ShaderVertex::BUFFER_LAYOUT, ShaderInstance::BUFFER_LAYOUT])
+    }
+
+    /// Replaces the vertex buffers the material reads, which are the model's vertices by default.
+    /// Materials reading instance data list its buffer after the vertices.
+    pub fn with_vertex_buffer_layouts(mut self, layouts: &[wgpu::VertexBufferLayout<'static>]) -> Self {
+        self.vertex_buffer_layouts = layouts.into();
+        self
+    }
+
+    /// Selects the fragment shader function, `fs_main` by default.
+    pub fn with_fragment_entry_point(mut self, entry_point: &str) -> Self {
+        self.fragment_entry_point = entry_point.to_owned();
+        self
+    }
+
+    /// Overrides the format of the color target, which is the viewport's scene format by default.
+    pub fn with_color_format(mut self, format: wgpu::TextureFormat) -> Self {
+        self.color_format = Some(format);
+        self
+    }
+
+    /// For materials drawn in passes without a depth attachment, such as post-processing.
+    pub fn without_depth_target(mut self) -> Self {
+        self.depth_target = false;
+        self
+    }
+
+    /// Fixes the sample count. By default materials follow the viewport's sample count,
+    /// as they are drawn in the scene pass.
+    pub fn with_sample_count(mut self, sample_count: u32) -> Self {
+        self.sample_count = Some(sample_count);
+        self
+    }
+
+    pub fn with_blend_mode(mut self, blend_mode: BlendMode) -> Self {
+        self.blend_mode = blend_mode;
+        self
+    }
+
+    /// Discards fragments whose alpha is below `cutoff`, for cutouts such as foliage.
+    /// Shaders read it from `ShaderPushConstants::alpha_cutoff`.
+    pub fn with_alpha_cutoff(mut self, cutoff: f32) -> Self {
+        self.alpha_cutoff = cutoff;
+        self
+    }
+
     pub fn with_cull_mode(mut self, cull_mode: Option<wgpu::Face>) -> Self {
         self.cull_mode = cull_mode;
         self
     }
 
+    pub fn with_depth(
+        mut self,
+        compare: wgpu::CompareFunction,
+        write_enabled: bool,
+        bias: wgpu::DepthBiasState,
+    ) -> Self {
+        self.depth_compare = compare;
+        self.depth_write_enabled = write_enabled;
+        self.depth_bias = bias;
+        self
+    }
+
     pub fn build(mut self, device: &wgpu::Device, viewport: &Viewport) -> Result<RenderMaterial> {
         let shader = self.shader.take().ok_or_eyre("No shader provided")?;
+        let frame_bind_group_index = self.frame_bind_group_layout.map(|layout| {
+            self.bind_group_layouts.push(layout);
+            self.bind_group_layouts.len() as u32 - 1
+        });
         let pipeline_layout =
             device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 label: Some("Render Pipeline Layout"),
@@ -59,45 +297,80 @@ impl<'a> RenderMaterialBuilder<'a> {
                     range: 0..size_of::<ShaderPushConstants>() as u32,
                 }],
             });
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Render Pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader.get_module(),
-                entry_point: Some("vs_main"),
-                buffers: &[ShaderVertex::BUFFER_LAYOUT],
-                compilation_options: Default::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader.get_module(),
-                entry_point: Some("fs_main"),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: viewport.get_config().format,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: Default::default(),
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: self.cull_mode,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
-            cache: None,
-        });
+        let settings = PipelineSettings {
+            vertex_entry_point: self.vertex_entry_point,
+            vertex_buffer_layouts: self.vertex_buffer_layouts,
+            fragment_entry_point: self.fragment_entry_point,
+            color_format: self.color_format.unwrap_or(viewport.get_scene_format()),
+            depth_target: self.depth_target,
+            sample_count: self.sample_count.unwrap_or(viewport.get_sample_count()),
+            follows_viewport_samples: self.sample_count.is_none(),
+            blend_mode: self.blend_mode,
+            cull_mode: self.cull_mode,
+            depth_compare: self.depth_compare,
+            depth_write_enabled: self.depth_write_enabled,
+            depth_bias: self.depth_bias,
+        };
+        let pipeline = Rc::new(create_pipeline(&pipeline_layout, &shader, &settings, device));
         Ok(RenderMaterial {
             pipeline,
+            pipeline_layout,
+            frame_bind_group_index,
+            alpha_cutoff: self.alpha_cutoff,
+            settings,
+            shader_path: shader.get_source_path().map(str::to_owned),
+            shader,
         })
     }
 }
+
+fn create_pipeline(
+    pipeline_layout: &wgpu::PipelineLayout,
+    shader: &Shader,
+    settings: &PipelineSettings,
+    device: &wgpu::Device,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Render Pipeline"),
+        layout: Some(pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: shader.get_module(),
+            entry_point: Some(&settings.vertex_entry_point),
+            buffers: &settings.vertex_buffer_layouts,
+            compilation_options: Default::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader.get_module(),
+            entry_point: Some(&settings.fragment_entry_point),
+            targets: &[Some(wgpu::ColorTargetState {
+                format: settings.color_format,
+                blend: Some(settings.blend_mode.get_blend_state()),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: Default::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: settings.cull_mode,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: settings.depth_target.then(|| wgpu::DepthStencilState {
+            format: Viewport::DEPTH_FORMAT,
+            depth_write_enabled: settings.depth_write_enabled,
+            depth_compare: settings.depth_compare,
+            stencil: wgpu::StencilState::default(),
+            bias: settings.depth_bias,
+        }),
+        multisample: wgpu::MultisampleState {
+            count: settings.sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+        cache: None,
+    })
+}
@@ -1,4 +1,7 @@
+use std::collections::HashMap;
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
 use std::sync::atomic::AtomicU32;
+use glam::{Vec2, Vec3};
 use super::vertex::Vertex;
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -33,6 +36,14 @@ impl Mesh {
         }
     }
 
+    /// The corners of the box around the mesh's vertices with the lowest and highest coordinates
+    pub fn get_bounds(&self) -> (Vec3, Vec3) {
+        self.vertices.iter().fold(
+            (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+            |(min, max), vertex| (min.min(vertex.position), max.max(vertex.position)),
+        )
+    }
+
     pub fn new_triangle() -> Self {
         let vertices = vec![
             Vertex { // Bottom left
@@ -96,6 +107,237 @@ impl Mesh {
 
         Self::new(vertices, Some(indices))
     }
+
+    /* Primitives below are white, centered at the origin with +Y up and wound counter-clockwise.
+     * Texture coordinates start at the top left of each face or at the top of round shapes. */
+
+    /// A cube with edges of length `size` and one texture per face
+    pub fn new_cube(size: f32) -> Self {
+        // Normal, right and up direction of each face
+        let faces = [
+            (Vec3::Z, Vec3::X, Vec3::Y),
+            (Vec3::NEG_Z, Vec3::NEG_X, Vec3::Y),
+            (Vec3::X, Vec3::NEG_Z, Vec3::Y),
+            (Vec3::NEG_X, Vec3::Z, Vec3::Y),
+            (Vec3::Y, Vec3::X, Vec3::NEG_Z),
+            (Vec3::NEG_Y, Vec3::X, Vec3::Z),
+        ];
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        for (normal, right, up) in faces {
+            push_grid_indices(&mut indices, vertices.len() as u32, 1, 1, false, false);
+            push_grid_vertices(&mut vertices, normal * size * 0.5, right * size, up * size, normal, 1);
+        }
+        Self::new(vertices, Some(indices))
+    }
+
+    /// A square in the XZ plane facing +Y with edges of length `size`,
+    /// split into `subdivisions` by `subdivisions` cells
+    pub fn new_plane(size: f32, subdivisions: u32) -> Self {
+        let subdivisions = subdivisions.max(1);
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        push_grid_indices(&mut indices, 0, subdivisions, subdivisions, false, false);
+        push_grid_vertices(&mut vertices, Vec3::ZERO, Vec3::X * size, Vec3::NEG_Z * size, Vec3::Y, subdivisions);
+        Self::new(vertices, Some(indices))
+    }
+
+    /// A sphere of `segments` slices around the Y axis and `rings` stacks from pole to pole
+    pub fn new_uv_sphere(radius: f32, segments: u32, rings: u32) -> Self {
+        let (segments, rings) = (segments.max(3), rings.max(2));
+        let mut vertices = Vec::new();
+        for ring in 0..=rings {
+            let polar_angle = PI * ring as f32 / rings as f32;
+            for segment in 0..=segments {
+                let normal = get_sphere_normal(polar_angle, segment, segments);
+                let texcoord = Vec2::new(segment as f32 / segments as f32, ring as f32 / rings as f32);
+                vertices.push(new_vertex(normal * radius, normal, texcoord));
+            }
+        }
+        let mut indices = Vec::new();
+        push_grid_indices(&mut indices, 0, segments, rings, true, true);
+        Self::new(vertices, Some(indices))
+    }
+
+    /// A sphere made of evenly sized triangles, starting from an icosahedron
+    /// whose triangles are split into four `subdivisions` times.
+    /// Texcoords of triangles crossing the seam go past 1, so they rely on a repeating sampler.
+    pub fn new_icosphere(radius: f32, subdivisions: u32) -> Self {
+        // The corners of an icosahedron are those of three orthogonal golden rectangles
+        let t = (1.0 + 5.0_f32.sqrt()) / 2.0;
+        let mut positions = [
+            (-1.0, t, 0.0), (1.0, t, 0.0), (-1.0, -t, 0.0), (1.0, -t, 0.0),
+            (0.0, -1.0, t), (0.0, 1.0, t), (0.0, -1.0, -t), (0.0, 1.0, -t),
+            (t, 0.0, -1.0), (t, 0.0, 1.0), (-t, 0.0, -1.0), (-t, 0.0, 1.0),
+        ]
+            .map(|(x, y, z)| Vec3::new(x, y, z).normalize())
+            .to_vec();
+        let mut triangles = vec![
+            [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+            [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+            [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+            [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+        ];
+
+        for _ in 0..subdivisions {
+            // Edges shared by two triangles get a single midpoint
+            let mut midpoints = HashMap::new();
+            let mut get_midpoint = |a: u32, b: u32| {
+                *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                    positions.push((positions[a as usize] + positions[b as usize]).normalize());
+                    positions.len() as u32 - 1
+                })
+            };
+            triangles = triangles
+                .into_iter()
+                .flat_map(|[a, b, c]| {
+                    let (ab, bc, ca) = (get_midpoint(a, b), get_midpoint(b, c), get_midpoint(c, a));
+                    [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+                })
+                .collect();
+        }
+
+        let mut texcoords = positions
+            .iter()
+            .map(|normal| Vec2::new(
+                0.5 + normal.x.atan2(normal.z) / TAU,
+                normal.y.clamp(-1.0, 1.0).acos() / PI,
+            ))
+            .collect::<Vec<_>>();
+        // Vertices on the Y axis have no longitude of their own
+        let is_pole = |position: Vec3| position.x.abs() < 1e-6 && position.z.abs() < 1e-6;
+        // Triangles crossing the seam at -Z would stretch across the whole texture,
+        // so their vertices at the start of it are duplicated one texture width further
+        let mut wrapped = HashMap::new();
+        for triangle in &mut triangles {
+            let u = triangle
+                .iter()
+                .filter(|&&index| !is_pole(positions[index as usize]))
+                .map(|&index| texcoords[index as usize].x);
+            if u.clone().fold(f32::MIN, f32::max) - u.fold(f32::MAX, f32::min) <= 0.5 {
+                continue;
+            }
+            for index in triangle {
+                if texcoords[*index as usize].x < 0.5 && !is_pole(positions[*index as usize]) {
+                    *index = *wrapped.entry(*index).or_insert_with(|| {
+                        positions.push(positions[*index as usize]);
+                        texcoords.push(texcoords[*index as usize] + Vec2::X);
+                        positions.len() as u32 - 1
+                    });
+                }
+            }
+        }
+        // Each triangle at a pole gets a pole vertex between the longitudes of its other vertices
+        for triangle in &mut triangles {
+            let Some(corner) = triangle.iter().position(|&index| is_pole(positions[index as usize])) else {
+                continue;
+            };
+            let pole = triangle[corner] as usize;
+            let u = triangle
+                .iter()
+                .filter(|&&index| index as usize != pole)
+                .map(|&index| texcoords[index as usize].x)
+                .sum::<f32>() / 2.0;
+            positions.push(positions[pole]);
+            texcoords.push(Vec2::new(u, texcoords[pole].y));
+            triangle[corner] = positions.len() as u32 - 1;
+        }
+
+        let vertices = positions
+            .into_iter()
+            .zip(texcoords)
+            .map(|(normal, texcoord)| new_vertex(normal * radius, normal, texcoord))
+            .collect();
+        Self::new(vertices, Some(triangles.into_flattened()))
+    }
+
+    /// A cylinder along the Y axis with `segments` sides and closed ends
+    pub fn new_cylinder(radius: f32, height: f32, segments: u32) -> Self {
+        let segments = segments.max(3);
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        push_grid_indices(&mut indices, 0, segments, 1, false, false);
+        for (row, y) in [height * 0.5, -height * 0.5].into_iter().enumerate() {
+            for segment in 0..=segments {
+                let normal = get_sphere_normal(FRAC_PI_2, segment, segments);
+                let texcoord = Vec2::new(segment as f32 / segments as f32, row as f32);
+                vertices.push(new_vertex(normal * radius + Vec3::Y * y, normal, texcoord));
+            }
+        }
+        push_cap(&mut vertices, &mut indices, radius, height * 0.5, Vec3::Y, segments);
+        push_cap(&mut vertices, &mut indices, radius, -height * 0.5, Vec3::NEG_Y, segments);
+        Self::new(vertices, Some(indices))
+    }
+
+    /// A cone along the Y axis with its tip at the top, `segments` sides and a closed base
+    pub fn new_cone(radius: f32, height: f32, segments: u32) -> Self {
+        let segments = segments.max(3);
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        push_grid_indices(&mut indices, 0, segments, 1, true, false);
+        // The tip is repeated for every segment, so each side gets its own normal there
+        for (row, position_radius) in [0.0, radius].into_iter().enumerate() {
+            for segment in 0..=segments {
+                let around = get_sphere_normal(FRAC_PI_2, segment, segments);
+                let normal = (around * height + Vec3::Y * radius).normalize();
+                let position = around * position_radius + Vec3::Y * (0.5 - row as f32) * height;
+                let texcoord = Vec2::new(segment as f32 / segments as f32, row as f32);
+                vertices.push(new_vertex(position, normal, texcoord));
+            }
+        }
+        push_cap(&mut vertices, &mut indices, radius, -height * 0.5, Vec3::NEG_Y, segments);
+        Self::new(vertices, Some(indices))
+    }
+
+    /// A ring around the Y axis, `major_radius` from the center to the middle of its tube of `minor_radius`
+    pub fn new_torus(major_radius: f32, minor_radius: f32, major_segments: u32, minor_segments: u32) -> Self {
+        let (major_segments, minor_segments) = (major_segments.max(3), minor_segments.max(3));
+        let mut vertices = Vec::new();
+        // Rows go around the tube, starting on the outside and going down first
+        for row in 0..=minor_segments {
+            let tube_angle = TAU * row as f32 / minor_segments as f32;
+            for segment in 0..=major_segments {
+                let around = get_sphere_normal(FRAC_PI_2, segment, major_segments);
+                let normal = around * tube_angle.cos() + Vec3::NEG_Y * tube_angle.sin();
+                let texcoord = Vec2::new(
+                    segment as f32 / major_segments as f32,
+                    row as f32 / minor_segments as f32,
+                );
+                vertices.push(new_vertex(around * major_radius + normal * minor_radius, normal, texcoord));
+            }
+        }
+        let mut indices = Vec::new();
+        push_grid_indices(&mut indices, 0, major_segments, minor_segments, false, false);
+        Self::new(vertices, Some(indices))
+    }
+
+    /// A cylinder along the Y axis capped by half spheres, `height` tall including the caps.
+    /// Each cap has `rings` stacks.
+    pub fn new_capsule(radius: f32, height: f32, segments: u32, rings: u32) -> Self {
+        let (segments, rings) = (segments.max(3), rings.max(1));
+        let half_cylinder = (height * 0.5 - radius).max(0.0);
+        let total_height = 2.0 * (half_cylinder + radius);
+        let mut vertices = Vec::new();
+        // The equator is repeated at the bottom of the upper cap and the top of the lower one
+        for (cap_offset, first_angle) in [(half_cylinder, 0.0), (-half_cylinder, FRAC_PI_2)] {
+            for ring in 0..=rings {
+                let polar_angle = first_angle + FRAC_PI_2 * ring as f32 / rings as f32;
+                for segment in 0..=segments {
+                    let normal = get_sphere_normal(polar_angle, segment, segments);
+                    let position = normal * radius + Vec3::Y * cap_offset;
+                    let texcoord = Vec2::new(
+                        segment as f32 / segments as f32,
+                        (total_height * 0.5 - position.y) / total_height,
+                    );
+                    vertices.push(new_vertex(position, normal, texcoord));
+                }
+            }
+        }
+        let mut indices = Vec::new();
+        push_grid_indices(&mut indices, 0, segments, 2 * rings + 1, true, true);
+        Self::new(vertices, Some(indices))
+    }
+
 }
 
 impl PartialEq for Mesh {
@@ -103,3 +345,81 @@ impl PartialEq for Mesh {
         self.id == other.id
     }
 }
+
+
+fn new_vertex(position: Vec3, normal: Vec3, texcoord: Vec2) -> Vertex {
+    Vertex {
+        position,
+        normal,
+        color: Vec3::ONE,
+        texcoord,
+    }
+}
+
+/// Points `polar_angle` from +Y and a `segment`th of the way around it, starting at +Z
+fn get_sphere_normal(polar_angle: f32, segment: u32, segments: u32) -> Vec3 {
+    let azimuth = TAU * segment as f32 / segments as f32;
+    Vec3::new(
+        polar_angle.sin() * azimuth.sin(),
+        polar_angle.cos(),
+        polar_angle.sin() * azimuth.cos(),
+    )
+}
+
+/// Adds the vertices of a flat grid of `subdivisions` by `subdivisions` cells,
+/// row by row from the edge at `up` to the one opposite it.
+fn push_grid_vertices(vertices: &mut Vec<Vertex>, center: Vec3, right: Vec3, up: Vec3, normal: Vec3, subdivisions: u32) {
+    for row in 0..=subdivisions {
+        for column in 0..=subdivisions {
+            let texcoord = Vec2::new(column as f32, row as f32) / subdivisions as f32;
+            let position = center + right * (texcoord.x - 0.5) + up * (0.5 - texcoord.y);
+            vertices.push(new_vertex(position, normal, texcoord));
+        }
+    }
+}
+
+/// Adds the indices of a grid of `columns` by `rows` cells whose vertices start at `first_vertex`.
+/// Its vertices are stored row by row, going down and to the right when seen from the front.
+/// With `top_pole` or `bottom_pole`, the first or last row of vertices collapses into a point,
+/// so the degenerate triangles touching it are left out.
+fn push_grid_indices(
+    indices: &mut Vec<u32>,
+    first_vertex: u32,
+    columns: u32,
+    rows: u32,
+    top_pole: bool,
+    bottom_pole: bool,
+) {
+    for row in 0..rows {
+        for column in 0..columns {
+            let top_left = first_vertex + row * (columns + 1) + column;
+            let bottom_left = top_left + columns + 1;
+            if !(top_pole && row == 0) {
+                indices.extend([top_left, bottom_left, top_left + 1]);
+            }
+            if !(bottom_pole && row == rows - 1) {
+                indices.extend([top_left + 1, bottom_left, bottom_left + 1]);
+            }
+        }
+    }
+}
+
+/// Adds a disk at height `y` facing `normal`, which must be +Y or -Y
+fn push_cap(vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>, radius: f32, y: f32, normal: Vec3, segments: u32) {
+    let center = vertices.len() as u32;
+    vertices.push(new_vertex(Vec3::Y * y, normal, Vec2::splat(0.5)));
+    for segment in 0..=segments {
+        let around = get_sphere_normal(FRAC_PI_2, segment, segments);
+        // Seen from the front, +Z is at the bottom of the top cap and at the top of the bottom one
+        let texcoord = Vec2::new(0.5 + around.x * 0.5, 0.5 + around.z * 0.5 * normal.y);
+        vertices.push(new_vertex(around * radius + Vec3::Y * y, normal, texcoord));
+    }
+    for segment in 0..segments {
+        let (current, next) = (center + 1 + segment, center + 2 + segment);
+        if normal.y > 0.0 {
+            indices.extend([center, current, next]);
+        } else {
+            indices.extend([center, next, current]);
+        }
+    }
+}
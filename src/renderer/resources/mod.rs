@@ -5,20 +5,60 @@ pub mod model;
 pub mod material;
 pub mod texture;
 pub mod shader_data;
+pub mod gltf_loader;
+pub mod obj_loader;
+pub mod file;
+pub mod registry;
+pub mod asset_loader;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod shader_watcher;
 
 use color_eyre::eyre::{OptionExt, Result, eyre};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use super::viewport::Viewport;
 use shader::Shader;
 use model::FullscreenQuad;
-use crate::renderer::compute_object::ComputeObject;
+use gltf_loader::GltfAsset;
+use obj_loader::ObjAsset;
+use registry::{NameCollision, ResourceHandle};
+use asset_loader::{AssetLoader, AssetState, DecodedAsset};
+use crate::renderer::compute_object::{ComputeDispatch, ComputeObject};
+use crate::renderer::particles;
+use crate::renderer::post_process;
 use crate::renderer::render_object::RenderObject;
 use crate::renderer::resources::material::compute_material::ComputeMaterial;
-use crate::renderer::resources::material::render_material::RenderMaterial;
-
-const SINGLE_TEXTURE_BIND_GROUP_LAYOUT_NAME: &str = "single texture";
-const CAMERA_BIND_GROUP_LAYOUT_NAME: &str = "camera";
-const COMPUTE_STORAGE_BIND_GROUP_LAYOUT_NAME: &str = "compute storage";
+use crate::renderer::resources::material::render_material::{BlendMode, RenderMaterial};
+use crate::renderer::resources::shader_data::ShaderParticle;
+
+pub const SINGLE_TEXTURE_BIND_GROUP_LAYOUT_NAME: &str = "single texture";
+pub const CAMERA_BIND_GROUP_LAYOUT_NAME: &str = "camera";
+pub const COMPUTE_STORAGE_BIND_GROUP_LAYOUT_NAME: &str = "compute storage";
+/// Layout of the per-frame uniform, see `shader_data::ShaderFrameUniform`
+pub const FRAME_BIND_GROUP_LAYOUT_NAME: &str = "frame";
+/// Input texture, filtering sampler and auxiliary texture of post-processing passes
+pub const POST_PROCESS_INPUT_BIND_GROUP_LAYOUT_NAME: &str = "post process input";
+/// Parameters of a post effect, see `shader_data::ShaderPostEffectUniform`
+pub const POST_EFFECT_BIND_GROUP_LAYOUT_NAME: &str = "post effect";
+/// Particle storage buffer and emitter uniform of particle simulations
+pub const PARTICLE_SIMULATION_BIND_GROUP_LAYOUT_NAME: &str = "particle simulation";
+/// Emitter uniform read when drawing particles, see `shader_data::ShaderParticleEmitterUniform`
+pub const PARTICLE_EMITTER_BIND_GROUP_LAYOUT_NAME: &str = "particle emitter";
+/// Drawn in place of textures whose asset is still loading or failed to load
+pub const PLACEHOLDER_TEXTURE_NAME: &str = "white";
+
+pub type ModelHandle = ResourceHandle<model::Model>;
+pub type TextureHandle = ResourceHandle<texture::Texture>;
+pub type RenderMaterialHandle = ResourceHandle<RenderMaterial>;
+pub type ComputeMaterialHandle = ResourceHandle<ComputeMaterial>;
+
+/// The resources an asset was registered as
+#[derive(Debug, Clone)]
+pub struct AssetHandles {
+    pub model: ModelHandle,
+    /// In the order of the asset's images or diffuse maps
+    pub textures: Vec<TextureHandle>,
+}
 
 /// Global resources
 pub struct Resources {
@@ -27,6 +67,9 @@ pub struct Resources {
     render_materials: HashMap<String, RenderMaterial>,
     compute_materials: HashMap<String, ComputeMaterial>,
     fullscreen_quad: FullscreenQuad,
+    asset_loader: AssetLoader,
+    // Changes whenever a model, texture or render material is added, removed or rebuilt
+    generation: u64,
 
     // wgpu resources
     samplers : HashMap<String, wgpu::Sampler>,
@@ -41,10 +84,11 @@ impl Resources {
     ) -> Result<Self> {
         let bind_group_layouts = create_default_bind_group_layouts(device);
         let samplers = create_default_samplers(device)?;
-        let render_materials = create_default_render_materials(&bind_group_layouts, device, viewport).await?;
-        let compute_materials = create_default_compute_materials(&bind_group_layouts, device).await?;
+        let shaders = DefaultShaders::load(device).await?;
+        let render_materials = create_default_render_materials(&shaders, &bind_group_layouts, device, viewport)?;
+        let compute_materials = create_default_compute_materials(&shaders, &bind_group_layouts, device)?;
         let models = create_default_models(device)?;
-        let fullscreen_quad = FullscreenQuad::new(viewport, device, queue)?;
+        let fullscreen_quad = FullscreenQuad::new(viewport, device)?;
         let mut result = Self {
             models,
             textures: HashMap::new(),
@@ -53,6 +97,8 @@ impl Resources {
             samplers,
             bind_group_layouts,
             fullscreen_quad,
+            asset_loader: AssetLoader::new(),
+            generation: next_generation(),
         };
         // Default textures depends on the bind group layouts and samplers
         result.textures = create_default_textures(device, queue, &result)?;
@@ -65,16 +111,11 @@ impl Resources {
         texture_name: &str,
         model_name: &str,
     ) -> Result<RenderObject> {
-        let material_exists = self.render_materials.contains_key(material_name);
-        let texture_exists = self.textures.contains_key(texture_name);
-        let model_exists = self.models.contains_key(model_name);
+        // Models of assets that are still loading may be referenced already
+        let model_exists = self.models.contains_key(model_name)
+            || self.asset_loader.is_unavailable(model_name);
 
-        if !material_exists {
-            return Err(eyre!("Material not found: {}", material_name));
-        }
-        if !texture_exists {
-            return Err(eyre!("Texture not found: {}", texture_name));
-        }
+        self.check_material_and_texture(material_name, texture_name)?;
         if !model_exists {
             return Err(eyre!("Model not found: {}", model_name));
         }
@@ -86,6 +127,32 @@ impl Resources {
         ))
     }
 
+    /// Fails unless the render material exists and the texture exists or is being loaded.
+    pub fn check_material_and_texture(&self, material_name: &str, texture_name: &str) -> Result<()> {
+        let material_exists = self.render_materials.contains_key(material_name);
+        let texture_exists = self.textures.contains_key(texture_name)
+            || self.asset_loader.is_unavailable(texture_name);
+
+        if !material_exists {
+            return Err(eyre!("Material not found: {}", material_name));
+        }
+        if !texture_exists {
+            return Err(eyre!("Texture not found: {}", texture_name));
+        }
+        Ok(())
+    }
+
+    pub fn create_compute_object(
+        &self,
+        material_name: &str,
+        dispatch: ComputeDispatch,
+    ) -> Result<ComputeObject> {
+        if !self.compute_materials.contains_key(material_name) {
+            return Err(eyre!("Material not found: {}", material_name));
+        }
+        Ok(ComputeObject::new(material_name.to_owned(), dispatch))
+    }
+
     pub fn create_compute_object_with_output_texture(
         &self,
         material_name: &str,
@@ -100,7 +167,7 @@ impl Resources {
         }
 
         ComputeObject::new_with_output_texture(
-            "basic compute".to_owned(),
+            material_name.to_owned(),
             texture_width,
             texture_height,
             device,
@@ -108,6 +175,265 @@ impl Resources {
         )
     }
 
+    pub fn add_model(
+        &mut self,
+        name: &str,
+        model: model::Model,
+        on_collision: NameCollision,
+    ) -> Result<ModelHandle> {
+        let name = registry::register(&mut self.models, "model", name, model, on_collision)?;
+        self.generation = next_generation();
+        Ok(ResourceHandle::new(name))
+    }
+
+    pub fn add_texture(
+        &mut self,
+        name: &str,
+        texture: texture::Texture,
+        on_collision: NameCollision,
+    ) -> Result<TextureHandle> {
+        let name = registry::register(&mut self.textures, "texture", name, texture, on_collision)?;
+        self.generation = next_generation();
+        Ok(ResourceHandle::new(name))
+    }
+
+    pub fn add_render_material(
+        &mut self,
+        name: &str,
+        material: RenderMaterial,
+        on_collision: NameCollision,
+    ) -> Result<RenderMaterialHandle> {
+        let name = registry::register(&mut self.render_materials, "render material", name, material, on_collision)?;
+        self.generation = next_generation();
+        Ok(ResourceHandle::new(name))
+    }
+
+    pub fn add_compute_material(
+        &mut self,
+        name: &str,
+        material: ComputeMaterial,
+        on_collision: NameCollision,
+    ) -> Result<ComputeMaterialHandle> {
+        registry::register(&mut self.compute_materials, "compute material", name, material, on_collision)
+            .map(ResourceHandle::new)
+    }
+
+    /* Objects still referencing a removed resource fail to render until it is registered again. */
+
+    pub fn remove_model(&mut self, name: &str) -> Option<model::Model> {
+        let removed = self.models.remove(name);
+        if removed.is_some() {
+            self.generation = next_generation();
+        }
+        removed
+    }
+
+    pub fn remove_texture(&mut self, name: &str) -> Option<texture::Texture> {
+        let removed = self.textures.remove(name);
+        if removed.is_some() {
+            self.generation = next_generation();
+        }
+        removed
+    }
+
+    pub fn remove_render_material(&mut self, name: &str) -> Option<RenderMaterial> {
+        let removed = self.render_materials.remove(name);
+        if removed.is_some() {
+            self.generation = next_generation();
+        }
+        removed
+    }
+
+    pub fn remove_compute_material(&mut self, name: &str) -> Option<ComputeMaterial> {
+        self.compute_materials.remove(name)
+    }
+
+    /// Registers the meshes of a glTF asset as a single model under `name`.
+    /// Its images are registered as textures named `<model name>/<image index>`,
+    /// and meshes are drawn with their material's base color texture.
+    pub fn add_gltf_asset(
+        &mut self,
+        name: &str,
+        asset: GltfAsset,
+        on_collision: NameCollision,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<AssetHandles> {
+        let textures = asset.images
+            .iter()
+            .enumerate()
+            .map(|(index, image)| {
+                let texture = texture::Texture::new_from_image(image, &format!("{name}/{index}"), device, queue, self)?;
+                Ok((index.to_string(), texture))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let mesh_texture_keys = asset.mesh_images
+            .iter()
+            .map(|image| image.map(|index| index.to_string()))
+            .collect();
+        let model = model::Model::new(asset.meshes, device)?;
+        self.add_asset(name, model, textures, mesh_texture_keys, on_collision)
+    }
+
+    /// Registers the model under `name` and each material's diffuse map as `<model name>/<material>`.
+    /// Meshes are drawn with the diffuse map of their material, if it has one.
+    pub fn add_obj_asset(
+        &mut self,
+        name: &str,
+        asset: ObjAsset,
+        on_collision: NameCollision,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+    ) -> Result<AssetHandles> {
+        let textures = asset.diffuse_maps
+            .into_iter()
+            .map(|(material, bytes)| {
+                let texture = texture::Texture::new_from_bytes(&bytes, &format!("{name}/{material}"), device, queue, self)?;
+                Ok((material, texture))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let model = model::Model::new(asset.meshes, device)?;
+        self.add_asset(name, model, textures, asset.mesh_materials, on_collision)
+    }
+
+    /// Registers `model` under `name` and `textures` under `<model name>/<key>`.
+    /// Each mesh is assigned the texture whose key is in `mesh_texture_keys`, if any.
+    /// Nothing is registered if any name collides under `NameCollision::Error`.
+    fn add_asset(
+        &mut self,
+        name: &str,
+        model: model::Model,
+        textures: Vec<(String, texture::Texture)>,
+        mesh_texture_keys: Vec<Option<String>>,
+        on_collision: NameCollision,
+    ) -> Result<AssetHandles> {
+        registry::check_available(&self.models, "model", name, on_collision)?;
+        for (key, _) in &textures {
+            registry::check_available(&self.textures, "texture", &format!("{name}/{key}"), on_collision)?;
+        }
+
+        let model_name = registry::register(&mut self.models, "model", name, model, on_collision)?;
+        let mut texture_names = HashMap::new();
+        let textures = textures
+            .into_iter()
+            .map(|(key, texture)| {
+                let texture_name = format!("{model_name}/{key}");
+                let texture_name = registry::register(&mut self.textures, "texture", &texture_name, texture, on_collision)?;
+                texture_names.insert(key, texture_name.clone());
+                Ok(ResourceHandle::new(texture_name))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        // Keys without a texture, such as materials whose diffuse map failed to load, leave the mesh to the render object
+        let mesh_texture_names = mesh_texture_keys
+            .iter()
+            .map(|key| key.as_ref().and_then(|key| texture_names.get(key)).cloned())
+            .collect();
+        self.models
+            .get_mut(&model_name)
+            .expect("The model was just registered")
+            .set_mesh_texture_names(mesh_texture_names)?;
+        self.generation = next_generation();
+        Ok(AssetHandles {
+            model: ResourceHandle::new(model_name),
+            textures,
+        })
+    }
+
+    /// Uploads the assets the asset loader finished since the last call and registers them,
+    /// replacing resources of the same name.
+    pub fn process_loaded_assets(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        for (name, result) in self.asset_loader.take_finished() {
+            let result = result.and_then(|asset| match asset {
+                DecodedAsset::Texture(image) => {
+                    let texture = texture::Texture::new_from_image(&image, &name, device, queue, self)?;
+                    self.add_texture(&name, texture, NameCollision::Replace).map(|_| ())
+                }
+                DecodedAsset::Obj(asset) => self.add_obj_asset(&name, asset, NameCollision::Replace, device, queue).map(|_| ()),
+                DecodedAsset::Gltf(asset) => self.add_gltf_asset(&name, asset, NameCollision::Replace, device, queue).map(|_| ()),
+            });
+            let state = match result {
+                Ok(()) => AssetState::Loaded,
+                Err(report) => {
+                    log::error!("Failed to load asset {name}: {report}");
+                    AssetState::Failed(report.to_string())
+                }
+            };
+            self.asset_loader.set_state(&name, state);
+        }
+    }
+
+    /// Recompiles the changed WGSL files and rebuilds the materials whose shaders they are.
+    /// Materials keep their last good pipeline if compilation fails.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn reload_shaders(&mut self, changed_paths: &[String], device: &wgpu::Device) {
+        use shader_watcher::is_shader_source;
+
+        for path in changed_paths {
+            let uses_shader = |shader_path: Option<&str>| {
+                shader_path.is_some_and(|shader_path| is_shader_source(shader_path, path))
+            };
+            let render_materials = self.render_materials
+                .iter_mut()
+                .filter(|(_, material)| uses_shader(material.get_shader_path()))
+                .collect::<Vec<_>>();
+            let compute_materials = self.compute_materials
+                .iter_mut()
+                .filter(|(_, material)| uses_shader(material.get_shader_path()))
+                .collect::<Vec<_>>();
+            if render_materials.is_empty() && compute_materials.is_empty() {
+                continue;
+            }
+
+            let shader = std::fs::read_to_string(file::get_native_path(path))
+                .map_err(Into::into)
+                .and_then(|source| Shader::new_from_wgsl(&source, path, device));
+            let shader = match shader {
+                Ok(shader) => shader,
+                Err(report) => {
+                    log::error!("Failed to compile {path}, keeping the previous pipelines:\n{report}");
+                    continue;
+                }
+            };
+
+            self.generation = next_generation();
+            for (name, material) in render_materials {
+                match pollster::block_on(material.rebuild_with_shader(&shader, device)) {
+                    Ok(()) => log::info!("Reloaded render material {name} from {path}"),
+                    Err(report) => log::error!("Failed to reload render material {name}: {report}"),
+                }
+            }
+            for (name, material) in compute_materials {
+                match pollster::block_on(material.rebuild_with_shader(&shader, device)) {
+                    Ok(()) => log::info!("Reloaded compute material {name} from {path}"),
+                    Err(report) => log::error!("Failed to reload compute material {name}: {report}"),
+                }
+            }
+        }
+    }
+
+    /// Rebuilds the render materials that follow the viewport's sample count.
+    pub fn set_viewport_sample_count(&mut self, sample_count: u32, device: &wgpu::Device) {
+        for material in self.render_materials.values_mut() {
+            material.set_viewport_sample_count(sample_count, device);
+        }
+        self.generation = next_generation();
+    }
+
+    /// Changes whenever a model, texture or render material is added, removed or rebuilt,
+    /// so state resolved from them can be reused while it stays the same.
+    /// Generations are unique across all `Resources`.
+    pub fn get_generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn get_asset_loader(&self) -> &AssetLoader {
+        &self.asset_loader
+    }
+
+    pub fn get_asset_loader_mut(&mut self) -> &mut AssetLoader {
+        &mut self.asset_loader
+    }
+
     pub fn get_model(&self, name: &str) -> Result<&model::Model> {
         self.models.get(name).ok_or_eyre(format!("Failed to get model: {name}"))
     }
@@ -116,6 +442,24 @@ impl Resources {
         self.textures.get(name).ok_or_eyre(format!("Failed to get texture: {name}"))
     }
 
+    /// Like `get_texture`, but returns the placeholder texture while the texture's asset is unavailable.
+    pub fn get_texture_or_placeholder(&self, name: &str) -> Result<&texture::Texture> {
+        match self.textures.get(name) {
+            Some(texture) => Ok(texture),
+            None if self.asset_loader.is_unavailable(name) => self.get_texture(PLACEHOLDER_TEXTURE_NAME),
+            None => self.get_texture(name),
+        }
+    }
+
+    /// Like `get_model`, but returns `None` while the model's asset is unavailable.
+    pub fn get_model_if_loaded(&self, name: &str) -> Result<Option<&model::Model>> {
+        match self.models.get(name) {
+            Some(model) => Ok(Some(model)),
+            None if self.asset_loader.is_unavailable(name) => Ok(None),
+            None => self.get_model(name).map(Some),
+        }
+    }
+
     pub fn get_render_material(&self, name: &str) -> Result<&RenderMaterial> {
         self.render_materials.get(name).ok_or_eyre(format!("Failed to get render material: {name}"))
     }
@@ -124,6 +468,22 @@ impl Resources {
         self.compute_materials.get(name).ok_or_eyre(format!("Failed to get compute material: {name}"))
     }
 
+    pub fn get_model_handle(&self, name: &str) -> Result<ModelHandle> {
+        self.get_model(name).map(|_| ResourceHandle::new(name.to_owned()))
+    }
+
+    pub fn get_texture_handle(&self, name: &str) -> Result<TextureHandle> {
+        self.get_texture(name).map(|_| ResourceHandle::new(name.to_owned()))
+    }
+
+    pub fn get_render_material_handle(&self, name: &str) -> Result<RenderMaterialHandle> {
+        self.get_render_material(name).map(|_| ResourceHandle::new(name.to_owned()))
+    }
+
+    pub fn get_compute_material_handle(&self, name: &str) -> Result<ComputeMaterialHandle> {
+        self.get_compute_material(name).map(|_| ResourceHandle::new(name.to_owned()))
+    }
+
     pub fn get_sampler(&self, name: &str) -> Result<&wgpu::Sampler> {
         self.samplers.get(name).ok_or_eyre(format!("Failed to get sampler: {name}"))
     }
@@ -141,6 +501,12 @@ impl Resources {
     }
 }
 
+static GENERATION_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+fn next_generation() -> u64 {
+    GENERATION_COUNTER.fetch_add(1, Ordering::Relaxed)
+}
+
 fn create_default_models(
     device: &wgpu::Device,
 ) -> Result<HashMap<String, model::Model>> {
@@ -155,6 +521,21 @@ fn create_default_models(
         device,
     )?);
 
+    // Primitives fitting into a unit cube, for debugging and placeholder geometry
+    let primitives = [
+        ("cube", mesh::Mesh::new_cube(1.0)),
+        ("plane", mesh::Mesh::new_plane(1.0, 8)),
+        ("uv sphere", mesh::Mesh::new_uv_sphere(0.5, 32, 16)),
+        ("icosphere", mesh::Mesh::new_icosphere(0.5, 3)),
+        ("cylinder", mesh::Mesh::new_cylinder(0.5, 1.0, 32)),
+        ("cone", mesh::Mesh::new_cone(0.5, 1.0, 32)),
+        ("torus", mesh::Mesh::new_torus(0.35, 0.15, 32, 16)),
+        ("capsule", mesh::Mesh::new_capsule(0.25, 1.0, 32, 8)),
+    ];
+    for (name, mesh) in primitives {
+        result.insert(name.to_owned(), model::Model::new(vec![mesh], device)?);
+    }
+
     Ok(result)
 }
 
@@ -196,25 +577,133 @@ fn create_default_textures(
     Ok(result)
 }
 
-async fn create_default_render_materials(
+/// Shaders of the default materials
+struct DefaultShaders {
+    basic: Shader,
+    particles: Shader,
+    post_process: Shader,
+    basic_compute: Shader,
+    particle_simulation: Shader,
+}
+
+impl DefaultShaders {
+    /// Loads the shaders concurrently, so on web their fetches overlap.
+    async fn load(device: &wgpu::Device) -> Result<Self> {
+        let (basic, particles, post_process, basic_compute, particle_simulation) = futures::try_join!(
+            Shader::new_from_file("shaders-compiled/basic.spv", device),
+            Shader::new_from_file("shaders-compiled/particles.spv", device),
+            Shader::new_from_file("shaders-compiled/post_process.spv", device),
+            Shader::new_from_file("shaders-compiled/basic_compute.spv", device),
+            Shader::new_from_file("shaders-compiled/particle_simulation.spv", device),
+        )?;
+        Ok(Self {
+            basic,
+            particles,
+            post_process,
+            basic_compute,
+            particle_simulation,
+        })
+    }
+}
+
+fn create_default_render_materials(
+    shaders: &DefaultShaders,
     bind_group_layouts: &HashMap<String, wgpu::BindGroupLayout>,
     device: &wgpu::Device,
     viewport: &Viewport<'_>,
 ) -> Result<HashMap<String, RenderMaterial>> {
     let mut result = HashMap::new();
 
-    result.insert("basic".to_owned(), RenderMaterial::builder()
+    let basic_builder = || RenderMaterial::builder()
         .with_bind_group_layouts(&[
             bind_group_layouts.get(SINGLE_TEXTURE_BIND_GROUP_LAYOUT_NAME).unwrap(),
             bind_group_layouts.get(CAMERA_BIND_GROUP_LAYOUT_NAME).unwrap(),
         ])
-        .with_shader(Shader::new_from_file("shaders-compiled/basic.spv", device).await?)
+        .with_frame_bind_group_layout(bind_group_layouts.get(FRAME_BIND_GROUP_LAYOUT_NAME).unwrap())
+        .with_shader(shaders.basic.clone());
+
+    result.insert("basic".to_owned(), basic_builder()
+        .with_depth(wgpu::CompareFunction::LessEqual, true, wgpu::DepthBiasState::default())
         .build(device, viewport)?);
 
+    // Transparent objects are tested against the depth but do not hide what is behind them
+    result.insert("basic alpha".to_owned(), basic_builder()
+        .with_blend_mode(BlendMode::Alpha)
+        .with_depth(wgpu::CompareFunction::LessEqual, false, wgpu::DepthBiasState::default())
+        .build(device, viewport)?);
+
+    result.insert("basic cutout".to_owned(), basic_builder()
+        .with_alpha_cutoff(0.5)
+        .with_depth(wgpu::CompareFunction::LessEqual, true, wgpu::DepthBiasState::default())
+        .build(device, viewport)?);
+
+    result.insert("basic instanced".to_owned(), basic_builder()
+        .with_instances()
+        .with_vertex_entry_point("vs_instanced")
+        .with_depth(wgpu::CompareFunction::LessEqual, true, wgpu::DepthBiasState::default())
+        .build(device, viewport)?);
+
+    // For scattered foliage
+    result.insert("basic instanced cutout".to_owned(), basic_builder()
+        .with_instances()
+        .with_vertex_entry_point("vs_instanced")
+        .with_alpha_cutoff(0.5)
+        .with_depth(wgpu::CompareFunction::LessEqual, true, wgpu::DepthBiasState::default())
+        .build(device, viewport)?);
+
+    for (name, blend_mode) in [
+        (particles::PARTICLES_MATERIAL_NAME, BlendMode::Alpha),
+        (particles::ADDITIVE_PARTICLES_MATERIAL_NAME, BlendMode::Additive),
+    ] {
+        result.insert(name.to_owned(), RenderMaterial::builder()
+            .with_bind_group_layouts(&[
+                bind_group_layouts.get(PARTICLE_EMITTER_BIND_GROUP_LAYOUT_NAME).unwrap(),
+                bind_group_layouts.get(CAMERA_BIND_GROUP_LAYOUT_NAME).unwrap(),
+            ])
+            .with_frame_bind_group_layout(bind_group_layouts.get(FRAME_BIND_GROUP_LAYOUT_NAME).unwrap())
+            .with_shader(shaders.particles.clone())
+            .with_vertex_buffer_layouts(&[ShaderParticle::BUFFER_LAYOUT])
+            .with_blend_mode(blend_mode)
+            .with_depth(wgpu::CompareFunction::LessEqual, false, wgpu::DepthBiasState::default())
+            .build(device, viewport)?);
+    }
+
+    let post_process_materials = [
+        (post_process::OUTPUT_MATERIAL_NAME, "fs_output"),
+        (post_process::COMPUTE_BLIT_MATERIAL_NAME, "fs_compute_blit"),
+        (post_process::TONEMAP_MATERIAL_NAME, "fs_tonemap"),
+        (post_process::GAMMA_MATERIAL_NAME, "fs_gamma"),
+        (post_process::VIGNETTE_MATERIAL_NAME, "fs_vignette"),
+        (post_process::COLOR_GRADING_MATERIAL_NAME, "fs_color_grading"),
+        (post_process::FXAA_MATERIAL_NAME, "fs_fxaa"),
+        (post_process::BLOOM_MATERIAL_NAME, "fs_bloom"),
+    ];
+    for (name, entry_point) in post_process_materials {
+        let mut builder = RenderMaterial::builder()
+            .with_bind_group_layouts(&[
+                bind_group_layouts.get(POST_PROCESS_INPUT_BIND_GROUP_LAYOUT_NAME).unwrap(),
+                bind_group_layouts.get(POST_EFFECT_BIND_GROUP_LAYOUT_NAME).unwrap(),
+            ])
+            .with_frame_bind_group_layout(bind_group_layouts.get(FRAME_BIND_GROUP_LAYOUT_NAME).unwrap())
+            .with_shader(shaders.post_process.clone())
+            .with_fragment_entry_point(entry_point)
+            .without_depth_target();
+        // Only the last pass writes to the viewport, the others to HDR targets.
+        // The compute blit draws into the scene's targets, so it follows their sample count.
+        if name == post_process::OUTPUT_MATERIAL_NAME {
+            builder = builder.with_color_format(*viewport.get_surface_format());
+        }
+        if name != post_process::COMPUTE_BLIT_MATERIAL_NAME {
+            builder = builder.with_sample_count(1);
+        }
+        result.insert(name.to_owned(), builder.build(device, viewport)?);
+    }
+
     Ok(result)
 }
 
-async fn create_default_compute_materials(
+fn create_default_compute_materials(
+    shaders: &DefaultShaders,
     bind_group_layouts: &HashMap<String, wgpu::BindGroupLayout>,
     device: &wgpu::Device,
 ) -> Result<HashMap<String, ComputeMaterial>> {
@@ -224,7 +713,17 @@ async fn create_default_compute_materials(
         .with_bind_group_layouts(&[
             bind_group_layouts.get("compute storage").unwrap(),
         ])
-        .with_shader(Shader::new_from_file("shaders-compiled/basic_compute.spv", device).await?)
+        .with_frame_bind_group_layout(bind_group_layouts.get(FRAME_BIND_GROUP_LAYOUT_NAME).unwrap())
+        .with_shader(shaders.basic_compute.clone())
+        .build(device)?);
+
+    result.insert(particles::PARTICLE_SIMULATION_MATERIAL_NAME.to_owned(), ComputeMaterial::builder()
+        .with_bind_group_layouts(&[
+            bind_group_layouts.get(PARTICLE_SIMULATION_BIND_GROUP_LAYOUT_NAME).unwrap(),
+        ])
+        .with_frame_bind_group_layout(bind_group_layouts.get(FRAME_BIND_GROUP_LAYOUT_NAME).unwrap())
+        .with_shader(shaders.particle_simulation.clone())
+        .with_entry_point("simulate")
         .build(device)?);
 
     Ok(result)
@@ -244,6 +743,16 @@ fn create_default_samplers(
         label: Some("Nearest Sampler"),
         ..Default::default()
     }));
+    result.insert("linear".to_owned(), device.create_sampler(&wgpu::SamplerDescriptor {
+        address_mode_u: wgpu::AddressMode::ClampToEdge,
+        address_mode_v: wgpu::AddressMode::ClampToEdge,
+        address_mode_w: wgpu::AddressMode::ClampToEdge,
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        mipmap_filter: wgpu::FilterMode::Nearest,
+        label: Some("Linear Sampler"),
+        ..Default::default()
+    }));
     Ok(result)
 }
 
@@ -295,7 +804,7 @@ fn create_default_bind_group_layouts(device: &wgpu::Device) -> HashMap<String, w
                 visibility: wgpu::ShaderStages::COMPUTE,
                 ty: wgpu::BindingType::StorageTexture {
                     access: wgpu::StorageTextureAccess::WriteOnly,
-                    format: wgpu::TextureFormat::Rgba8Unorm,
+                    format: texture::Texture::COMPUTE_STORAGE_FORMAT,
                     view_dimension: wgpu::TextureViewDimension::D2,
                 },
                 count: None,
@@ -304,6 +813,112 @@ fn create_default_bind_group_layouts(device: &wgpu::Device) -> HashMap<String, w
         label: Some("Compute Storage Bind Group Layout"),
     }));
 
+    result.insert(FRAME_BIND_GROUP_LAYOUT_NAME.to_owned(), device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT | wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }
+        ],
+        label: Some("Frame Bind Group Layout"),
+    }));
+
+    result.insert(POST_PROCESS_INPUT_BIND_GROUP_LAYOUT_NAME.to_owned(), device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    multisampled: false,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                },
+                count: None,
+            },
+        ],
+        label: Some("Post Process Input Bind Group Layout"),
+    }));
+
+    result.insert(POST_EFFECT_BIND_GROUP_LAYOUT_NAME.to_owned(), device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }
+        ],
+        label: Some("Post Effect Bind Group Layout"),
+    }));
+
+    result.insert(PARTICLE_SIMULATION_BIND_GROUP_LAYOUT_NAME.to_owned(), device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: false },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+        ],
+        label: Some("Particle Simulation Bind Group Layout"),
+    }));
+
+    result.insert(PARTICLE_EMITTER_BIND_GROUP_LAYOUT_NAME.to_owned(), device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }
+        ],
+        label: Some("Particle Emitter Bind Group Layout"),
+    }));
+
     result
 }
 
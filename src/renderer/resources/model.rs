@@ -1,8 +1,11 @@
+use std::ops::Range;
+use std::rc::Rc;
 use wgpu::util::DeviceExt;
 use color_eyre::eyre::{eyre, Result};
 use super::mesh::Mesh;
 use super::shader_data::ShaderVertex;
 use super::vertex::Vertex;
+use super::super::uploader::Uploader;
 use super::super::viewport::Viewport;
 
 pub struct FullscreenQuad {
@@ -10,10 +13,12 @@ pub struct FullscreenQuad {
     // Image width and height determine the aspect ratio of an image to be displayed on the quad
     image_width: f32,
     image_height: f32,
+    // Vertices to be uploaded with the next frame
+    pending_vertices: Option<Vec<ShaderVertex>>,
 }
 
 impl FullscreenQuad {
-    pub fn new(viewport: &Viewport, device: &wgpu::Device, queue: &wgpu::Queue) -> Result<Self> {
+    pub fn new(viewport: &Viewport, device: &wgpu::Device) -> Result<Self> {
         let quad_mesh = Mesh::new_quad();
         let quad_model = Model::new(vec![quad_mesh], device)?;
         let mut result = Self {
@@ -21,8 +26,9 @@ impl FullscreenQuad {
             // Assume a square image by default
             image_width: 1.0,
             image_height: 1.0,
+            pending_vertices: None,
         };
-        result.resize_to_viewport(viewport, device, queue);
+        result.resize_to_viewport(viewport);
         Ok(result)
     }
 
@@ -30,11 +36,10 @@ impl FullscreenQuad {
         self.quad_model.draw(render_pass);
     }
 
+    /// The quad's vertex buffer is updated by `upload_pending` with the next frame.
     pub fn resize_to_viewport(
         &mut self, // This method mutates the quad model's vertex buffer
         viewport: &Viewport,
-        device: &wgpu::Device,
-        queue: &wgpu::Queue,
     ) {
         // Correct for image aspect ratio
         let mut x = if self.image_width >= self.image_height {
@@ -70,33 +75,54 @@ impl FullscreenQuad {
                 vertex
             })
             .collect::<Vec<ShaderVertex>>();
-        let staging_buffer = device
-            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Fullscreen Quad Vertex Staging Buffer"),
-                contents: bytemuck::cast_slice(&vertices_merged),
-                usage: wgpu::BufferUsages::COPY_SRC,
-            });
-        let mut encoder = device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Fullscreen Quad Vertex Buffer Update Encoder"),
-            });
-        let copy_size = size_of::<ShaderVertex>() * vertices_merged.len();
-        encoder.copy_buffer_to_buffer(
-            &staging_buffer,
-            0,
-            self.quad_model.get_vertex_buffer(),
-            0,
-            copy_size as wgpu::BufferAddress,
-        );
-        queue.submit(Some(encoder.finish()));
+        self.pending_vertices = Some(vertices_merged);
+    }
+
+    /// Records the upload of the vertices changed since the last frame, if any.
+    pub fn upload_pending(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        uploader: &mut Uploader,
+        device: &wgpu::Device,
+    ) {
+        if let Some(vertices) = self.pending_vertices.take() {
+            uploader.upload(
+                encoder,
+                self.quad_model.get_vertex_buffer(),
+                0,
+                bytemuck::cast_slice(&vertices),
+                device,
+            );
+        }
+    }
+}
+
+/// Where a mesh of a model is stored in the model's buffers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MeshRange {
+    /// The mesh's first index, or its first vertex if the model has no indices
+    pub first_element: u32,
+    /// Indices drawn, or vertices if the model has no indices
+    pub element_count: u32,
+    /// Added to the mesh's indices, as they start at 0 for every mesh
+    pub base_vertex: i32,
+}
+
+impl MeshRange {
+    pub fn get_elements(&self) -> Range<u32> {
+        self.first_element..self.first_element + self.element_count
     }
 }
 
 #[derive(Debug)]
 pub struct Model {
     meshes: Vec<Mesh>,
-    vertex_buffer: wgpu::Buffer,
-    index_buffer: Option<wgpu::Buffer>,
+    mesh_ranges: Vec<MeshRange>,
+    // Textures assigned to meshes by the asset the model was loaded from, by mesh index
+    mesh_texture_names: Vec<Option<String>>,
+    // Shared with the draw state cached by render objects
+    vertex_buffer: Rc<wgpu::Buffer>,
+    index_buffer: Option<Rc<wgpu::Buffer>>,
 }
 
 impl Model {
@@ -130,6 +156,31 @@ impl Model {
             usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
         });
 
+        // Each mesh's indices refer to its own vertices, so they are drawn offset by the vertices before it
+        let mut first_vertex = 0;
+        let mut first_index = 0;
+        let mesh_ranges = meshes
+            .iter()
+            .map(|m| {
+                let vertex_count = m.vertices.len() as u32;
+                let range = match m.indices.as_ref() {
+                    Some(indices) => MeshRange {
+                        first_element: first_index,
+                        element_count: indices.len() as u32,
+                        base_vertex: first_vertex as i32,
+                    },
+                    None => MeshRange {
+                        first_element: first_vertex,
+                        element_count: vertex_count,
+                        base_vertex: 0,
+                    },
+                };
+                first_vertex += vertex_count;
+                first_index += m.indices.as_ref().map_or(0, |indices| indices.len() as u32);
+                range
+            })
+            .collect();
+
         // Create a GPU-side index buffer if the model has indices
         let index_buffer = if has_indices {
             // Collect all indices from all meshes
@@ -149,30 +200,53 @@ impl Model {
 
 
         Ok(Self {
+            mesh_texture_names: vec![None; meshes.len()],
             meshes,
-            vertex_buffer,
-            index_buffer,
+            mesh_ranges,
+            vertex_buffer: Rc::new(vertex_buffer),
+            index_buffer: index_buffer.map(Rc::new),
         })
     }
 
     pub fn draw(&self, render_pass: &mut wgpu::RenderPass) {
+        self.draw_instanced(render_pass, 0..1);
+    }
+
+    /// Draws the instances in `instances`, whose data must be bound to the vertex buffer slots after 0.
+    /// Every mesh is drawn with a call of its own.
+    pub fn draw_instanced(&self, render_pass: &mut wgpu::RenderPass, instances: Range<u32>) {
         render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
         if let Some(index_buffer) = self.index_buffer.as_ref() {
-            let index_count = self.meshes
-                .iter()
-                .map(|m| m.indices.as_ref().unwrap().len() as u32)
-                .sum();
             render_pass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint32);
-            render_pass.draw_indexed(0..index_count, 0, 0..1);
+            for range in &self.mesh_ranges {
+                render_pass.draw_indexed(range.get_elements(), range.base_vertex, instances.clone());
+            }
         } else {
-            let vertex_count = self.meshes
-                .iter()
-                .map(|m| m.vertices.len() as u32)
-                .sum();
-            render_pass.draw(0..vertex_count, 0..1);
+            for range in &self.mesh_ranges {
+                render_pass.draw(range.get_elements(), instances.clone());
+            }
         }
     }
 
+    /// Where each mesh, in the order of `get_meshes`, is stored in the model's buffers
+    pub fn get_mesh_ranges(&self) -> &[MeshRange] {
+        &self.mesh_ranges
+    }
+
+    /// The texture mesh `mesh_index` is drawn with instead of the render object's texture, if any
+    pub fn get_mesh_texture_name(&self, mesh_index: usize) -> Option<&str> {
+        self.mesh_texture_names.get(mesh_index)?.as_deref()
+    }
+
+    /// Assigns each mesh, in the order of `get_meshes`, a texture of its own or `None` to use the render object's.
+    pub fn set_mesh_texture_names(&mut self, names: Vec<Option<String>>) -> Result<()> {
+        if names.len() != self.meshes.len() {
+            return Err(eyre!("Got {} mesh textures for {} meshes", names.len(), self.meshes.len()));
+        }
+        self.mesh_texture_names = names;
+        Ok(())
+    }
+
     pub fn get_vertices_merged(&self) -> Vec<&Vertex> {
         self.meshes
             .iter()
@@ -200,6 +274,14 @@ impl Model {
     }
 
     pub fn get_index_buffer(&self) -> Option<&wgpu::Buffer> {
+        self.index_buffer.as_deref()
+    }
+
+    pub(crate) fn get_shared_vertex_buffer(&self) -> &Rc<wgpu::Buffer> {
+        &self.vertex_buffer
+    }
+
+    pub(crate) fn get_shared_index_buffer(&self) -> Option<&Rc<wgpu::Buffer>> {
         self.index_buffer.as_ref()
     }
 }
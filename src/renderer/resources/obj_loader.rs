@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+use color_eyre::eyre::{eyre, Result};
+use glam::{Vec2, Vec3};
+use super::file;
+use super::mesh::Mesh;
+use super::vertex::Vertex;
+
+/// Meshes and diffuse maps loaded from a Wavefront OBJ file and its MTL material libraries.
+/// Faces are grouped into one mesh per material used with `usemtl`.
+pub struct ObjAsset {
+    pub meshes: Vec<Mesh>,
+    /// Material of each mesh, `None` for faces that appear before any `usemtl`
+    pub mesh_materials: Vec<Option<String>>,
+    /// Encoded diffuse map images by material name
+    pub diffuse_maps: Vec<(String, Vec<u8>)>,
+}
+
+impl ObjAsset {
+    /// Loads an OBJ file along with the material libraries and diffuse maps it references.
+    /// Missing material libraries and maps are logged and skipped.
+    pub async fn new_from_file(filepath: &str) -> Result<Self> {
+        let source = file::load_bytes(filepath).await?;
+        let obj = ObjData::parse(std::str::from_utf8(&source)?)?;
+
+        let mut materials = HashMap::new();
+        for library in &obj.material_libraries {
+            let library_path = file::resolve_relative(filepath, library);
+            let library_source = match file::load_bytes(&library_path).await {
+                Ok(source) => source,
+                Err(report) => {
+                    log::warn!("Failed to load material library {library_path}: {report}");
+                    continue;
+                }
+            };
+            for mut material in parse_mtl(std::str::from_utf8(&library_source)?)? {
+                material.diffuse_map = material.diffuse_map
+                    .map(|map| file::resolve_relative(&library_path, &map));
+                materials.insert(material.name.clone(), material);
+            }
+        }
+
+        let mut diffuse_maps = Vec::new();
+        for material in materials.values() {
+            let Some(map) = &material.diffuse_map else {
+                continue;
+            };
+            match file::load_bytes(map).await {
+                Ok(bytes) => diffuse_maps.push((material.name.clone(), bytes)),
+                Err(report) => log::warn!("Failed to load diffuse map {map}: {report}"),
+            }
+        }
+
+        let mut result = Self::new_from_data(obj, &materials)?;
+        result.diffuse_maps = diffuse_maps;
+        Ok(result)
+    }
+
+    /// Parses OBJ source without loading any material libraries.
+    pub fn new_from_str(source: &str) -> Result<Self> {
+        Self::new_from_data(ObjData::parse(source)?, &HashMap::new())
+    }
+
+    fn new_from_data(obj: ObjData, materials: &HashMap<String, MtlMaterial>) -> Result<Self> {
+        let mut meshes = Vec::new();
+        let mut mesh_materials = Vec::new();
+        for group in &obj.groups {
+            if group.faces.is_empty() {
+                continue;
+            }
+            let diffuse_color = group.material
+                .as_ref()
+                .and_then(|name| materials.get(name))
+                .and_then(|material| material.diffuse_color);
+            meshes.push(obj.build_mesh(group, diffuse_color));
+            mesh_materials.push(group.material.clone());
+        }
+
+        if meshes.is_empty() {
+            return Err(eyre!("OBJ file contains no faces"));
+        }
+
+        Ok(Self {
+            meshes,
+            mesh_materials,
+            diffuse_maps: Vec::new(),
+        })
+    }
+}
+
+/// Indices into the position, texcoord and normal lists of an OBJ file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct FaceVertex {
+    position: usize,
+    texcoord: Option<usize>,
+    normal: Option<usize>,
+}
+
+struct FaceGroup {
+    material: Option<String>,
+    faces: Vec<Vec<FaceVertex>>,
+}
+
+#[derive(Default)]
+struct ObjData {
+    positions: Vec<Vec3>,
+    colors: Vec<Option<Vec3>>,
+    texcoords: Vec<Vec2>,
+    normals: Vec<Vec3>,
+    groups: Vec<FaceGroup>,
+    material_libraries: Vec<String>,
+}
+
+impl ObjData {
+    fn parse(source: &str) -> Result<Self> {
+        let mut result = Self::default();
+        // Faces before the first `usemtl` belong to the group without a material
+        let mut group_indices = HashMap::from([(None, 0)]);
+        result.groups.push(FaceGroup {
+            material: None,
+            faces: Vec::new(),
+        });
+        let mut current_group = 0;
+
+        for (line_index, line) in source.lines().enumerate() {
+            let line_number = line_index + 1;
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let mut tokens = line.split_whitespace();
+            let Some(keyword) = tokens.next() else {
+                continue;
+            };
+
+            match keyword {
+                "v" => {
+                    let values = parse_floats(tokens, line_number)?;
+                    if values.len() < 3 {
+                        return Err(eyre!("OBJ line {line_number}: vertex needs 3 coordinates"));
+                    }
+                    result.positions.push(Vec3::new(values[0], values[1], values[2]));
+                    // Vertex colors are a common extension: v x y z r g b
+                    result.colors.push(if values.len() >= 6 {
+                        Some(Vec3::new(values[3], values[4], values[5]))
+                    } else {
+                        None
+                    });
+                }
+                "vt" => {
+                    let values = parse_floats(tokens, line_number)?;
+                    if values.is_empty() {
+                        return Err(eyre!("OBJ line {line_number}: texcoord needs coordinates"));
+                    }
+                    // OBJ texcoords start at the bottom left, textures at the top left
+                    let v = values.get(1).copied().unwrap_or(0.0);
+                    result.texcoords.push(Vec2::new(values[0], 1.0 - v));
+                }
+                "vn" => {
+                    let values = parse_floats(tokens, line_number)?;
+                    if values.len() < 3 {
+                        return Err(eyre!("OBJ line {line_number}: normal needs 3 coordinates"));
+                    }
+                    result.normals.push(Vec3::new(values[0], values[1], values[2]).normalize_or_zero());
+                }
+                "f" => {
+                    let face = tokens
+                        .map(|token| result.parse_face_vertex(token, line_number))
+                        .collect::<Result<Vec<_>>>()?;
+                    if face.len() < 3 {
+                        return Err(eyre!("OBJ line {line_number}: face needs at least 3 vertices"));
+                    }
+                    result.groups[current_group].faces.push(face);
+                }
+                "usemtl" => {
+                    let material = tokens.next().map(str::to_owned);
+                    current_group = *group_indices
+                        .entry(material.clone())
+                        .or_insert_with(|| {
+                            result.groups.push(FaceGroup {
+                                material,
+                                faces: Vec::new(),
+                            });
+                            result.groups.len() - 1
+                        });
+                }
+                "mtllib" => {
+                    result.material_libraries.extend(tokens.map(str::to_owned));
+                }
+                // Objects, groups, smoothing groups and other statements do not affect the meshes
+                _ => {}
+            }
+        }
+
+        Ok(result)
+    }
+
+    fn parse_face_vertex(&self, token: &str, line_number: usize) -> Result<FaceVertex> {
+        let mut parts = token.split('/');
+        let position = parts.next().unwrap_or_default();
+        let texcoord = parts.next().filter(|part| !part.is_empty());
+        let normal = parts.next().filter(|part| !part.is_empty());
+
+        Ok(FaceVertex {
+            position: parse_index(position, self.positions.len(), line_number)?,
+            texcoord: texcoord
+                .map(|index| parse_index(index, self.texcoords.len(), line_number))
+                .transpose()?,
+            normal: normal
+                .map(|index| parse_index(index, self.normals.len(), line_number))
+                .transpose()?,
+        })
+    }
+
+    /// Triangulates the faces of a group as fans and de-duplicates their vertices.
+    fn build_mesh(&self, group: &FaceGroup, diffuse_color: Option<Vec3>) -> Mesh {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let mut vertex_lookup = HashMap::new();
+
+        for face in &group.faces {
+            let face_indices = face
+                .iter()
+                .map(|face_vertex| {
+                    *vertex_lookup.entry(*face_vertex).or_insert_with(|| {
+                        vertices.push(Vertex {
+                            position: self.positions[face_vertex.position],
+                            normal: face_vertex.normal
+                                .map(|index| self.normals[index])
+                                .unwrap_or(Vec3::ZERO),
+                            color: self.colors[face_vertex.position]
+                                .or(diffuse_color)
+                                .unwrap_or(Vec3::ONE),
+                            texcoord: face_vertex.texcoord
+                                .map(|index| self.texcoords[index])
+                                .unwrap_or(Vec2::ZERO),
+                        });
+                        (vertices.len() - 1) as u32
+                    })
+                })
+                .collect::<Vec<u32>>();
+
+            for i in 1..face_indices.len() - 1 {
+                indices.extend([face_indices[0], face_indices[i], face_indices[i + 1]]);
+            }
+        }
+
+        // Vertices without normals get the area-weighted average of their faces' normals
+        let missing_normals = vertex_lookup
+            .iter()
+            .filter(|(face_vertex, _)| face_vertex.normal.is_none())
+            .map(|(_, &index)| index as usize)
+            .collect::<Vec<_>>();
+        if !missing_normals.is_empty() {
+            let mut accumulated = vec![Vec3::ZERO; vertices.len()];
+            for triangle in indices.chunks_exact(3) {
+                let [a, b, c] = [0, 1, 2].map(|i| vertices[triangle[i] as usize].position);
+                let face_normal = (b - a).cross(c - a);
+                for &index in triangle {
+                    accumulated[index as usize] += face_normal;
+                }
+            }
+            for index in missing_normals {
+                vertices[index].normal = accumulated[index].normalize_or(Vec3::Z);
+            }
+        }
+
+        Mesh::new(vertices, Some(indices))
+    }
+}
+
+struct MtlMaterial {
+    name: String,
+    diffuse_color: Option<Vec3>,
+    diffuse_map: Option<String>,
+}
+
+fn parse_mtl(source: &str) -> Result<Vec<MtlMaterial>> {
+    let mut materials: Vec<MtlMaterial> = Vec::new();
+
+    for (line_index, line) in source.lines().enumerate() {
+        let line_number = line_index + 1;
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let arguments = line[keyword.len()..].trim_start();
+
+        if keyword == "newmtl" {
+            materials.push(MtlMaterial {
+                name: tokens.next().unwrap_or_default().to_owned(),
+                diffuse_color: None,
+                diffuse_map: None,
+            });
+            continue;
+        }
+
+        let Some(material) = materials.last_mut() else {
+            continue;
+        };
+        match keyword {
+            "Kd" => {
+                let values = parse_floats(tokens, line_number)?;
+                if values.len() < 3 {
+                    return Err(eyre!("MTL line {line_number}: Kd needs 3 components"));
+                }
+                material.diffuse_color = Some(Vec3::new(values[0], values[1], values[2]));
+            }
+            "map_Kd" => {
+                material.diffuse_map = parse_map_path(arguments);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(materials)
+}
+
+/// Skips the options of a texture map statement such as `-s 1 1 1` and returns the rest
+/// of the line as the file name, which may contain spaces.
+fn parse_map_path(arguments: &str) -> Option<String> {
+    let mut rest = arguments;
+    while let Some(option) = rest.strip_prefix('-') {
+        let (name, mut after) = split_token(option);
+        match name {
+            // Up to three numbers
+            "o" | "s" | "t" => {
+                for _ in 0..3 {
+                    let (value, next) = split_token(after);
+                    if value.parse::<f32>().is_err() {
+                        break;
+                    }
+                    after = next;
+                }
+            }
+            "mm" => after = split_token(split_token(after).1).1,
+            // Options with a single value, such as `-clamp on` or `-bm 0.5`
+            _ => after = split_token(after).1,
+        }
+        rest = after;
+    }
+    Some(rest.to_owned()).filter(|path| !path.is_empty())
+}
+
+/// Splits off the first whitespace-separated token, returns it and the rest with leading whitespace removed
+fn split_token(source: &str) -> (&str, &str) {
+    let (token, rest) = source.split_once(char::is_whitespace).unwrap_or((source, ""));
+    (token, rest.trim_start())
+}
+
+fn parse_floats<'a>(tokens: impl Iterator<Item = &'a str>, line_number: usize) -> Result<Vec<f32>> {
+    tokens
+        .map(|token| {
+            token
+                .parse::<f32>()
+                .map_err(|e| eyre!("Line {line_number}: invalid number {token:?}: {e}"))
+        })
+        .collect()
+}
+
+/// Converts a 1-based or negative (relative to the end) OBJ index into a 0-based index.
+fn parse_index(token: &str, count: usize, line_number: usize) -> Result<usize> {
+    let index = token
+        .parse::<i64>()
+        .map_err(|e| eyre!("OBJ line {line_number}: invalid index {token:?}: {e}"))?;
+    let resolved = match index {
+        1.. => index - 1,
+        ..=-1 => count as i64 + index,
+        0 => return Err(eyre!("OBJ line {line_number}: indices start at 1")),
+    };
+    if resolved < 0 || resolved >= count as i64 {
+        return Err(eyre!("OBJ line {line_number}: index {index} out of range"));
+    }
+    Ok(resolved as usize)
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use color_eyre::eyre::{eyre, Result};
+
+/// What to do when a resource is registered under a name that is already taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NameCollision {
+    /// Fail and keep the existing resource
+    #[default]
+    Error,
+    /// Drop the existing resource and register the new one in its place
+    Replace,
+    /// Register the new resource as `<name>.1`, `<name>.2`, ... whichever is free first
+    AutoSuffix,
+}
+
+/// Typed handle to a resource registered in `Resources`, identified by its name.
+/// Render and compute objects reference resources by name, so a handle
+/// to a replaced resource refers to its replacement.
+pub struct ResourceHandle<T> {
+    name: String,
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> ResourceHandle<T> {
+    pub(super) fn new(name: String) -> Self {
+        Self {
+            name,
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+}
+
+// Implemented manually since deriving would require `T` to implement these traits as well
+impl<T> Clone for ResourceHandle<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.name.clone())
+    }
+}
+
+impl<T> PartialEq for ResourceHandle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl<T> Eq for ResourceHandle<T> {}
+
+impl<T> Hash for ResourceHandle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for ResourceHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ResourceHandle({:?})", self.name)
+    }
+}
+
+/// Fails if registering `name` into `map` according to `on_collision` would fail, without registering anything.
+pub(super) fn check_available<T>(
+    map: &HashMap<String, T>,
+    kind: &str,
+    name: &str,
+    on_collision: NameCollision,
+) -> Result<()> {
+    if on_collision == NameCollision::Error && map.contains_key(name) {
+        return Err(eyre!("A {kind} named {name:?} already exists"));
+    }
+    Ok(())
+}
+
+/// Inserts `resource` into `map` according to `on_collision` and returns the name it was stored under.
+pub(super) fn register<T>(
+    map: &mut HashMap<String, T>,
+    kind: &str,
+    name: &str,
+    resource: T,
+    on_collision: NameCollision,
+) -> Result<String> {
+    check_available(map, kind, name, on_collision)?;
+    let name = match on_collision {
+        NameCollision::AutoSuffix if map.contains_key(name) => (1..)
+            .map(|suffix| format!("{name}.{suffix}"))
+            .find(|candidate| !map.contains_key(candidate))
+            .expect("Suffixes are unbounded"),
+        _ => name.to_owned(),
+    };
+    map.insert(name.clone(), resource);
+    Ok(name)
+}
@@ -1,20 +1,26 @@
-use std::borrow::Cow;
 use color_eyre::{eyre::eyre, Result};
+use std::collections::HashMap;
 use std::path::Path;
+use std::rc::Rc;
 use color_eyre::eyre::ErrReport;
-#[cfg(target_arch = "wasm32")]
-use reqwest::Url;
+use super::file;
 
-#[derive(Debug)]
+/// Clones share the same shader module.
+#[derive(Debug, Clone)]
 pub struct Shader {
-    module: wgpu::ShaderModule,
+    module: Rc<wgpu::ShaderModule>,
+    source_path: Option<String>,
+    // Workgroup sizes of the compute entry points, reflected from the source if it could be parsed
+    workgroup_sizes: Rc<HashMap<String, [u32; 3]>>,
 }
 
 impl Shader {
     pub fn new_from_descriptor(desc: wgpu::ShaderModuleDescriptor, device: &wgpu::Device) -> Self {
         let module = device.create_shader_module(desc);
         Self {
-            module
+            module: Rc::new(module),
+            source_path: None,
+            workgroup_sizes: Rc::default(),
         }
     }
 
@@ -22,14 +28,8 @@ impl Shader {
         filepath: &str,
         device: &wgpu::Device,
     ) -> Result<Self> {
-        #[cfg(not(target_arch = "wasm32"))]
-        let source = {
-            let filepath = Path::new(env!("CARGO_MANIFEST_DIR"))
-                .join(filepath);
-            std::fs::read(filepath)?
-        };
-        #[cfg(target_arch = "wasm32")]
-        let source = fetch_shader_file(filepath).await?;
+        let source = file::load_bytes(filepath).await?;
+        let naga_module = parse_naga_module(&source, filepath);
 
         let source = match Path::new(filepath)
             .extension()
@@ -58,33 +58,71 @@ impl Shader {
             label: Some(filepath),
             source,
         };
-        Ok(Self::new_from_descriptor(desc, device))
+        let mut shader = Self::new_from_descriptor(desc, device);
+        shader.source_path = Some(filepath.to_owned());
+        if let Some(naga_module) = naga_module {
+            shader.workgroup_sizes = Rc::new(reflect_workgroup_sizes(&naga_module));
+        }
+        Ok(shader)
+    }
+
+    /// Parses and validates WGSL with naga before handing it to the device,
+    /// so errors are returned as readable diagnostics instead of being raised by the device.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new_from_wgsl(source: &str, filepath: &str, device: &wgpu::Device) -> Result<Self> {
+        use wgpu::naga::front::wgsl;
+        use wgpu::naga::valid::{Capabilities, ValidationFlags, Validator};
+
+        let module = wgsl::parse_str(source)
+            .map_err(|e| eyre!(e.emit_to_string_with_path(source, filepath)))?;
+        Validator::new(ValidationFlags::all(), Capabilities::all())
+            .validate(&module)
+            .map_err(|e| eyre!(e.emit_to_string_with_path(source, filepath)))?;
+
+        let desc = wgpu::ShaderModuleDescriptor {
+            label: Some(filepath),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        };
+        let mut shader = Self::new_from_descriptor(desc, device);
+        shader.source_path = Some(filepath.to_owned());
+        shader.workgroup_sizes = Rc::new(reflect_workgroup_sizes(&module));
+        Ok(shader)
     }
 
     pub fn get_module(&self) -> &wgpu::ShaderModule {
         &self.module
     }
-}
 
-#[cfg(target_arch = "wasm32")]
-async fn fetch_shader_file(filepath: &str) -> Result<Vec<u8>> {
-    let base_url = get_base_url();
-    let url = base_url.join(filepath)?;
-    log::info!("Fetching shader from: {}", url);
-    let response = reqwest::get(url.as_str()).await?;
-    Ok(response.bytes().await?.to_vec())
+    /// The file the shader was loaded from, if any
+    pub fn get_source_path(&self) -> Option<&str> {
+        self.source_path.as_deref()
+    }
+
+    /// The workgroup size declared by the compute entry point `entry_point`.
+    /// Returns `None` if the shader was created from a descriptor or has no such entry point.
+    pub fn get_workgroup_size(&self, entry_point: &str) -> Option<[u32; 3]> {
+        self.workgroup_sizes.get(entry_point).copied()
+    }
 }
 
-#[cfg(target_arch = "wasm32")]
-fn get_base_url() -> Url {
-    use winit::platform::web::WindowExtWebSys;
-    let window = web_sys::window().expect("No window");
-    let document = window.document().expect("No document");
-    let base_url = if let Ok(Some(base_uri)) = document.base_uri() {
-        base_uri
-    } else {
-        window.location().origin().expect("No origin")
+fn parse_naga_module(source: &[u8], filepath: &str) -> Option<wgpu::naga::Module> {
+    use wgpu::naga::front::{spv, wgsl};
+
+    let module = match Path::new(filepath).extension().and_then(|ext| ext.to_str()) {
+        Some("wgsl") => wgsl::parse_str(std::str::from_utf8(source).ok()?).ok(),
+        Some("spv") => spv::parse_u8_slice(source, &spv::Options::default()).ok(),
+        _ => None,
     };
-    Url::parse(&base_url)
-        .expect(&format!("Failed to parse base URL: {}", base_url))
-}
\ No newline at end of file
+    if module.is_none() {
+        log::warn!("Failed to reflect shader {filepath}");
+    }
+    module
+}
+
+fn reflect_workgroup_sizes(module: &wgpu::naga::Module) -> HashMap<String, [u32; 3]> {
+    module.entry_points
+        .iter()
+        .filter(|entry_point| entry_point.stage == wgpu::naga::ShaderStage::Compute)
+        .map(|entry_point| (entry_point.name.clone(), entry_point.workgroup_size))
+        .collect()
+}
@@ -1,12 +1,24 @@
 use bytemuck::{Pod, Zeroable};
-use glam::{Mat4, Vec3, Vec2};
+use glam::{Mat4, Vec2, Vec3, Vec4};
 
 /* This module contains data to be sent to and from shaders. */
 
-/// Scene-related data
+/// Per-frame data shared by all materials through the frame bind group
 #[repr(C)]
-#[derive(Debug, Copy, Clone, Pod, Zeroable)]
-pub struct ShaderSceneUniform {
+#[derive(Debug, Default, Copy, Clone, Pod, Zeroable)]
+pub struct ShaderFrameUniform {
+    /// Viewport size in pixels
+    pub resolution: Vec2,
+    /// Cursor position in pixels from the top left corner of the viewport
+    pub mouse: Vec2,
+    pub camera_position: Vec3,
+    /// Seconds since the first frame
+    pub time: f32,
+    /// Seconds since the previous frame
+    pub delta_time: f32,
+    /// Number of frames rendered before this one
+    pub frame: u32,
+    pub _padding: [u32; 2],
 }
 
 /// Camera-related data
@@ -22,8 +34,123 @@ pub struct ShaderCameraUniform {
 #[repr(C)]
 #[derive(Debug, Copy, Clone, Pod, Zeroable)]
 pub struct ShaderPushConstants {
+    pub model: Mat4,
     pub flipv: u32,
-    pub gamma_correct: u32,
+    /// Fragments with a lower alpha are discarded, 0 keeps all fragments
+    pub alpha_cutoff: f32,
+    pub _padding: [u32; 2],
+}
+
+/// Per-frame inputs of playground passes
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Pod, Zeroable)]
+pub struct ShaderPlaygroundUniform {
+    pub resolution: Vec2,
+    pub time: f32,
+    pub delta_time: f32,
+    /// Cursor position in pixels, then whether the left and right buttons are down
+    pub mouse: Vec4,
+    pub frame: u32,
+    pub _padding: [u32; 3],
+}
+
+/// Parameters of a post-processing effect, their meaning depends on the effect
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Pod, Zeroable)]
+pub struct ShaderPostEffectUniform {
+    pub parameters: [f32; 8],
+}
+
+/// Per-instance data of instanced render objects
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Pod, Zeroable)]
+pub struct ShaderInstance {
+    /// Applied before the render object's own transform
+    pub model: Mat4,
+    /// Multiplies the material's color
+    pub tint: Vec4,
+    /// Free for custom shaders
+    pub custom_data: Vec4,
+}
+
+impl ShaderInstance {
+    // Follows the attributes of `ShaderVertex`
+    const ATTRIBS: [wgpu::VertexAttribute; 6] =
+        wgpu::vertex_attr_array![
+            4 => Float32x4, // model, first column
+            5 => Float32x4,
+            6 => Float32x4,
+            7 => Float32x4,
+            8 => Float32x4, // tint
+            9 => Float32x4, // custom data
+        ];
+
+    /// Read from the vertex buffer after the model's vertices
+    pub const BUFFER_LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+        array_stride: size_of::<Self>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Instance,
+        attributes: &Self::ATTRIBS,
+    };
+}
+
+/// A simulated particle, dead once its age reaches its lifetime
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Pod, Zeroable)]
+pub struct ShaderParticle {
+    pub position: Vec3,
+    /// Seconds since the particle spawned
+    pub age: f32,
+    pub velocity: Vec3,
+    pub lifetime: f32,
+}
+
+impl ShaderParticle {
+    const ATTRIBS: [wgpu::VertexAttribute; 4] =
+        wgpu::vertex_attr_array![
+            0 => Float32x3, // position
+            1 => Float32, // age
+            2 => Float32x3, // velocity
+            3 => Float32, // lifetime
+        ];
+
+    /// Particles are drawn as instances, straight from the simulated buffer
+    pub const BUFFER_LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+        array_stride: size_of::<Self>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Instance,
+        attributes: &Self::ATTRIBS,
+    };
+}
+
+/// Settings of a particle emitter, shared by its simulation and drawing
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone, Pod, Zeroable)]
+pub struct ShaderParticleEmitterUniform {
+    pub start_color: Vec4,
+    pub end_color: Vec4,
+    pub position: Vec3,
+    /// 0 for a point, 1 for a sphere, 2 for a box
+    pub shape: u32,
+    /// Radius of spheres in x, half extents of boxes
+    pub shape_size: Vec3,
+    /// Particles spawned per second
+    pub rate: f32,
+    pub velocity: Vec3,
+    /// Speed of the random velocity added to `velocity`
+    pub velocity_spread: f32,
+    /// Acceleration applied to all particles, e.g. gravity
+    pub force: Vec3,
+    pub drag: f32,
+    pub camera_right: Vec3,
+    pub start_size: f32,
+    pub camera_up: Vec3,
+    pub end_size: f32,
+    pub min_lifetime: f32,
+    pub max_lifetime: f32,
+    pub ground_height: f32,
+    /// Fraction of the vertical speed kept when bouncing off the ground, below 0 to disable collision
+    pub bounciness: f32,
+    pub max_particles: u32,
+    pub _padding: [u32; 3],
 }
 
 /// Vertex data
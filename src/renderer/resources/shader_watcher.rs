@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime};
+use color_eyre::Result;
+use super::file;
+
+/// Watches a directory of WGSL shaders by polling their modification times.
+pub struct ShaderWatcher {
+    directory: String,
+    modified_times: HashMap<String, SystemTime>,
+    last_poll: Instant,
+}
+
+impl ShaderWatcher {
+    const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+    /// `directory` is relative to the crate root, like the paths of `file::load_bytes`.
+    pub fn new(directory: &str) -> Result<Self> {
+        let directory = directory.trim_end_matches(['/', '\\']).to_owned();
+        let modified_times = scan(&directory)?;
+        Ok(Self {
+            directory,
+            modified_times,
+            last_poll: Instant::now(),
+        })
+    }
+
+    pub fn get_directory(&self) -> &str {
+        &self.directory
+    }
+
+    /// Returns the paths of the shaders that were added or modified since the last call,
+    /// such as `shaders/basic.wgsl`. The directory is scanned at most every 200ms.
+    pub fn poll_changed(&mut self) -> Vec<String> {
+        if self.last_poll.elapsed() < Self::POLL_INTERVAL {
+            return Vec::new();
+        }
+        self.last_poll = Instant::now();
+
+        let modified_times = match scan(&self.directory) {
+            Ok(modified_times) => modified_times,
+            Err(report) => {
+                log::warn!("Failed to scan shader directory {}: {report}", self.directory);
+                return Vec::new();
+            }
+        };
+        let changed = modified_times
+            .iter()
+            .filter(|(path, modified)| self.modified_times.get(*path) != Some(modified))
+            .map(|(path, _)| path.clone())
+            .collect();
+        self.modified_times = modified_times;
+        changed
+    }
+}
+
+/// Whether a shader loaded from `shader_path` is built from the WGSL file `wgsl_path`.
+/// Shaders precompiled into `shaders-compiled` by the build script are matched by name.
+pub fn is_shader_source(shader_path: &str, wgsl_path: &str) -> bool {
+    if shader_path == wgsl_path {
+        return true;
+    }
+    let shader_path = Path::new(shader_path);
+    let precompiled = shader_path.extension().is_some_and(|ext| ext == "spv")
+        && shader_path.parent().is_some_and(|parent| parent.ends_with("shaders-compiled"));
+    precompiled && shader_path.file_stem() == Path::new(wgsl_path).file_stem()
+}
+
+fn scan(directory: &str) -> Result<HashMap<String, SystemTime>> {
+    let mut result = HashMap::new();
+    for entry in std::fs::read_dir(file::get_native_path(directory))? {
+        let path = entry?.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("wgsl") {
+            continue;
+        }
+        let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let modified = std::fs::metadata(&path)?.modified()?;
+        result.insert(format!("{directory}/{file_name}"), modified);
+    }
+    Ok(result)
+}
@@ -1,3 +1,4 @@
+use std::rc::Rc;
 use super::{Resources, COMPUTE_STORAGE_BIND_GROUP_LAYOUT_NAME};
 use color_eyre::eyre::Result;
 
@@ -5,12 +6,16 @@ use color_eyre::eyre::Result;
 pub struct Texture {
     texture: wgpu::Texture,
     view: wgpu::TextureView,
-    bind_group: wgpu::BindGroup,
+    // Shared with the draw state cached by render objects
+    bind_group: Rc<wgpu::BindGroup>,
     width: u32,
     height: u32,
 }
 
 impl Texture {
+    /// Format of compute output textures, which hold linear HDR colors
+    pub const COMPUTE_STORAGE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
     pub fn new_from_bytes(
         bytes: &[u8],
         label: &str,
@@ -29,8 +34,19 @@ impl Texture {
         queue: &wgpu::Queue,
         resources: &Resources,
     ) -> Result<Self> {
-        let image = image.to_rgba8();
-        let dimensions = image.dimensions();
+        // Floating point images such as Radiance HDR files keep their range, others are sRGB colors
+        let (format, data) = match image {
+            image::DynamicImage::ImageRgb32F(_) | image::DynamicImage::ImageRgba32F(_) => {
+                let data = image.to_rgba32f()
+                    .into_raw()
+                    .into_iter()
+                    .flat_map(|value| half::f16::from_f32(value).to_le_bytes())
+                    .collect::<Vec<_>>();
+                (wgpu::TextureFormat::Rgba16Float, data)
+            }
+            _ => (wgpu::TextureFormat::Rgba8UnormSrgb, image.to_rgba8().into_raw()),
+        };
+        let dimensions = (image.width(), image.height());
         let size = wgpu::Extent3d {
             width: dimensions.0,
             height: dimensions.1,
@@ -41,7 +57,7 @@ impl Texture {
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            format,
             usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
             label: Some(label),
             view_formats: &[],
@@ -53,10 +69,10 @@ impl Texture {
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
             },
-            &image,
+            &data,
             wgpu::ImageDataLayout {
                 offset: 0,
-                bytes_per_row: Some(4 * dimensions.0),
+                bytes_per_row: Some(format.block_copy_size(None).unwrap_or(4) * dimensions.0),
                 rows_per_image: Some(dimensions.1),
             },
             size,
@@ -67,7 +83,7 @@ impl Texture {
 
         let layout = resources.get_bind_group_layout("single texture")?;
         let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &layout,
+            layout,
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
@@ -84,7 +100,7 @@ impl Texture {
         Ok(Self {
             texture,
             view,
-            bind_group,
+            bind_group: Rc::new(bind_group),
             width: size.width,
             height: size.height,
         })
@@ -102,7 +118,9 @@ impl Texture {
             height,
             depth_or_array_layers: 1,
         };
+        // Sampled when the output is drawn into the scene
         let usage = wgpu::TextureUsages::STORAGE_BINDING
+            | wgpu::TextureUsages::TEXTURE_BINDING
             | wgpu::TextureUsages::COPY_SRC
             | wgpu::TextureUsages::COPY_DST;
         let texture = device.create_texture(&wgpu::TextureDescriptor {
@@ -110,7 +128,7 @@ impl Texture {
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8Unorm,
+            format: Self::COMPUTE_STORAGE_FORMAT,
             usage,
             label: Some(label),
             view_formats: &[],
@@ -119,7 +137,7 @@ impl Texture {
         let layout = resources.get_bind_group_layout(COMPUTE_STORAGE_BIND_GROUP_LAYOUT_NAME)?;
         let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some(&format!("{label} Bind Group")),
-            layout: &layout,
+            layout,
             entries: &[
                 wgpu::BindGroupEntry {
                     binding: 0,
@@ -131,7 +149,7 @@ impl Texture {
         Ok(Self {
             texture,
             view,
-            bind_group,
+            bind_group: Rc::new(bind_group),
             width,
             height,
         })
@@ -141,6 +159,10 @@ impl Texture {
         &self.bind_group
     }
 
+    pub(crate) fn get_shared_bind_group(&self) -> &Rc<wgpu::BindGroup> {
+        &self.bind_group
+    }
+
     pub fn get_width(&self) -> u32 {
         self.width
     }
@@ -149,7 +171,15 @@ impl Texture {
         self.height
     }
 
+    pub fn get_format(&self) -> wgpu::TextureFormat {
+        self.texture.format()
+    }
+
     pub fn get_texture(&self) -> &wgpu::Texture {
         &self.texture
     }
-}
\ No newline at end of file
+
+    pub fn get_view(&self) -> &wgpu::TextureView {
+        &self.view
+    }
+}
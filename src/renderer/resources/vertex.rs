@@ -12,10 +12,10 @@ pub struct Vertex {
 impl Vertex {
     pub fn as_shader_data(&self) -> ShaderVertex {
         ShaderVertex {
-            position: self.position.into(),
-            normal: self.normal.into(),
-            color: self.color.into(),
-            texcoord: self.texcoord.into(),
+            position: self.position,
+            normal: self.normal,
+            color: self.color,
+            texcoord: self.texcoord,
         }
     }
 }
@@ -1,13 +1,24 @@
 use std::cell::RefCell;
 use std::rc::Rc;
-use color_eyre::eyre::Result;
-use crate::renderer::compute_object::ComputeObject;
+use color_eyre::eyre::{eyre, OptionExt, Result};
+use crate::renderer::compute_object::{ComputeDispatch, ComputeObject};
+use crate::renderer::instance::{Instance, InstanceBuffer};
+use crate::renderer::object_pool::{ObjectId, ObjectPool};
+use crate::renderer::particles::{ParticleEmitter, ParticleSystem};
+use crate::renderer::readback;
 use crate::renderer::render_object::RenderObject;
 use crate::renderer::resources::Resources;
+use crate::renderer::resources::shader_data::ShaderParticle;
+use crate::renderer::Transform;
+
+pub type RenderObjectId = ObjectId<RenderObject>;
+pub type ComputeObjectId = ObjectId<ComputeObject>;
+pub type ParticleSystemId = ObjectId<ParticleSystem>;
 
 pub struct Scene {
-    render_objects: Vec<RenderObject>,
-    compute_objects: Vec<ComputeObject>,
+    render_objects: ObjectPool<RenderObject>,
+    compute_objects: ObjectPool<ComputeObject>,
+    particle_systems: ObjectPool<ParticleSystem>,
 
     device: Rc<wgpu::Device>,
     queue: Rc<wgpu::Queue>,
@@ -21,8 +32,9 @@ impl Scene {
         resources: Rc<RefCell<Resources>>
     ) -> Self {
         Self {
-            render_objects: Vec::new(),
-            compute_objects: Vec::new(),
+            render_objects: ObjectPool::new(),
+            compute_objects: ObjectPool::new(),
+            particle_systems: ObjectPool::new(),
 
             device,
             queue,
@@ -30,27 +42,161 @@ impl Scene {
         }
     }
 
-    pub fn get_render_objects(&self) -> &Vec<RenderObject> {
-        &self.render_objects
+    /// Iterates over all render objects in insertion order, including hidden ones.
+    pub fn get_render_objects(&self) -> impl Iterator<Item = (RenderObjectId, &RenderObject)> {
+        self.render_objects.iter()
+    }
+
+    /// Iterates over the render objects to be drawn in insertion order.
+    pub fn get_visible_render_objects(&self) -> impl Iterator<Item = (RenderObjectId, &RenderObject)> {
+        self.render_objects.iter_visible()
     }
 
-    pub fn get_compute_objects(&self) -> &Vec<ComputeObject> {
-        &self.compute_objects
+    /// Iterates over all compute objects in insertion order, including hidden ones.
+    pub fn get_compute_objects(&self) -> impl Iterator<Item = (ComputeObjectId, &ComputeObject)> {
+        self.compute_objects.iter()
     }
 
-    pub fn add_render_object(&mut self, material_name: &str, texture_name: &str, model_name: &str) -> Result<()> {
+    /// Iterates over the compute objects to be dispatched in insertion order.
+    pub fn get_visible_compute_objects(&self) -> impl Iterator<Item = (ComputeObjectId, &ComputeObject)> {
+        self.compute_objects.iter_visible()
+    }
+
+    /// Iterates over all particle systems in insertion order, including hidden ones.
+    pub fn get_particle_systems(&self) -> impl Iterator<Item = (ParticleSystemId, &ParticleSystem)> {
+        self.particle_systems.iter()
+    }
+
+    /// Iterates over the particle systems to be simulated and drawn in insertion order.
+    pub fn get_visible_particle_systems(&self) -> impl Iterator<Item = (ParticleSystemId, &ParticleSystem)> {
+        self.particle_systems.iter_visible()
+    }
+
+    /// Adds a render object at the origin.
+    pub fn add_render_object(
+        &mut self,
+        material_name: &str,
+        texture_name: &str,
+        model_name: &str,
+    ) -> Result<RenderObjectId> {
         let resources = self.resources.try_borrow()?;
         let render_object = resources.create_render_object(material_name, texture_name, model_name)?;
-        self.render_objects.push(render_object);
+        Ok(self.render_objects.insert(render_object))
+    }
+
+    /// Adds a render object drawing one copy of the model per instance in a single draw call.
+    /// The material must read instance data, see `RenderMaterialBuilder::with_instances`.
+    pub fn add_instanced_render_object(
+        &mut self,
+        material_name: &str,
+        texture_name: &str,
+        model_name: &str,
+        instances: &[Instance],
+    ) -> Result<RenderObjectId> {
+        let resources = self.resources.try_borrow()?;
+        if !resources.get_render_material(material_name)?.is_instanced() {
+            return Err(eyre!("Material {material_name} does not read instances"));
+        }
+        let instances = InstanceBuffer::new(instances, &self.device, &self.queue);
+        let render_object = resources
+            .create_render_object(material_name, texture_name, model_name)?
+            .with_instances(instances);
+        Ok(self.render_objects.insert(render_object))
+    }
+
+    /// Replaces the instances of an instanced render object.
+    pub fn set_render_object_instances(&mut self, id: RenderObjectId, instances: &[Instance]) -> Result<()> {
+        get_instance_buffer_mut(&mut self.render_objects, id)?.set(instances, &self.device, &self.queue);
+        Ok(())
+    }
+
+    /// Overwrites the instances of an instanced render object from index `first` on,
+    /// uploading only the changed ones.
+    pub fn update_render_object_instances(
+        &mut self,
+        id: RenderObjectId,
+        first: u32,
+        instances: &[Instance],
+    ) -> Result<()> {
+        get_instance_buffer_mut(&mut self.render_objects, id)?.update(first, instances, &self.queue)
+    }
+
+    /// Removes a render object. Returns `None` if the handle is stale.
+    pub fn remove_render_object(&mut self, id: RenderObjectId) -> Option<RenderObject> {
+        self.render_objects.remove(id)
+    }
+
+    pub fn get_render_object(&self, id: RenderObjectId) -> Option<&RenderObject> {
+        self.render_objects.get(id)
+    }
+
+    pub fn get_render_object_mut(&mut self, id: RenderObjectId) -> Option<&mut RenderObject> {
+        self.render_objects.get_mut(id)
+    }
+
+    pub fn set_render_object_transform(&mut self, id: RenderObjectId, transform: Transform) -> Result<()> {
+        self.render_objects
+            .get_mut(id)
+            .ok_or_eyre(format!("Render object not found: {id:?}"))?
+            .set_transform(transform);
+        Ok(())
+    }
+
+    /// Draws the mesh `mesh_index` of a render object's model with another material and texture.
+    /// The mesh index is checked against the model when the object is drawn, as it may still be loading.
+    pub fn set_render_object_mesh_material(
+        &mut self,
+        id: RenderObjectId,
+        mesh_index: usize,
+        material_name: &str,
+        texture_name: &str,
+    ) -> Result<()> {
+        self.resources.try_borrow()?.check_material_and_texture(material_name, texture_name)?;
+        self.render_objects
+            .get_mut(id)
+            .ok_or_eyre(format!("Render object not found: {id:?}"))?
+            .set_mesh_material(mesh_index, material_name.to_owned(), texture_name.to_owned());
+        Ok(())
+    }
+
+    /// Draws the mesh `mesh_index` of a render object's model with the object's material and texture again.
+    pub fn clear_render_object_mesh_material(&mut self, id: RenderObjectId, mesh_index: usize) -> Result<()> {
+        self.render_objects
+            .get_mut(id)
+            .ok_or_eyre(format!("Render object not found: {id:?}"))?
+            .clear_mesh_material(mesh_index);
+        Ok(())
+    }
+
+    pub fn is_render_object_visible(&self, id: RenderObjectId) -> Option<bool> {
+        self.render_objects.is_visible(id)
+    }
+
+    pub fn set_render_object_visible(&mut self, id: RenderObjectId, visible: bool) -> Result<()> {
+        if !self.render_objects.set_visible(id, visible) {
+            return Err(eyre!("Render object not found: {id:?}"));
+        }
         Ok(())
     }
 
+    /// Adds a compute object without an output texture, bind its resources
+    /// through `get_compute_object_mut`.
+    pub fn add_compute_object(
+        &mut self,
+        material_name: &str,
+        dispatch: ComputeDispatch,
+    ) -> Result<ComputeObjectId> {
+        let resources = self.resources.try_borrow()?;
+        let compute_object = resources.create_compute_object(material_name, dispatch)?;
+        Ok(self.compute_objects.insert(compute_object))
+    }
+
     pub fn add_compute_object_with_output_texture(
         &mut self,
         material_name: &str,
         texture_width: u32,
         texture_height: u32,
-    ) -> Result<()> {
+    ) -> Result<ComputeObjectId> {
         let resources = self.resources.try_borrow()?;
         let compute_object = resources.create_compute_object_with_output_texture(
             material_name,
@@ -58,12 +204,76 @@ impl Scene {
             texture_height,
             &self.device,
         )?;
-        self.compute_objects.push(compute_object);
+        Ok(self.compute_objects.insert(compute_object))
+    }
+
+    /// Removes a compute object. Returns `None` if the handle is stale.
+    pub fn remove_compute_object(&mut self, id: ComputeObjectId) -> Option<ComputeObject> {
+        self.compute_objects.remove(id)
+    }
+
+    pub fn get_compute_object(&self, id: ComputeObjectId) -> Option<&ComputeObject> {
+        self.compute_objects.get(id)
+    }
+
+    pub fn get_compute_object_mut(&mut self, id: ComputeObjectId) -> Option<&mut ComputeObject> {
+        self.compute_objects.get_mut(id)
+    }
+
+    pub fn is_compute_object_visible(&self, id: ComputeObjectId) -> Option<bool> {
+        self.compute_objects.is_visible(id)
+    }
+
+    /// Hidden compute objects are not dispatched.
+    pub fn set_compute_object_visible(&mut self, id: ComputeObjectId, visible: bool) -> Result<()> {
+        if !self.compute_objects.set_visible(id, visible) {
+            return Err(eyre!("Compute object not found: {id:?}"));
+        }
         Ok(())
     }
 
+    pub fn add_particle_system(&mut self, emitter: ParticleEmitter) -> Result<ParticleSystemId> {
+        let resources = self.resources.try_borrow()?;
+        let particle_system = ParticleSystem::new(emitter, &self.device, &resources)?;
+        Ok(self.particle_systems.insert(particle_system))
+    }
+
+    /// Removes a particle system. Returns `None` if the handle is stale.
+    pub fn remove_particle_system(&mut self, id: ParticleSystemId) -> Option<ParticleSystem> {
+        self.particle_systems.remove(id)
+    }
+
+    pub fn get_particle_system(&self, id: ParticleSystemId) -> Option<&ParticleSystem> {
+        self.particle_systems.get(id)
+    }
+
+    pub fn get_particle_system_mut(&mut self, id: ParticleSystemId) -> Option<&mut ParticleSystem> {
+        self.particle_systems.get_mut(id)
+    }
+
+    pub fn is_particle_system_visible(&self, id: ParticleSystemId) -> Option<bool> {
+        self.particle_systems.is_visible(id)
+    }
+
+    /// Hidden particle systems are neither simulated nor drawn.
+    pub fn set_particle_system_visible(&mut self, id: ParticleSystemId, visible: bool) -> Result<()> {
+        if !self.particle_systems.set_visible(id, visible) {
+            return Err(eyre!("Particle system not found: {id:?}"));
+        }
+        Ok(())
+    }
+
+    /// Reads the particles of a particle system back, dead ones included.
+    pub fn read_particles(&self, id: ParticleSystemId) -> Result<Vec<ShaderParticle>> {
+        let particle_system = self.particle_systems
+            .get(id)
+            .ok_or_eyre(format!("Particle system not found: {id:?}"))?;
+        let bytes = readback::read_buffer(particle_system.get_particle_buffer(), &self.device, &self.queue)?;
+        Ok(bytemuck::pod_collect_to_vec(&bytes))
+    }
+
     pub fn resize_compute_output_textures(&mut self, width: u32, height: u32) -> Result<()> {
-        for compute_object in self.compute_objects.iter_mut() {
+        for (_, compute_object) in self.compute_objects.iter_mut() {
             if compute_object.has_output_texture() {
                 compute_object.resize_output_texture(
                     width,
@@ -76,4 +286,25 @@ impl Scene {
         }
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// Reads the output texture of a compute object back into an image.
+    pub fn read_compute_output_texture(&self, id: ComputeObjectId) -> Result<image::RgbaImage> {
+        let texture = self.compute_objects
+            .get(id)
+            .ok_or_eyre(format!("Compute object not found: {id:?}"))?
+            .get_output_texture()
+            .ok_or_eyre("Compute object has no output texture")?;
+        readback::read_texture_to_image(texture.get_texture(), &self.device, &self.queue)
+    }
+}
+
+fn get_instance_buffer_mut(
+    render_objects: &mut ObjectPool<RenderObject>,
+    id: RenderObjectId,
+) -> Result<&mut InstanceBuffer> {
+    render_objects
+        .get_mut(id)
+        .ok_or_eyre(format!("Render object not found: {id:?}"))?
+        .get_instances_mut()
+        .ok_or_eyre(format!("Render object is not instanced: {id:?}"))
+}
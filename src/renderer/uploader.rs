@@ -0,0 +1,52 @@
+use std::num::NonZeroU64;
+use wgpu::util::StagingBelt;
+
+/// Records buffer uploads into the encoder of the frame being rendered,
+/// reusing staging memory once the GPU is done with it.
+pub struct Uploader {
+    staging_belt: StagingBelt,
+}
+
+impl Uploader {
+    const CHUNK_SIZE: wgpu::BufferAddress = 4096;
+
+    pub fn new() -> Self {
+        Self {
+            staging_belt: StagingBelt::new(Self::CHUNK_SIZE),
+        }
+    }
+
+    /// Copies `data` into `target` at `offset` when `encoder` is executed.
+    /// The length of `data` must be a multiple of `wgpu::COPY_BUFFER_ALIGNMENT`.
+    pub fn upload(
+        &mut self,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::Buffer,
+        offset: wgpu::BufferAddress,
+        data: &[u8],
+        device: &wgpu::Device,
+    ) {
+        let Some(size) = NonZeroU64::new(data.len() as u64) else {
+            return;
+        };
+        self.staging_belt
+            .write_buffer(encoder, target, offset, size, device)
+            .copy_from_slice(data);
+    }
+
+    /// Must be called after the last upload of a frame, before its encoder is submitted.
+    pub(super) fn finish(&mut self) {
+        self.staging_belt.finish();
+    }
+
+    /// Must be called after the frame's encoder is submitted to reclaim the staging memory.
+    pub(super) fn recall(&mut self) {
+        self.staging_belt.recall();
+    }
+}
+
+impl Default for Uploader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,34 +1,109 @@
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{eyre, OptionExt, Result};
 use winit::{dpi::PhysicalSize, window::Window};
 
-/// Viewport contains the window and wgpu surface.
+/// Viewport contains the render target, which is either a window surface
+/// or an offscreen texture when rendering headlessly.
 /// It is the target for rendering.
 pub struct Viewport<'window> {
-    window: &'window Window,
-    surface: wgpu::Surface<'window>,
+    target: ViewportTarget<'window>,
+    depth_texture: wgpu::Texture,
+    depth_view: wgpu::TextureView,
     config: wgpu::SurfaceConfiguration,
     size: PhysicalSize<u32>,
     background: wgpu::Color,
+    // Samples per pixel of the scene's color and depth targets
+    sample_count: u32,
+    supported_sample_counts: Vec<u32>,
+}
+
+enum ViewportTarget<'window> {
+    Surface {
+        window: &'window Window,
+        surface: wgpu::Surface<'window>,
+    },
+    Offscreen {
+        texture: wgpu::Texture,
+    },
+}
+
+/// How the linear colors of the scene are encoded when they are written to the viewport
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputTransform {
+    /// The format is sRGB and encodes colors when they are written
+    SrgbFormat,
+    /// The format stores colors as they are written, so shaders encode them to sRGB
+    SrgbShader,
+}
+
+impl OutputTransform {
+    pub fn for_format(format: wgpu::TextureFormat) -> Self {
+        if format.is_srgb() {
+            Self::SrgbFormat
+        } else {
+            Self::SrgbShader
+        }
+    }
+}
+
+/// Texture acquired from the viewport for the current frame.
+pub enum ViewportTexture<'a> {
+    Surface(wgpu::SurfaceTexture),
+    Offscreen(&'a wgpu::Texture),
+}
+
+impl ViewportTexture<'_> {
+    pub fn get_texture(&self) -> &wgpu::Texture {
+        match self {
+            Self::Surface(surface_texture) => &surface_texture.texture,
+            Self::Offscreen(texture) => texture,
+        }
+    }
+
+    /// Presents the frame to the window. Does nothing for offscreen targets.
+    pub fn present(self) {
+        if let Self::Surface(surface_texture) = self {
+            surface_texture.present();
+        }
+    }
 }
 
 impl<'window> Viewport<'window> {
+    pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+    /// Format of the HDR target the scene is rendered into before post-processing
+    pub const SCENE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
+
+    /// Usage flags of the offscreen texture used by headless viewports.
+    const OFFSCREEN_USAGE: wgpu::TextureUsages = wgpu::TextureUsages::RENDER_ATTACHMENT
+        .union(wgpu::TextureUsages::COPY_DST)
+        .union(wgpu::TextureUsages::COPY_SRC)
+        .union(wgpu::TextureUsages::TEXTURE_BINDING);
+
     pub fn new(
         window: &'window Window,
         background: wgpu::Color,
         surface: wgpu::Surface<'window>,
         adapter: &wgpu::Adapter,
+        device: &wgpu::Device,
     ) -> Result<Viewport<'window>> {
         let size = window.inner_size();
         let surface_caps = surface.get_capabilities(adapter);
+        // Prefer sRGB formats, web surfaces only offer linear ones, see `OutputTransform`
         let surface_format = surface_caps
             .formats
             .iter()
             .copied()
             .find(|format| format.is_srgb())
-            .unwrap_or(wgpu::TextureFormat::Rgba8Unorm);
+            .or(surface_caps.formats.first().copied())
+            .ok_or_eyre("Surface is incompatible with the adapter")?;
+
+        // Frames can only be read back if the surface allows copying from it
+        let mut usage = wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_DST;
+        if surface_caps.usages.contains(wgpu::TextureUsages::COPY_SRC) {
+            usage |= wgpu::TextureUsages::COPY_SRC;
+        }
 
         let config = wgpu::SurfaceConfiguration {
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_DST,
+            usage,
             format: surface_format,
             width: size.width,
             height: size.height,
@@ -37,18 +112,68 @@ impl<'window> Viewport<'window> {
             view_formats: vec![],
             desired_maximum_frame_latency: 2,
         };
+        let (depth_texture, depth_view) = create_depth_texture(&config, 1, device);
 
         Ok(Self {
-            window,
-            surface,
+            target: ViewportTarget::Surface {
+                window,
+                surface,
+            },
+            depth_texture,
+            depth_view,
             config,
             size,
             background,
+            sample_count: 1,
+            supported_sample_counts: get_supported_sample_counts(adapter, device),
+        })
+    }
+
+    /// Creates a viewport that renders into an offscreen texture instead of a window surface.
+    pub fn new_offscreen(
+        size: PhysicalSize<u32>,
+        background: wgpu::Color,
+        format: wgpu::TextureFormat,
+        adapter: &wgpu::Adapter,
+        device: &wgpu::Device,
+    ) -> Result<Viewport<'window>> {
+        let config = wgpu::SurfaceConfiguration {
+            usage: Self::OFFSCREEN_USAGE,
+            format,
+            width: size.width.max(1),
+            height: size.height.max(1),
+            present_mode: wgpu::PresentMode::AutoNoVsync,
+            alpha_mode: wgpu::CompositeAlphaMode::Opaque,
+            view_formats: vec![],
+            desired_maximum_frame_latency: 2,
+        };
+        let texture = create_offscreen_texture(&config, device);
+        let (depth_texture, depth_view) = create_depth_texture(&config, 1, device);
+
+        Ok(Self {
+            target: ViewportTarget::Offscreen {
+                texture,
+            },
+            depth_texture,
+            depth_view,
+            size: PhysicalSize::new(config.width, config.height),
+            config,
+            background,
+            sample_count: 1,
+            supported_sample_counts: get_supported_sample_counts(adapter, device),
         })
     }
 
-    pub fn get_window(&self) -> &Window {
-        self.window
+    /// Returns the window this viewport presents to, or `None` for offscreen viewports.
+    pub fn get_window(&self) -> Option<&Window> {
+        match &self.target {
+            ViewportTarget::Surface { window, .. } => Some(window),
+            ViewportTarget::Offscreen { .. } => None,
+        }
+    }
+
+    pub fn is_offscreen(&self) -> bool {
+        matches!(self.target, ViewportTarget::Offscreen { .. })
     }
 
     pub fn get_config(&self) -> &wgpu::SurfaceConfiguration {
@@ -65,20 +190,82 @@ impl<'window> Viewport<'window> {
 
     pub fn get_current_texture(
         &self,
-    ) -> core::result::Result<wgpu::SurfaceTexture, wgpu::SurfaceError> {
-        self.surface.get_current_texture()
+    ) -> core::result::Result<ViewportTexture<'_>, wgpu::SurfaceError> {
+        match &self.target {
+            ViewportTarget::Surface { surface, .. } => {
+                surface.get_current_texture().map(ViewportTexture::Surface)
+            }
+            ViewportTarget::Offscreen { texture } => Ok(ViewportTexture::Offscreen(texture)),
+        }
     }
 
     pub fn get_surface_format(&self) -> &wgpu::TextureFormat {
         &self.config.format
     }
 
+    pub fn get_output_transform(&self) -> OutputTransform {
+        OutputTransform::for_format(self.config.format)
+    }
+
+    /// Render materials target this format unless built for another one
+    pub fn get_scene_format(&self) -> wgpu::TextureFormat {
+        Self::SCENE_FORMAT
+    }
+
+    /// Samples per pixel of the scene's color and depth targets, 1 without multisampling
+    pub fn get_sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    /// The sample counts the adapter supports for both the scene and the depth format
+    pub fn get_supported_sample_counts(&self) -> &[u32] {
+        &self.supported_sample_counts
+    }
+
+    /// Recreates the depth texture with `sample_count` samples per pixel.
+    /// Use `Renderer::set_sample_count` to also rebuild the render materials.
+    pub fn set_sample_count(&mut self, sample_count: u32, device: &wgpu::Device) -> Result<()> {
+        if !self.supported_sample_counts.contains(&sample_count) {
+            return Err(eyre!(
+                "Unsupported sample count {sample_count}, the adapter supports {:?}",
+                self.supported_sample_counts,
+            ));
+        }
+        self.sample_count = sample_count;
+        (self.depth_texture, self.depth_view) = create_depth_texture(&self.config, sample_count, device);
+        Ok(())
+    }
+
+    pub fn get_depth_texture(&self) -> &wgpu::Texture {
+        &self.depth_texture
+    }
+
+    pub fn get_depth_view(&self) -> &wgpu::TextureView {
+        &self.depth_view
+    }
+
     pub fn resize(&mut self, new_size: PhysicalSize<u32>, device: &wgpu::Device) {
         if new_size.width > 0 && new_size.height > 0 {
             self.size = new_size;
             self.config.width = new_size.width;
             self.config.height = new_size.height;
-            self.surface.configure(device, &self.config);
+            match &mut self.target {
+                ViewportTarget::Surface { surface, .. } => {
+                    surface.configure(device, &self.config);
+                }
+                ViewportTarget::Offscreen { texture } => {
+                    *texture = create_offscreen_texture(&self.config, device);
+                }
+            }
+            (self.depth_texture, self.depth_view) = create_depth_texture(&self.config, self.sample_count, device);
+        }
+    }
+
+    /// Returns the offscreen texture of a headless viewport.
+    pub fn get_offscreen_texture(&self) -> Option<&wgpu::Texture> {
+        match &self.target {
+            ViewportTarget::Surface { .. } => None,
+            ViewportTarget::Offscreen { texture } => Some(texture),
         }
     }
 
@@ -89,4 +276,65 @@ impl<'window> Viewport<'window> {
             wgpu::PresentMode::AutoNoVsync
         };
     }
-}
\ No newline at end of file
+}
+
+fn create_offscreen_texture(
+    config: &wgpu::SurfaceConfiguration,
+    device: &wgpu::Device,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Offscreen Viewport Texture"),
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: config.format,
+        usage: config.usage,
+        view_formats: &[],
+    })
+}
+
+fn create_depth_texture(
+    config: &wgpu::SurfaceConfiguration,
+    sample_count: u32,
+    device: &wgpu::Device,
+) -> (wgpu::Texture, wgpu::TextureView) {
+    // Multisampled depth is only rendered to, binding it breaks multisampling on some GL drivers
+    let usage = if sample_count > 1 {
+        wgpu::TextureUsages::RENDER_ATTACHMENT
+    } else {
+        wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING
+    };
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Viewport Depth Texture"),
+        size: wgpu::Extent3d {
+            width: config.width.max(1),
+            height: config.height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format: Viewport::DEPTH_FORMAT,
+        usage,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    (texture, view)
+}
+
+fn get_supported_sample_counts(adapter: &wgpu::Adapter, device: &wgpu::Device) -> Vec<u32> {
+    // Other devices only accept the sample counts WebGPU guarantees
+    let adapter_specific = device.features().contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES);
+    let scene_flags = adapter.get_texture_format_features(Viewport::SCENE_FORMAT).flags;
+    let depth_flags = adapter.get_texture_format_features(Viewport::DEPTH_FORMAT).flags;
+    [1, 2, 4, 8, 16]
+        .into_iter()
+        .filter(|count| adapter_specific || [1, 4].contains(count))
+        .filter(|count| scene_flags.sample_count_supported(*count) && depth_flags.sample_count_supported(*count))
+        .collect()
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use std::time::{Duration, Instant};
+use fragma::renderer::resources::asset_loader::AssetState;
+use fragma::renderer::resources::registry::NameCollision;
+use fragma::renderer::Renderer;
+use common::{assert_matches_golden, create_headless_renderer, render_headless_with_renderer, Tolerance};
+
+fn wait_for_assets(renderer: &mut Renderer) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !renderer.get_load_progress().unwrap().is_done() {
+        assert!(Instant::now() < deadline, "Assets did not finish loading in time");
+        std::thread::sleep(Duration::from_millis(10));
+        renderer.process_loaded_assets().unwrap();
+    }
+}
+
+#[test]
+fn loaded_assets_replace_placeholders() {
+    let Some(image) = render_headless_with_renderer(|renderer, scene| {
+        renderer.request_texture_load("checker", "tests/assets/checker.png")?;
+        renderer.request_obj_load("shapes", "tests/assets/textured_shapes.obj")?;
+        // Referencing the assets must not wait for them
+        scene.add_render_object("basic", "checker", "quad")?;
+        scene.add_render_object("basic", "shapes/checker", "shapes")?;
+
+        wait_for_assets(renderer);
+        Ok(())
+    }) else {
+        return;
+    };
+    assert_matches_golden("loaded_assets_replace_placeholders", &image, Tolerance::default());
+}
+
+#[test]
+fn failed_assets_keep_placeholders() {
+    let Some(image) = render_headless_with_renderer(|renderer, scene| {
+        renderer.request_texture_load("missing texture", "tests/assets/missing.png")?;
+        renderer.request_obj_load("missing model", "tests/assets/missing.obj")?;
+        scene.add_render_object("basic", "missing texture", "quad")?;
+        scene.add_render_object("basic", "white", "missing model")?;
+
+        wait_for_assets(renderer);
+        assert!(matches!(renderer.get_asset_state("missing texture")?, Some(AssetState::Failed(_))));
+        assert!(matches!(renderer.get_asset_state("missing model")?, Some(AssetState::Failed(_))));
+        Ok(())
+    }) else {
+        return;
+    };
+    // The quad shows the white placeholder, the missing model is skipped
+    assert_matches_golden("failed_assets_keep_placeholders", &image, Tolerance::default());
+}
+
+#[test]
+fn load_progress_counts_assets() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    assert_eq!(renderer.get_load_progress().unwrap().get_fraction(), 1.0);
+
+    renderer.request_texture_load("checker", "tests/assets/checker.png").unwrap();
+    renderer.request_texture_load("missing", "tests/assets/missing.png").unwrap();
+    let progress = renderer.get_load_progress().unwrap();
+    assert_eq!(progress.pending, 2);
+    assert_eq!(progress.get_total(), 2);
+    assert_eq!(renderer.get_asset_state("checker").unwrap(), Some(AssetState::Pending));
+
+    wait_for_assets(&mut renderer);
+    let progress = renderer.get_load_progress().unwrap();
+    assert_eq!((progress.loaded, progress.failed), (1, 1));
+    assert_eq!(progress.get_fraction(), 1.0);
+    assert_eq!(renderer.get_asset_state("checker").unwrap(), Some(AssetState::Loaded));
+
+    // Unrequested resources are still validated
+    let mut scene = renderer.create_scene();
+    assert!(scene.add_render_object("basic", "checker/0", "quad").is_err());
+    assert!(scene.add_render_object("basic", "unknown", "quad").is_err());
+}
+
+#[test]
+fn gltf_paths_resolve_the_same_for_both_loaders() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    // Both resolve against the crate root, not the working directory
+    std::env::set_current_dir(env!("CARGO_TARGET_TMPDIR")).unwrap();
+    let path = "tests/assets/textured_quad.gltf";
+    renderer.load_gltf_from_file("immediate", path, NameCollision::Error).unwrap();
+    renderer.request_gltf_load("background", path).unwrap();
+    wait_for_assets(&mut renderer);
+    assert_eq!(renderer.get_asset_state("background").unwrap(), Some(AssetState::Loaded));
+
+    let resources = renderer.get_resources().borrow();
+    let immediate = resources.get_model("immediate").unwrap();
+    let background = resources.get_model("background").unwrap();
+    assert_eq!(immediate.get_mesh_texture_name(0), Some("immediate/0"));
+    assert_eq!(background.get_mesh_texture_name(0), Some("background/0"));
+}
+
+#[test]
+fn later_requests_supersede_pending_ones() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    renderer.request_texture_load("checker", "tests/assets/checker.png").unwrap();
+    renderer.request_texture_load("checker", "tests/assets/missing.png").unwrap();
+    assert_eq!(renderer.get_load_progress().unwrap().get_total(), 1);
+    wait_for_assets(&mut renderer);
+    // Give the first request time to finish, its result must not replace the second one
+    std::thread::sleep(Duration::from_millis(200));
+    renderer.process_loaded_assets().unwrap();
+
+    assert!(matches!(renderer.get_asset_state("checker").unwrap(), Some(AssetState::Failed(_))));
+    assert!(renderer.get_resources().borrow().get_texture("checker").is_err());
+}
@@ -0,0 +1,187 @@
+mod common;
+
+use fragma::renderer::resources::material::render_material::{BlendMode, RenderMaterial};
+use fragma::renderer::resources::mesh::Mesh;
+use fragma::renderer::resources::model::Model;
+use fragma::renderer::resources::registry::NameCollision;
+use fragma::renderer::resources::shader::Shader;
+use fragma::renderer::resources::texture::Texture;
+use fragma::renderer::resources::{CAMERA_BIND_GROUP_LAYOUT_NAME, FRAME_BIND_GROUP_LAYOUT_NAME, SINGLE_TEXTURE_BIND_GROUP_LAYOUT_NAME};
+use fragma::renderer::scene::Scene;
+use fragma::renderer::{Renderer, Transform};
+use common::{create_headless_renderer, linear_to_srgb, WIDTH, HEIGHT};
+use glam::Vec3;
+
+fn add_texture(renderer: &Renderer, name: &str, image: image::RgbaImage) {
+    let texture = Texture::new_from_image(
+        &image.into(),
+        name,
+        renderer.get_device(),
+        renderer.get_queue(),
+        &renderer.get_resources().borrow(),
+    ).unwrap();
+    renderer.get_resources().borrow_mut().add_texture(name, texture, NameCollision::Replace).unwrap();
+}
+
+fn add_color_texture(renderer: &Renderer, name: &str, color: [u8; 4]) {
+    add_texture(renderer, name, image::RgbaImage::from_pixel(1, 1, image::Rgba(color)));
+}
+
+fn add_blended_material(renderer: &Renderer, name: &str, blend_mode: BlendMode) {
+    let mut resources = renderer.get_resources().borrow_mut();
+    let material = RenderMaterial::builder()
+        .with_bind_group_layouts(&[
+            resources.get_bind_group_layout(SINGLE_TEXTURE_BIND_GROUP_LAYOUT_NAME).unwrap(),
+            resources.get_bind_group_layout(CAMERA_BIND_GROUP_LAYOUT_NAME).unwrap(),
+        ])
+        .with_frame_bind_group_layout(resources.get_bind_group_layout(FRAME_BIND_GROUP_LAYOUT_NAME).unwrap())
+        .with_shader(pollster::block_on(Shader::new_from_file("shaders-compiled/basic.spv", renderer.get_device())).unwrap())
+        .with_blend_mode(blend_mode)
+        .with_depth(wgpu::CompareFunction::LessEqual, blend_mode == BlendMode::Opaque, wgpu::DepthBiasState::default())
+        .build(renderer.get_device(), renderer.get_viewport())
+        .unwrap();
+    assert_eq!(material.is_transparent(), blend_mode != BlendMode::Opaque);
+    resources.add_render_material(name, material, NameCollision::Replace).unwrap();
+}
+
+fn srgb_to_linear(value: u8) -> f32 {
+    let value = value as f32 / 255.0;
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn assert_color_near(actual: [u8; 4], expected: [f32; 3]) {
+    let expected = expected.map(linear_to_srgb);
+    let near = actual.iter().zip(expected).all(|(actual, expected)| actual.abs_diff(expected) <= 2);
+    assert!(near, "expected {expected:?}, got {actual:?}");
+}
+
+fn render_center(renderer: &mut Renderer, scene: &Scene) -> [u8; 4] {
+    let mut camera = renderer.create_camera();
+    renderer.render(&mut camera, scene).unwrap();
+    renderer.read_frame().unwrap().get_pixel(WIDTH / 2, HEIGHT / 2).0
+}
+
+#[test]
+fn blend_modes_combine_with_the_target() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    add_color_texture(&renderer, "gray", [128, 128, 128, 255]);
+    add_color_texture(&renderer, "half red", [255, 0, 0, 128]);
+    let base = srgb_to_linear(128);
+    let alpha = 128.0 / 255.0;
+
+    let cases = [
+        (BlendMode::Opaque, [1.0, 0.0, 0.0]),
+        (BlendMode::Alpha, [alpha + base * (1.0 - alpha), base * (1.0 - alpha), base * (1.0 - alpha)]),
+        (BlendMode::Premultiplied, [1.0 + base * (1.0 - alpha), base * (1.0 - alpha), base * (1.0 - alpha)]),
+        (BlendMode::Additive, [base + alpha, base, base]),
+        (BlendMode::Multiply, [base, 0.0, 0.0]),
+    ];
+    for (blend_mode, expected) in cases {
+        add_blended_material(&renderer, "blended", blend_mode);
+        let mut scene = renderer.create_scene();
+        // Added first but in front, blended objects are still drawn over opaque ones
+        let overlay = scene.add_render_object("blended", "half red", "quad").unwrap();
+        scene.set_render_object_transform(overlay, Transform::from_translation(Vec3::new(0.0, 0.0, 1.0))).unwrap();
+        scene.add_render_object("basic", "gray", "quad").unwrap();
+        assert_color_near(render_center(&mut renderer, &scene), expected);
+    }
+}
+
+#[test]
+fn transparent_objects_are_sorted_back_to_front() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    add_color_texture(&renderer, "half red", [255, 0, 0, 128]);
+    add_color_texture(&renderer, "half green", [0, 255, 0, 128]);
+    let alpha = 128.0 / 255.0;
+    let blend = |source: [f32; 3], target: [f32; 3]| -> [f32; 3] {
+        std::array::from_fn(|i| source[i] * alpha + target[i] * (1.0 - alpha))
+    };
+
+    let mut scene = renderer.create_scene();
+    let green = scene.add_render_object("basic alpha", "half green", "quad").unwrap();
+    let red = scene.add_render_object("basic alpha", "half red", "quad").unwrap();
+    scene.add_render_object("basic", "white", "quad").unwrap();
+
+    let near = Transform::from_translation(Vec3::new(0.0, 0.0, 1.0));
+    let far = Transform::from_translation(Vec3::new(0.0, 0.0, 0.5));
+    scene.set_render_object_transform(green, near).unwrap();
+    scene.set_render_object_transform(red, far).unwrap();
+    let expected = blend([0.0, 1.0, 0.0], blend([1.0, 0.0, 0.0], [1.0; 3]));
+    assert_color_near(render_center(&mut renderer, &scene), expected);
+
+    scene.set_render_object_transform(green, far).unwrap();
+    scene.set_render_object_transform(red, near).unwrap();
+    let expected = blend([1.0, 0.0, 0.0], blend([0.0, 1.0, 0.0], [1.0; 3]));
+    assert_color_near(render_center(&mut renderer, &scene), expected);
+}
+
+#[test]
+fn transparent_meshes_are_sorted_by_their_bounds() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    add_color_texture(&renderer, "half red", [255, 0, 0, 128]);
+    add_color_texture(&renderer, "half green", [0, 255, 0, 128]);
+    // The quad is in front of the model's origin
+    let mut mesh = Mesh::new_quad();
+    for vertex in &mut mesh.vertices {
+        vertex.position.z += 1.0;
+    }
+    let model = Model::new(vec![mesh], renderer.get_device()).unwrap();
+    renderer.get_resources().borrow_mut().add_model("offset quad", model, NameCollision::Error).unwrap();
+
+    let mut scene = renderer.create_scene();
+    scene.add_render_object("basic alpha", "half green", "offset quad").unwrap();
+    let red = scene.add_render_object("basic alpha", "half red", "quad").unwrap();
+    scene.set_render_object_transform(red, Transform::from_translation(Vec3::new(0.0, 0.0, 0.5))).unwrap();
+    scene.add_render_object("basic", "white", "quad").unwrap();
+
+    let alpha = 128.0 / 255.0;
+    let blend = |source: [f32; 3], target: [f32; 3]| -> [f32; 3] {
+        std::array::from_fn(|i| source[i] * alpha + target[i] * (1.0 - alpha))
+    };
+    let expected = blend([0.0, 1.0, 0.0], blend([1.0, 0.0, 0.0], [1.0; 3]));
+    assert_color_near(render_center(&mut renderer, &scene), expected);
+}
+
+#[test]
+fn cutout_discards_transparent_texels() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    // Transparent black everywhere but an opaque white center
+    let mut image = image::RgbaImage::from_pixel(8, 8, image::Rgba([0, 0, 0, 0]));
+    for (x, y) in [(3, 3), (3, 4), (4, 3), (4, 4)] {
+        image.put_pixel(x, y, image::Rgba([255, 255, 255, 255]));
+    }
+    add_texture(&renderer, "white center", image);
+
+    let mut camera = renderer.create_camera();
+    renderer.render(&mut camera, &renderer.create_scene()).unwrap();
+    let background = renderer.read_frame().unwrap().get_pixel(0, 0).0;
+
+    let mut scene = renderer.create_scene();
+    scene.add_render_object("basic", "white center", "quad").unwrap();
+    renderer.render(&mut camera, &scene).unwrap();
+    let opaque = renderer.read_frame().unwrap();
+
+    let mut scene = renderer.create_scene();
+    scene.add_render_object("basic cutout", "white center", "quad").unwrap();
+    renderer.render(&mut camera, &scene).unwrap();
+    let cutout = renderer.read_frame().unwrap();
+
+    assert_eq!(opaque.get_pixel(WIDTH / 2, HEIGHT / 2).0, [255, 255, 255, 255]);
+    assert_eq!(cutout.get_pixel(WIDTH / 2, HEIGHT / 2).0, [255, 255, 255, 255]);
+    // Inside the quad but outside the opaque texels
+    let (x, y) = (WIDTH / 2 + 8, HEIGHT / 2 + 8);
+    assert_eq!(opaque.get_pixel(x, y).0[..3], [0, 0, 0]);
+    assert_eq!(cutout.get_pixel(x, y).0, background);
+}
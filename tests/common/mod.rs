@@ -0,0 +1,198 @@
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+use color_eyre::eyre::Result;
+use fragma::renderer::resources::texture::Texture;
+use fragma::renderer::scene::Scene;
+use fragma::renderer::Renderer;
+use winit::dpi::PhysicalSize;
+
+/* Golden-image test harness.
+ * Scenes are rendered headlessly on a software adapter, read back and compared
+ * against the reference images in `tests/golden`.
+ * Set `FRAGMA_UPDATE_GOLDEN=1` to (re)write the reference images. */
+
+pub const WIDTH: u32 = 64;
+pub const HEIGHT: u32 = 64;
+
+const UPDATE_GOLDEN_ENV: &str = "FRAGMA_UPDATE_GOLDEN";
+
+/// How far a rendered image may deviate from its reference image.
+#[derive(Debug, Clone, Copy)]
+pub struct Tolerance {
+    /// Largest allowed difference of a single channel of a pixel
+    pub max_channel_difference: u8,
+    /// Number of pixels allowed to exceed `max_channel_difference`
+    pub max_mismatched_pixels: usize,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Self {
+            max_channel_difference: 2,
+            max_mismatched_pixels: 0,
+        }
+    }
+}
+
+/// Creates a headless renderer on a software adapter.
+/// Returns `None` when the machine has no software adapter, in which case the test is skipped.
+pub fn create_headless_renderer() -> Option<Renderer<'static>> {
+    if !software_adapter_available() {
+        eprintln!("No software adapter available, skipping test");
+        return None;
+    }
+
+    let renderer = pollster::block_on(
+        Renderer::new_headless(PhysicalSize::new(WIDTH, HEIGHT), true)
+    ).expect("Failed to create headless renderer");
+
+    Some(renderer)
+}
+
+/// Renders a single frame of the scene built by `configure` and reads it back.
+/// Returns `None` when the machine has no software adapter, in which case the test is skipped.
+pub fn render_headless(
+    configure: impl FnOnce(&mut Scene) -> Result<()>,
+) -> Option<image::RgbaImage> {
+    render_headless_with_renderer(|_, scene| configure(scene))
+}
+
+/// Like `render_headless`, but also gives `configure` access to the renderer,
+/// e.g. to load resources.
+pub fn render_headless_with_renderer(
+    configure: impl FnOnce(&mut Renderer<'static>, &mut Scene) -> Result<()>,
+) -> Option<image::RgbaImage> {
+    let mut renderer = create_headless_renderer()?;
+    let mut camera = renderer.create_camera();
+    let mut scene = renderer.create_scene();
+
+    configure(&mut renderer, &mut scene).expect("Failed to configure scene");
+    renderer.render(&mut camera, &scene).expect("Failed to render headless frame");
+    let image = renderer.read_frame().expect("Failed to read back frame");
+
+    Some(image)
+}
+
+/// Renders `scene` with a default camera and reads back the colors of `pixels`
+pub fn render_pixels(renderer: &mut Renderer, scene: &Scene, pixels: &[(u32, u32)]) -> Vec<[u8; 4]> {
+    let mut camera = renderer.create_camera();
+    renderer.render(&mut camera, scene).expect("Failed to render headless frame");
+    let image = renderer.read_frame().expect("Failed to read back frame");
+    pixels.iter().map(|(x, y)| image.get_pixel(*x, *y).0).collect()
+}
+
+/// Creates a 1x1 texture of `color`
+pub fn create_solid_texture(renderer: &Renderer, color: [u8; 4]) -> Result<Texture> {
+    let image = image::RgbaImage::from_pixel(1, 1, image::Rgba(color));
+    Texture::new_from_image(
+        &image.into(),
+        "solid",
+        renderer.get_device(),
+        renderer.get_queue(),
+        &renderer.get_resources().borrow(),
+    )
+}
+
+/// Encodes a linear color channel the way an sRGB target stores it
+pub fn linear_to_srgb(value: f32) -> u8 {
+    let value = value.clamp(0.0, 1.0);
+    let encoded = if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    };
+    (encoded * 255.0).round() as u8
+}
+
+/// Compares `actual` to the reference image `tests/golden/<name>.png`.
+/// On failure the actual and diff images are written next to the build's temporary files.
+pub fn assert_matches_golden(name: &str, actual: &image::RgbaImage, tolerance: Tolerance) {
+    let golden_path = golden_dir().join(format!("{name}.png"));
+
+    if std::env::var_os(UPDATE_GOLDEN_ENV).is_some() {
+        actual.save(&golden_path).expect("Failed to write golden image");
+        return;
+    }
+
+    let expected = match image::open(&golden_path) {
+        Ok(expected) => expected.to_rgba8(),
+        Err(e) => {
+            let actual_path = write_output(name, "actual", actual);
+            panic!(
+                "Failed to open golden image {}: {e}\n\
+                 Actual image written to {}; run with {UPDATE_GOLDEN_ENV}=1 to accept it",
+                golden_path.display(),
+                actual_path.display(),
+            );
+        }
+    };
+
+    assert_eq!(
+        expected.dimensions(),
+        actual.dimensions(),
+        "Golden image {name} has a different size",
+    );
+
+    let (diff, mismatched_pixels) = diff_images(&expected, actual, tolerance.max_channel_difference);
+    if mismatched_pixels > tolerance.max_mismatched_pixels {
+        let actual_path = write_output(name, "actual", actual);
+        let diff_path = write_output(name, "diff", &diff);
+        panic!(
+            "Golden image {name} mismatch: {mismatched_pixels} pixels differ by more than {} \
+             (allowed: {})\nActual: {}\nDiff: {}",
+            tolerance.max_channel_difference,
+            tolerance.max_mismatched_pixels,
+            actual_path.display(),
+            diff_path.display(),
+        );
+    }
+}
+
+/// Returns an image highlighting mismatched pixels in red on top of a dimmed
+/// copy of the expected image, and the number of mismatched pixels.
+fn diff_images(
+    expected: &image::RgbaImage,
+    actual: &image::RgbaImage,
+    max_channel_difference: u8,
+) -> (image::RgbaImage, usize) {
+    let mut mismatched_pixels = 0;
+    let diff = image::RgbaImage::from_fn(expected.width(), expected.height(), |x, y| {
+        let e = expected.get_pixel(x, y);
+        let a = actual.get_pixel(x, y);
+        let mismatch = e.0
+            .iter()
+            .zip(a.0.iter())
+            .any(|(e, a)| e.abs_diff(*a) > max_channel_difference);
+        if mismatch {
+            mismatched_pixels += 1;
+            image::Rgba([255, 0, 0, 255])
+        } else {
+            let luma = (e[0] as u32 + e[1] as u32 + e[2] as u32) / 3 / 4;
+            image::Rgba([luma as u8, luma as u8, luma as u8, 255])
+        }
+    });
+    (diff, mismatched_pixels)
+}
+
+fn software_adapter_available() -> bool {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::util::backend_bits_from_env().unwrap_or(wgpu::Backends::all()),
+        ..Default::default()
+    });
+    pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::default(),
+        compatible_surface: None,
+        force_fallback_adapter: true,
+    })).is_some()
+}
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden")
+}
+
+fn write_output(name: &str, suffix: &str, image: &image::RgbaImage) -> PathBuf {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("{name}.{suffix}.png"));
+    image.save(&path).expect("Failed to write test output image");
+    path
+}
@@ -0,0 +1,238 @@
+mod common;
+
+use std::rc::Rc;
+use fragma::renderer::resources::material::compute_material::ComputeMaterial;
+use fragma::renderer::resources::registry::NameCollision;
+use fragma::renderer::resources::shader::Shader;
+use fragma::renderer::resources::COMPUTE_STORAGE_BIND_GROUP_LAYOUT_NAME;
+use fragma::renderer::{ComputeDispatch, Renderer};
+use common::{create_headless_renderer, WIDTH, HEIGHT};
+use wgpu::util::DeviceExt;
+
+const INDEX_SHADER: &str = "
+@group(0) @binding(0)
+var<storage, read_write> data: array<u32>;
+
+// Writes one plus the index of the invocation
+@compute @workgroup_size(64)
+fn main_1d(@builtin(global_invocation_id) id: vec3<u32>) {
+    if (id.x < arrayLength(&data)) {
+        data[id.x] = id.x + 1u;
+    }
+}
+
+@compute @workgroup_size(2, 2, 2)
+fn main_3d(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x + id.y * 4u + id.z * 16u;
+    data[index] = index + 1u;
+}
+";
+
+const RED_SHADER: &str = "
+@group(0) @binding(0) var output_texture: texture_storage_2d<rgba16float, write>;
+
+@compute @workgroup_size(8, 8, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    textureStore(output_texture, vec2<i32>(id.xy), vec4<f32>(1.0, 0.0, 0.0, 1.0));
+}
+";
+
+// Values outside of [0, 1] are clamped when read back
+const HALF_SHADER: &str = "
+@group(0) @binding(0) var output_texture: texture_storage_2d<rgba16float, write>;
+
+@compute @workgroup_size(8, 8, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    textureStore(output_texture, vec2<i32>(id.xy), vec4<f32>(0.5, 0.25, 2.0, 1.0));
+}
+";
+
+fn create_storage_layout(renderer: &Renderer) -> wgpu::BindGroupLayout {
+    renderer.get_device().create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Storage Buffer Layout"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: false },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    })
+}
+
+fn add_index_material(renderer: &Renderer, name: &str, entry_point: &str) {
+    let layout = create_storage_layout(renderer);
+    let material = ComputeMaterial::builder()
+        .with_bind_group_layouts(&[&layout])
+        .with_shader(Shader::new_from_wgsl(INDEX_SHADER, "index.wgsl", renderer.get_device()).unwrap())
+        .with_entry_point(entry_point)
+        .build(renderer.get_device())
+        .unwrap();
+    renderer.get_resources().borrow_mut().add_compute_material(name, material, NameCollision::Error).unwrap();
+}
+
+fn create_data_buffer(renderer: &Renderer, len: usize) -> wgpu::Buffer {
+    renderer.get_device().create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Data Buffer"),
+        contents: bytemuck::cast_slice(&vec![0u32; len]),
+        usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+    })
+}
+
+/// Dispatches the material `name` once over `data` and reads it back
+fn dispatch_over(renderer: &mut Renderer, name: &str, dispatch: ComputeDispatch, data: &wgpu::Buffer) -> Vec<u32> {
+    let mut camera = renderer.create_camera();
+    let mut scene = renderer.create_scene();
+    let id = scene.add_compute_object(name, dispatch).unwrap();
+    scene.get_compute_object_mut(id).unwrap().set_bindings(
+        0,
+        &[data.as_entire_binding()],
+        renderer.get_device(),
+        &renderer.get_resources().borrow(),
+    ).unwrap();
+    renderer.render(&mut camera, &scene).unwrap();
+    bytemuck::cast_slice(&renderer.read_buffer(data).unwrap()).to_vec()
+}
+
+#[test]
+fn workgroup_sizes_are_reflected_from_shaders() {
+    let Some(renderer) = create_headless_renderer() else {
+        return;
+    };
+    add_index_material(&renderer, "index 1d", "main_1d");
+    add_index_material(&renderer, "index 3d", "main_3d");
+    let resources = renderer.get_resources().borrow();
+    assert_eq!(resources.get_compute_material("basic compute").unwrap().get_workgroup_size(), [16, 16, 1]);
+    assert_eq!(resources.get_compute_material("index 1d").unwrap().get_workgroup_size(), [64, 1, 1]);
+    assert_eq!(resources.get_compute_material("index 3d").unwrap().get_workgroup_size(), [2, 2, 2]);
+
+    // Shaders from descriptors cannot be reflected
+    let shader = Shader::new_from_descriptor(wgpu::ShaderModuleDescriptor {
+        label: None,
+        source: wgpu::ShaderSource::Wgsl(INDEX_SHADER.into()),
+    }, renderer.get_device());
+    let layout = create_storage_layout(&renderer);
+    let builder = ComputeMaterial::builder()
+        .with_bind_group_layouts(&[&layout])
+        .with_shader(shader.clone())
+        .with_entry_point("main_1d");
+    assert!(builder.build(renderer.get_device()).is_err());
+    let material = ComputeMaterial::builder()
+        .with_bind_group_layouts(&[&layout])
+        .with_shader(shader)
+        .with_entry_point("main_1d")
+        .with_workgroup_size([64, 1, 1])
+        .build(renderer.get_device())
+        .unwrap();
+    assert_eq!(material.get_workgroup_size(), [64, 1, 1]);
+}
+
+#[test]
+fn dispatches_cover_the_requested_invocations() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    add_index_material(&renderer, "index 1d", "main_1d");
+    add_index_material(&renderer, "index 3d", "main_3d");
+    let expected = (1..=100).collect::<Vec<u32>>();
+
+    // 100 invocations need two workgroups of 64, the shader skips the excess ones
+    let data = create_data_buffer(&renderer, 100);
+    assert_eq!(dispatch_over(&mut renderer, "index 1d", ComputeDispatch::Invocations([100, 1, 1]), &data), expected);
+
+    let data = create_data_buffer(&renderer, 100);
+    let result = dispatch_over(&mut renderer, "index 1d", ComputeDispatch::Workgroups([1, 1, 1]), &data);
+    assert_eq!(result[..64], expected[..64]);
+    assert!(result[64..].iter().all(|value| *value == 0));
+
+    let data = create_data_buffer(&renderer, 64);
+    assert_eq!(dispatch_over(&mut renderer, "index 3d", ComputeDispatch::Invocations([4, 4, 4]), &data), expected[..64]);
+}
+
+#[test]
+fn invalid_bindings_are_rejected() {
+    let Some(renderer) = create_headless_renderer() else {
+        return;
+    };
+    add_index_material(&renderer, "index 1d", "main_1d");
+    let data = create_data_buffer(&renderer, 4);
+    let mut scene = renderer.create_scene();
+    let id = scene.add_compute_object("index 1d", ComputeDispatch::Invocations([4, 1, 1])).unwrap();
+    let compute_object = scene.get_compute_object_mut(id).unwrap();
+    let resources = renderer.get_resources().borrow();
+    let mut set_bindings = |index, bindings: &[wgpu::BindingResource]| {
+        compute_object.set_bindings(index, bindings, renderer.get_device(), &resources)
+    };
+
+    assert!(set_bindings(1, &[data.as_entire_binding()]).is_err());
+    assert!(set_bindings(0, &[]).is_err());
+    assert!(set_bindings(0, &[data.as_entire_binding(), data.as_entire_binding()]).is_err());
+    assert!(set_bindings(0, &[data.as_entire_binding()]).is_ok());
+}
+
+#[test]
+fn indirect_dispatch_reads_workgroup_counts_from_buffer() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    add_index_material(&renderer, "index 1d", "main_1d");
+    // The first arguments are skipped by the offset
+    let arguments = renderer.get_device().create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("Indirect Arguments"),
+        contents: bytemuck::cast_slice(&[4u32, 1, 1, 2, 1, 1]),
+        usage: wgpu::BufferUsages::INDIRECT,
+    });
+    let dispatch = ComputeDispatch::Indirect {
+        buffer: Rc::new(arguments),
+        offset: 12,
+    };
+
+    let data = create_data_buffer(&renderer, 200);
+    let result = dispatch_over(&mut renderer, "index 1d", dispatch, &data);
+    assert_eq!(result[..128], (1..=128).collect::<Vec<u32>>());
+    assert!(result[128..].iter().all(|value| *value == 0));
+}
+
+fn add_output_material(renderer: &Renderer, name: &str, shader: &str) {
+    let mut resources = renderer.get_resources().borrow_mut();
+    let material = ComputeMaterial::builder()
+        .with_bind_group_layouts(&[resources.get_bind_group_layout(COMPUTE_STORAGE_BIND_GROUP_LAYOUT_NAME).unwrap()])
+        .with_shader(Shader::new_from_wgsl(shader, &format!("{name}.wgsl"), renderer.get_device()).unwrap())
+        .build(renderer.get_device())
+        .unwrap();
+    resources.add_compute_material(name, material, NameCollision::Error).unwrap();
+}
+
+#[test]
+fn output_texture_uses_the_requested_material() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    add_output_material(&renderer, "red", RED_SHADER);
+    let mut camera = renderer.create_camera();
+    let mut scene = renderer.create_scene();
+    scene.add_compute_object_with_output_texture("red", WIDTH, HEIGHT).unwrap();
+    renderer.render(&mut camera, &scene).unwrap();
+    let image = renderer.read_frame().unwrap();
+    assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(WIDTH - 1, HEIGHT - 1).0, [255, 0, 0, 255]);
+}
+
+#[test]
+fn compute_output_textures_can_be_read_back() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    add_output_material(&renderer, "half", HALF_SHADER);
+    let mut camera = renderer.create_camera();
+    let mut scene = renderer.create_scene();
+    let id = scene.add_compute_object_with_output_texture("half", 16, 8).unwrap();
+    renderer.render(&mut camera, &scene).unwrap();
+
+    let image = scene.read_compute_output_texture(id).unwrap();
+    assert_eq!(image.dimensions(), (16, 8));
+    assert!(image.pixels().all(|pixel| pixel.0 == [128, 64, 255, 255]), "{:?}", image.get_pixel(0, 0));
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use fragma::renderer::resources::registry::NameCollision;
+use fragma::renderer::{DrawStatistics, Renderer, Transform};
+use fragma::renderer::scene::Scene;
+use common::{create_headless_renderer, create_solid_texture, WIDTH, HEIGHT};
+use glam::Vec3;
+
+fn render(renderer: &mut Renderer, scene: &Scene) -> DrawStatistics {
+    let mut camera = renderer.create_camera();
+    renderer.render(&mut camera, scene).unwrap();
+    renderer.get_draw_statistics()
+}
+
+#[test]
+fn objects_sharing_state_are_drawn_together() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    assert_eq!(renderer.get_draw_statistics(), DrawStatistics::default());
+    let mut scene = renderer.create_scene();
+    // Interleaved, so drawing in insertion order would switch pipelines for every object
+    for i in 0..6 {
+        let material = if i % 2 == 0 { "basic" } else { "basic cutout" };
+        let id = scene.add_render_object(material, "white", "quad").unwrap();
+        let transform = Transform::from_translation(Vec3::new(i as f32 - 2.5, 0.0, -i as f32));
+        scene.set_render_object_transform(id, transform).unwrap();
+    }
+    let hidden = scene.add_render_object("basic", "tree", "triangle").unwrap();
+    scene.set_render_object_visible(hidden, false).unwrap();
+
+    let statistics = render(&mut renderer, &scene);
+    assert_eq!(statistics.draw_calls, 6);
+    assert_eq!(statistics.instances, 6);
+    assert_eq!(statistics.pipeline_changes, 2);
+    assert_eq!(statistics.vertex_buffer_changes, 1);
+    assert_eq!(statistics.index_buffer_changes, 1);
+    // Every object has its own transform
+    assert_eq!(statistics.push_constant_changes, 6);
+    assert!(statistics.skipped_state_changes > 0);
+    // The same scene records the same commands every frame
+    assert_eq!(render(&mut renderer, &scene), statistics);
+
+    scene.set_render_object_visible(hidden, true).unwrap();
+    let statistics = render(&mut renderer, &scene);
+    assert_eq!(statistics.draw_calls, 7);
+    assert_eq!(statistics.pipeline_changes, 2);
+    // The triangle is drawn between the quads of the first pipeline and those of the second
+    assert_eq!(statistics.vertex_buffer_changes, 3);
+}
+
+#[test]
+fn instances_are_counted() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    let mut scene = renderer.create_scene();
+    let instances = [fragma::renderer::Instance::new(Transform::IDENTITY); 5];
+    scene.add_instanced_render_object("basic instanced", "white", "quad", &instances).unwrap();
+    scene.add_render_object("basic", "white", "quad").unwrap();
+
+    let statistics = render(&mut renderer, &scene);
+    assert_eq!(statistics.draw_calls, 2);
+    assert_eq!(statistics.instances, 6);
+}
+
+#[test]
+fn replaced_resources_are_resolved_again() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    let mut scene = renderer.create_scene();
+    let generation = renderer.get_resources().borrow().get_generation();
+    let texture = create_solid_texture(&renderer, [255, 0, 0, 255]).unwrap();
+    renderer.get_resources().borrow_mut().add_texture("solid", texture, NameCollision::Error).unwrap();
+    assert_ne!(renderer.get_resources().borrow().get_generation(), generation);
+    scene.add_render_object("basic", "solid", "quad").unwrap();
+
+    render(&mut renderer, &scene);
+    let center = renderer.read_frame().unwrap().get_pixel(WIDTH / 2, HEIGHT / 2).0;
+    assert_eq!(center, [255, 0, 0, 255]);
+
+    let texture = create_solid_texture(&renderer, [0, 0, 255, 255]).unwrap();
+    renderer.get_resources().borrow_mut().add_texture("solid", texture, NameCollision::Replace).unwrap();
+    render(&mut renderer, &scene);
+    let center = renderer.read_frame().unwrap().get_pixel(WIDTH / 2, HEIGHT / 2).0;
+    assert_eq!(center, [0, 0, 255, 255]);
+
+    // Removing a missing resource leaves the resolved state valid
+    let generation = renderer.get_resources().borrow().get_generation();
+    assert!(renderer.get_resources().borrow_mut().remove_texture("missing").is_none());
+    assert_eq!(renderer.get_resources().borrow().get_generation(), generation);
+
+    renderer.get_resources().borrow_mut().remove_texture("solid");
+    let mut camera = renderer.create_camera();
+    assert!(renderer.render(&mut camera, &scene).is_err());
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use std::path::Path;
+use common::{assert_matches_golden, create_headless_renderer, Tolerance, WIDTH, HEIGHT};
+
+#[test]
+fn frame_capture_holds_the_next_frame() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    let mut camera = renderer.create_camera();
+    let mut scene = renderer.create_scene();
+    scene.add_render_object("basic", "tree", "triangle").unwrap();
+
+    renderer.request_frame_capture();
+    assert!(renderer.take_captured_frame().is_none());
+    renderer.render(&mut camera, &scene).unwrap();
+    let captured = renderer.take_captured_frame().unwrap();
+    assert_eq!(captured, renderer.read_frame().unwrap());
+    assert!(renderer.take_captured_frame().is_none());
+
+    // Only the requested frame is captured
+    renderer.render(&mut camera, &scene).unwrap();
+    assert!(renderer.take_captured_frame().is_none());
+}
+
+#[test]
+fn saved_png_matches_the_frame() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    let mut camera = renderer.create_camera();
+    let mut scene = renderer.create_scene();
+    scene.add_render_object("basic", "tree", "triangle").unwrap();
+    renderer.render(&mut camera, &scene).unwrap();
+
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("saved_png_matches_the_frame.png");
+    renderer.save_frame_png(&path).unwrap();
+    let saved = image::open(&path).unwrap().to_rgba8();
+    assert_eq!(saved, renderer.read_frame().unwrap());
+}
+
+#[test]
+fn compute_output_texture_is_read_back() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    let mut camera = renderer.create_camera();
+    let mut scene = renderer.create_scene();
+    let id = scene.add_compute_object_with_output_texture("basic compute", WIDTH / 2, HEIGHT).unwrap();
+    renderer.render(&mut camera, &scene).unwrap();
+
+    let image = scene.read_compute_output_texture(id).unwrap();
+    assert_eq!(image.dimensions(), (WIDTH / 2, HEIGHT));
+    assert_matches_golden("compute_output_texture_is_read_back", &image, Tolerance::default());
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use glam::Vec2;
+use fragma::renderer::resources::material::render_material::RenderMaterial;
+use fragma::renderer::resources::registry::NameCollision;
+use fragma::renderer::resources::shader::Shader;
+use fragma::renderer::resources::{CAMERA_BIND_GROUP_LAYOUT_NAME, FRAME_BIND_GROUP_LAYOUT_NAME, SINGLE_TEXTURE_BIND_GROUP_LAYOUT_NAME};
+use common::{create_headless_renderer, WIDTH, HEIGHT};
+
+// Each channel is lit when one of the frame values is as expected
+const FRAME_SHADER: &str = "
+struct ShaderCameraUniform {
+    viewproj: mat4x4<f32>,
+    near: f32,
+    far: f32,
+    _padding: vec2<f32>,
+}
+
+struct ShaderFrameUniform {
+    resolution: vec2<f32>,
+    mouse: vec2<f32>,
+    camera_position: vec3<f32>,
+    time: f32,
+    delta_time: f32,
+    frame: u32,
+    _padding: vec2<u32>,
+}
+
+@group(1) @binding(0)
+var<uniform> camera: ShaderCameraUniform;
+@group(2) @binding(0)
+var<uniform> frame: ShaderFrameUniform;
+
+@vertex
+fn vs_main(@location(0) position: vec3<f32>) -> @builtin(position) vec4<f32> {
+    return camera.viewproj * vec4<f32>(position, 1.0);
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    let frame_matches = frame.frame == 2u;
+    let inputs_match = all(frame.resolution == vec2<f32>(64.0, 64.0)) && all(frame.mouse == vec2<f32>(10.0, 20.0));
+    let camera_matches = all(frame.camera_position == vec3<f32>(0.0, 0.0, 5.0));
+    return vec4<f32>(select(0.0, 1.0, frame_matches), select(0.0, 1.0, inputs_match), select(0.0, 1.0, camera_matches), 1.0);
+}
+";
+
+#[test]
+fn frame_uniform_is_updated_each_frame() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    {
+        let mut resources = renderer.get_resources().borrow_mut();
+        let material = RenderMaterial::builder()
+            .with_bind_group_layouts(&[
+                resources.get_bind_group_layout(SINGLE_TEXTURE_BIND_GROUP_LAYOUT_NAME).unwrap(),
+                resources.get_bind_group_layout(CAMERA_BIND_GROUP_LAYOUT_NAME).unwrap(),
+            ])
+            .with_frame_bind_group_layout(resources.get_bind_group_layout(FRAME_BIND_GROUP_LAYOUT_NAME).unwrap())
+            .with_shader(Shader::new_from_wgsl(FRAME_SHADER, "frame.wgsl", renderer.get_device()).unwrap())
+            .build(renderer.get_device(), renderer.get_viewport())
+            .unwrap();
+        assert_eq!(material.get_frame_bind_group_index(), Some(2));
+        resources.add_render_material("frame", material, NameCollision::Error).unwrap();
+    }
+    let mut camera = renderer.create_camera();
+    let mut scene = renderer.create_scene();
+    scene.add_render_object("frame", "white", "quad").unwrap();
+    renderer.set_mouse_position(Vec2::new(10.0, 20.0));
+
+    let mut times = Vec::new();
+    for expected in [[0, 255, 255, 255], [0, 255, 255, 255], [255, 255, 255, 255]] {
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        renderer.render(&mut camera, &scene).unwrap();
+        let image = renderer.read_frame().unwrap();
+        assert_eq!(image.get_pixel(WIDTH / 2, HEIGHT / 2).0, expected);
+        times.push(*renderer.get_frame_uniform());
+    }
+
+    assert_eq!(times[0].time, 0.0);
+    assert_eq!(times[0].delta_time, 0.0);
+    assert!(times[1].time > times[0].time && times[2].time > times[1].time);
+    assert!(times[2].delta_time > 0.0);
+    assert!((times[2].time - times[1].time - times[2].delta_time).abs() < 1e-4);
+}
@@ -0,0 +1,26 @@
+mod common;
+
+use glam::Vec3;
+use fragma::renderer::Renderer;
+use common::{create_headless_renderer, WIDTH, HEIGHT};
+
+#[test]
+fn per_frame_data_follows_the_camera_every_frame() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    let mut camera = renderer.create_camera();
+    let mut scene = renderer.create_scene();
+    scene.add_render_object("basic", "white", "quad").unwrap();
+
+    // Alternate between looking at the quad and past it for more frames than are in flight
+    for frame in 0..Renderer::FRAMES_IN_FLIGHT * 3 {
+        let looks_at_quad = frame % 2 == 0;
+        camera.look_at(if looks_at_quad { Vec3::ZERO } else { Vec3::new(5.0, 0.0, 0.0) });
+        renderer.render(&mut camera, &scene).unwrap();
+
+        let pixel = renderer.read_frame().unwrap().get_pixel(WIDTH / 2, HEIGHT / 2).0;
+        assert_eq!(pixel == [255, 255, 255, 255], looks_at_quad, "frame {frame}: {pixel:?}");
+    }
+    assert_eq!(renderer.get_frame_uniform().frame as usize, Renderer::FRAMES_IN_FLIGHT * 3 - 1);
+}
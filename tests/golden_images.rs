@@ -0,0 +1,56 @@
+mod common;
+
+use common::{assert_matches_golden, render_headless, Tolerance, HEIGHT, WIDTH};
+use fragma::renderer::Transform;
+use glam::{Quat, Vec3};
+
+#[test]
+fn basic_textured_triangle() {
+    let Some(image) = render_headless(|scene| {
+        scene.add_render_object("basic", "tree", "triangle")?;
+        Ok(())
+    }) else {
+        return;
+    };
+    assert_matches_golden("basic_textured_triangle", &image, Tolerance::default());
+}
+
+#[test]
+fn basic_compute_output() {
+    let Some(image) = render_headless(|scene| {
+        scene.add_compute_object_with_output_texture("basic compute", WIDTH, HEIGHT)?;
+        Ok(())
+    }) else {
+        return;
+    };
+    assert_matches_golden("basic_compute_output", &image, Tolerance::default());
+}
+
+#[test]
+fn basic_compute_output_with_triangle() {
+    let Some(image) = render_headless(|scene| {
+        scene.add_compute_object_with_output_texture("basic compute", WIDTH, HEIGHT)?;
+        scene.add_render_object("basic", "white", "triangle")?;
+        Ok(())
+    }) else {
+        return;
+    };
+    assert_matches_golden("basic_compute_output_with_triangle", &image, Tolerance::default());
+}
+
+#[test]
+fn transformed_triangles_with_depth() {
+    let Some(image) = render_headless(|scene| {
+        let front = scene.add_render_object("basic", "white", "triangle")?;
+        scene.set_render_object_transform(front, Transform::from_translation(Vec3::new(-0.3, 0.0, 0.0))
+            .with_rotation(Quat::from_rotation_z(0.3)))?;
+        // Drawn last but further away, so the overlapping part must be hidden
+        let back = scene.add_render_object("basic", "black", "triangle")?;
+        scene.set_render_object_transform(back, Transform::from_translation(Vec3::new(0.3, 0.2, -1.0))
+            .with_scale(Vec3::splat(1.5)))?;
+        Ok(())
+    }) else {
+        return;
+    };
+    assert_matches_golden("transformed_triangles_with_depth", &image, Tolerance::default());
+}
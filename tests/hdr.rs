@@ -0,0 +1,84 @@
+mod common;
+
+use fragma::renderer::post_process::{PostEffect, Tonemapper};
+use fragma::renderer::resources::registry::NameCollision;
+use fragma::renderer::resources::texture::Texture;
+use fragma::renderer::Renderer;
+use common::{create_headless_renderer, render_headless, WIDTH, HEIGHT};
+use winit::dpi::PhysicalSize;
+
+/// Renders a quad textured with a single HDR texel of `value`, tonemapped with `tonemapper`
+fn render_hdr_quad(renderer: &mut Renderer, value: f32, tonemapper: Tonemapper, exposure: f32) -> [u8; 4] {
+    let image = image::Rgba32FImage::from_pixel(1, 1, image::Rgba([value, value, value, 1.0]));
+    let texture = Texture::new_from_image(
+        &image.into(),
+        "hdr",
+        renderer.get_device(),
+        renderer.get_queue(),
+        &renderer.get_resources().borrow(),
+    ).unwrap();
+    assert_eq!(texture.get_format(), wgpu::TextureFormat::Rgba16Float);
+    renderer.get_resources().borrow_mut().add_texture("hdr", texture, NameCollision::Replace).unwrap();
+
+    let mut post_process = renderer.get_post_process().borrow_mut();
+    post_process.remove_effect("tonemap");
+    post_process.add_effect(PostEffect::new_tonemap(tonemapper, exposure, renderer).unwrap()).unwrap();
+    drop(post_process);
+
+    let mut camera = renderer.create_camera();
+    let mut scene = renderer.create_scene();
+    scene.add_render_object("basic", "hdr", "quad").unwrap();
+    renderer.render(&mut camera, &scene).unwrap();
+    renderer.read_frame().unwrap().get_pixel(WIDTH / 2, HEIGHT / 2).0
+}
+
+#[test]
+fn tonemappers_map_hdr_colors_into_range() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    // Without tonemapping, colors above 1 are clipped
+    assert_eq!(render_hdr_quad(&mut renderer, 4.0, Tonemapper::None, 1.0), [255, 255, 255, 255]);
+    // 4 / (1 + 4) = 0.8, which is 231 in sRGB
+    let reinhard = render_hdr_quad(&mut renderer, 4.0, Tonemapper::Reinhard, 1.0);
+    assert!(reinhard[0].abs_diff(231) <= 2, "{reinhard:?}");
+    // An exposure of 0.25 brings the color back to 1, which maps to 0.5
+    let exposed = render_hdr_quad(&mut renderer, 4.0, Tonemapper::Reinhard, 0.25);
+    assert!(exposed[0].abs_diff(188) <= 2, "{exposed:?}");
+
+    for tonemapper in [Tonemapper::AcesFitted, Tonemapper::AgX] {
+        let dim = render_hdr_quad(&mut renderer, 0.5, tonemapper, 1.0);
+        let bright = render_hdr_quad(&mut renderer, 4.0, tonemapper, 1.0);
+        let brighter = render_hdr_quad(&mut renderer, 16.0, tonemapper, 1.0);
+        assert!(dim[0] < bright[0] && bright[0] < brighter[0], "{tonemapper:?}: {dim:?} {bright:?} {brighter:?}");
+        assert!(brighter[0] < 255, "{tonemapper:?} clips: {brighter:?}");
+        // Gray stays gray
+        assert!(bright[0].abs_diff(bright[1]) <= 2 && bright[1].abs_diff(bright[2]) <= 2, "{tonemapper:?}: {bright:?}");
+    }
+}
+
+#[test]
+fn linear_and_srgb_outputs_match() {
+    let Some(srgb) = render_headless(|scene| {
+        scene.add_render_object("basic", "tree", "quad")?;
+        Ok(())
+    }) else {
+        return;
+    };
+
+    let mut renderer = pollster::block_on(Renderer::new_headless_with_format(
+        PhysicalSize::new(WIDTH, HEIGHT),
+        wgpu::TextureFormat::Rgba8Unorm,
+        true,
+    )).unwrap();
+    let mut camera = renderer.create_camera();
+    let mut scene = renderer.create_scene();
+    scene.add_render_object("basic", "tree", "quad").unwrap();
+    renderer.render(&mut camera, &scene).unwrap();
+    let linear = renderer.read_frame().unwrap();
+
+    for (srgb, linear) in srgb.pixels().zip(linear.pixels()) {
+        let near = srgb.0.iter().zip(linear.0).all(|(srgb, linear)| srgb.abs_diff(linear) <= 1);
+        assert!(near, "sRGB output {:?} differs from linear output {:?}", srgb.0, linear.0);
+    }
+}
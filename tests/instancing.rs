@@ -0,0 +1,91 @@
+mod common;
+
+use fragma::renderer::{Instance, Transform};
+use fragma::renderer::scene::{RenderObjectId, Scene};
+use common::{assert_matches_golden, create_headless_renderer, render_headless, render_pixels, Tolerance, WIDTH, HEIGHT};
+use glam::{Quat, Vec3, Vec4};
+
+const RED: Vec4 = Vec4::new(1.0, 0.0, 0.0, 1.0);
+const GREEN: Vec4 = Vec4::new(0.0, 1.0, 0.0, 1.0);
+const BLUE: Vec4 = Vec4::new(0.0, 0.0, 1.0, 1.0);
+
+/// A small quad tinted `tint` at `(x, y)`
+fn tinted_quad(x: f32, y: f32, tint: Vec4) -> Instance {
+    Instance::new(Transform::from_translation(Vec3::new(x, y, 0.0)).with_scale(Vec3::splat(0.3)))
+        .with_tint(tint)
+}
+
+fn add_quads(scene: &mut Scene, instances: &[Instance]) -> RenderObjectId {
+    scene.add_instanced_render_object("basic instanced", "white", "quad", instances).unwrap()
+}
+
+#[test]
+fn instances_are_drawn_with_their_transform_and_tint() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    let mut scene = renderer.create_scene();
+    let id = add_quads(&mut scene, &[tinted_quad(-1.0, 0.0, RED), tinted_quad(1.0, 0.0, GREEN)]);
+    assert_eq!(scene.get_render_object(id).unwrap().get_instance_count(), Some(2));
+    // The render object's transform moves all instances
+    scene.set_render_object_transform(id, Transform::from_translation(Vec3::new(0.0, 1.0, 0.0))).unwrap();
+
+    let left = (WIDTH / 4, HEIGHT / 4);
+    let right = (WIDTH * 3 / 4, HEIGHT / 4);
+    let center = (WIDTH / 2, HEIGHT / 4);
+    let [left_color, right_color, center_color] = render_pixels(&mut renderer, &scene, &[left, right, center])[..] else {
+        unreachable!();
+    };
+    assert_eq!(left_color, [255, 0, 0, 255]);
+    assert_eq!(right_color, [0, 255, 0, 255]);
+    assert_ne!(center_color, [255, 255, 255, 255]);
+
+    // Only the updated instance changes
+    scene.update_render_object_instances(id, 1, &[tinted_quad(1.0, 0.0, BLUE)]).unwrap();
+    assert_eq!(render_pixels(&mut renderer, &scene, &[left, right]), [[255, 0, 0, 255], [0, 0, 255, 255]]);
+    assert!(scene.update_render_object_instances(id, 1, &[tinted_quad(0.0, 0.0, RED); 2]).is_err());
+
+    // Growing the instances
+    let instances = [tinted_quad(-1.0, 0.0, BLUE), tinted_quad(1.0, 0.0, BLUE), tinted_quad(0.0, 0.0, RED)];
+    scene.set_render_object_instances(id, &instances).unwrap();
+    assert_eq!(scene.get_render_object(id).unwrap().get_instance_count(), Some(3));
+    assert_eq!(
+        render_pixels(&mut renderer, &scene, &[left, right, center]),
+        [[0, 0, 255, 255], [0, 0, 255, 255], [255, 0, 0, 255]],
+    );
+
+    scene.set_render_object_instances(id, &[]).unwrap();
+    assert_ne!(render_pixels(&mut renderer, &scene, &[left]), [[0, 0, 255, 255]]);
+}
+
+#[test]
+fn materials_and_objects_must_agree_on_instancing() {
+    let Some(renderer) = create_headless_renderer() else {
+        return;
+    };
+    let mut scene = renderer.create_scene();
+    assert!(scene.add_instanced_render_object("basic", "white", "quad", &[]).is_err());
+    let id = scene.add_render_object("basic", "white", "quad").unwrap();
+    assert_eq!(scene.get_render_object(id).unwrap().get_instance_count(), None);
+    assert!(scene.set_render_object_instances(id, &[]).is_err());
+}
+
+#[test]
+fn instanced_foliage() {
+    let Some(image) = render_headless(|scene| {
+        let instances = (0..9)
+            .map(|i| {
+                let (x, z) = ((i % 3) as f32 - 1.0, (i / 3) as f32 - 1.0);
+                let transform = Transform::from_translation(Vec3::new(x * 1.2, -0.5, z * 1.5))
+                    .with_rotation(Quat::from_rotation_y(i as f32 * 0.4))
+                    .with_scale(Vec3::splat(0.6));
+                Instance::new(transform).with_tint(Vec4::new(1.0, 1.0 - i as f32 * 0.08, 1.0, 1.0))
+            })
+            .collect::<Vec<_>>();
+        scene.add_instanced_render_object("basic instanced cutout", "tree", "quad", &instances)?;
+        Ok(())
+    }) else {
+        return;
+    };
+    assert_matches_golden("instanced_foliage", &image, Tolerance::default());
+}
@@ -0,0 +1,130 @@
+mod common;
+
+use std::path::Path;
+use fragma::renderer::resources::obj_loader::ObjAsset;
+use fragma::renderer::resources::registry::NameCollision;
+use common::{assert_matches_golden, create_headless_renderer, render_headless_with_renderer, Tolerance};
+
+fn asset_path(name: &str) -> std::path::PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("assets").join(name)
+}
+
+#[test]
+fn gltf_model_with_embedded_texture() {
+    let Some(image) = render_headless_with_renderer(|renderer, scene| {
+        renderer.load_gltf_from_file("quad", "tests/assets/textured_quad.gltf", NameCollision::Replace)?;
+        scene.add_render_object("basic", "quad/0", "quad")?;
+        Ok(())
+    }) else {
+        return;
+    };
+    assert_matches_golden("gltf_model_with_embedded_texture", &image, Tolerance::default());
+}
+
+#[test]
+fn gltf_from_bytes_registers_model_and_textures() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    let bytes = std::fs::read(asset_path("textured_quad.gltf")).unwrap();
+    // Replaces the default quad model
+    renderer.load_gltf_from_bytes("quad", &bytes, NameCollision::Replace).unwrap();
+
+    let mut scene = renderer.create_scene();
+    assert!(scene.add_render_object("basic", "quad/0", "quad").is_ok());
+    assert!(scene.add_render_object("basic", "quad/1", "quad").is_err());
+    let resources = renderer.get_resources().borrow();
+    assert_eq!(resources.get_model("quad").unwrap().get_mesh_texture_name(0), Some("quad/0"));
+}
+
+#[test]
+fn gltf_meshes_are_drawn_with_their_base_color_texture() {
+    let Some(image) = render_headless_with_renderer(|renderer, scene| {
+        renderer.load_gltf_from_file("quad", "tests/assets/textured_quad.gltf", NameCollision::Replace)?;
+        scene.add_render_object("basic", "white", "quad")?;
+        Ok(())
+    }) else {
+        return;
+    };
+    assert_matches_golden("gltf_model_with_embedded_texture", &image, Tolerance::default());
+}
+
+#[test]
+fn obj_model_with_diffuse_map() {
+    let Some(image) = render_headless_with_renderer(|renderer, scene| {
+        pollster::block_on(renderer.load_obj_from_file("shapes", "tests/assets/textured_shapes.obj", NameCollision::Error))?;
+        scene.add_render_object("basic", "shapes/checker", "shapes")?;
+        Ok(())
+    }) else {
+        return;
+    };
+    assert_matches_golden("obj_model_with_diffuse_map", &image, Tolerance::default());
+}
+
+#[test]
+fn obj_registers_diffuse_maps_per_material() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    pollster::block_on(renderer.load_obj_from_file("two", "tests/assets/two_materials.obj", NameCollision::Error)).unwrap();
+
+    let mut scene = renderer.create_scene();
+    assert!(scene.add_render_object("basic", "two/checker", "two").is_ok());
+    assert!(scene.add_render_object("basic", "two/plain", "two").is_err());
+}
+
+#[test]
+fn obj_diffuse_map_options_and_spaces_are_parsed() {
+    let directory = std::env::temp_dir().join("fragma_map_options");
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::copy(asset_path("checker.png"), directory.join("my checker.png")).unwrap();
+    std::fs::write(
+        directory.join("spaces.mtl"),
+        "newmtl checker\nmap_Kd -blendu on -s 1 1 -mm 0 1 -clamp on my checker.png\n",
+    )
+    .unwrap();
+    let obj_path = directory.join("spaces.obj");
+    std::fs::write(&obj_path, "mtllib spaces.mtl\nv 0 0 0\nv 1 0 0\nv 0 1 0\nusemtl checker\nf 1 2 3\n").unwrap();
+
+    let asset = pollster::block_on(ObjAsset::new_from_file(obj_path.to_str().unwrap())).unwrap();
+    let materials = asset.diffuse_maps.iter().map(|(material, _)| material.as_str()).collect::<Vec<_>>();
+    assert_eq!(materials, ["checker"]);
+}
+
+#[test]
+fn assets_follow_the_name_collision_policy() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    let bytes = std::fs::read(asset_path("textured_quad.gltf")).unwrap();
+    // The default quad model is taken
+    assert!(renderer.load_gltf_from_bytes("quad", &bytes, NameCollision::Error).is_err());
+    renderer.load_gltf_from_bytes("gltf", &bytes, NameCollision::Error).unwrap();
+    assert!(renderer.load_gltf_from_bytes("gltf", &bytes, NameCollision::Error).is_err());
+
+    let handles = renderer.load_gltf_from_bytes("gltf", &bytes, NameCollision::AutoSuffix).unwrap();
+    assert_eq!(handles.model.get_name(), "gltf.1");
+    let texture_names = handles.textures.iter().map(|texture| texture.get_name()).collect::<Vec<_>>();
+    assert_eq!(texture_names, ["gltf.1/0"]);
+
+    // A taken texture name fails the whole asset
+    renderer.get_resources().borrow_mut().remove_model("gltf.1");
+    assert!(renderer.load_gltf_from_bytes("gltf.1", &bytes, NameCollision::Error).is_err());
+    assert!(renderer.get_resources().borrow().get_model("gltf.1").is_err());
+
+    let handles = renderer.load_gltf_from_bytes("gltf.1", &bytes, NameCollision::Replace).unwrap();
+    assert_eq!(handles.model.get_name(), "gltf.1");
+    assert_eq!(handles.textures[0].get_name(), "gltf.1/0");
+}
+
+#[test]
+fn obj_with_out_of_range_index_fails() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    let path = std::env::temp_dir().join("fragma_out_of_range.obj");
+    std::fs::write(&path, "v 0 0 0\nv 1 0 0\nf 1 2 3\n").unwrap();
+
+    let result = pollster::block_on(renderer.load_obj_from_file("broken", path.to_str().unwrap(), NameCollision::Error));
+    assert!(result.is_err());
+}
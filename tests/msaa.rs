@@ -0,0 +1,65 @@
+mod common;
+
+use fragma::renderer::Renderer;
+use common::{assert_matches_golden, create_headless_renderer, Tolerance, WIDTH, HEIGHT};
+use winit::dpi::PhysicalSize;
+
+fn render_triangle(renderer: &mut Renderer) -> image::RgbaImage {
+    let mut camera = renderer.create_camera();
+    let mut scene = renderer.create_scene();
+    scene.add_render_object("basic", "white", "triangle").unwrap();
+    scene.add_compute_object_with_output_texture("basic compute", WIDTH, HEIGHT).unwrap();
+    renderer.render(&mut camera, &scene).unwrap();
+    renderer.read_frame().unwrap()
+}
+
+/// Number of pixels that are neither the compute output below the triangle nor white
+fn count_edge_pixels(image: &image::RgbaImage, background: &image::RgbaImage) -> usize {
+    image.pixels()
+        .zip(background.pixels())
+        .filter(|(pixel, background)| pixel.0 != [255, 255, 255, 255] && pixel.0 != background.0)
+        .count()
+}
+
+#[test]
+fn multisampling_smooths_edges() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    let supported = renderer.get_viewport().get_supported_sample_counts().to_vec();
+    assert!(supported.contains(&1));
+    assert!(renderer.set_sample_count(3).is_err());
+    if !supported.contains(&4) {
+        eprintln!("4x multisampling is not supported, skipping test");
+        return;
+    }
+
+    let mut camera = renderer.create_camera();
+    let mut background_scene = renderer.create_scene();
+    background_scene.add_compute_object_with_output_texture("basic compute", WIDTH, HEIGHT).unwrap();
+    renderer.render(&mut camera, &background_scene).unwrap();
+    let background = renderer.read_frame().unwrap();
+
+    let aliased = render_triangle(&mut renderer);
+    assert_eq!(count_edge_pixels(&aliased, &background), 0);
+
+    renderer.set_sample_count(4).unwrap();
+    assert_eq!(renderer.get_viewport().get_sample_count(), 4);
+    let smoothed = render_triangle(&mut renderer);
+    assert!(count_edge_pixels(&smoothed, &background) > 0);
+    assert_matches_golden("multisampled_triangle", &smoothed, Tolerance {
+        max_channel_difference: 2,
+        max_mismatched_pixels: 4,
+    });
+
+    // Multisampled targets follow the viewport's size
+    renderer.resize(PhysicalSize::new(WIDTH * 2, HEIGHT));
+    let mut scene = renderer.create_scene();
+    scene.add_render_object("basic", "white", "triangle").unwrap();
+    renderer.render(&mut camera, &scene).unwrap();
+    assert_eq!(renderer.read_frame().unwrap().dimensions(), (WIDTH * 2, HEIGHT));
+
+    renderer.set_sample_count(1).unwrap();
+    renderer.resize(PhysicalSize::new(WIDTH, HEIGHT));
+    assert_eq!(count_edge_pixels(&render_triangle(&mut renderer), &background), 0);
+}
@@ -0,0 +1,132 @@
+mod common;
+
+use std::time::Duration;
+use fragma::renderer::particles::{EmitterShape, ParticleEmitter, ADDITIVE_PARTICLES_MATERIAL_NAME};
+use fragma::renderer::resources::shader_data::ShaderParticle;
+use fragma::renderer::scene::Scene;
+use fragma::renderer::Renderer;
+use common::{create_headless_renderer, WIDTH, HEIGHT};
+use glam::{Vec3, Vec4};
+
+/// Renders `frames` frames a few milliseconds apart
+fn render_frames(renderer: &mut Renderer, scene: &Scene, frames: usize) {
+    let mut camera = renderer.create_camera();
+    for _ in 0..frames {
+        std::thread::sleep(Duration::from_millis(10));
+        renderer.render(&mut camera, scene).unwrap();
+    }
+}
+
+fn alive(particles: &[ShaderParticle]) -> impl Iterator<Item = &ShaderParticle> {
+    particles.iter().filter(|particle| particle.age < particle.lifetime)
+}
+
+#[test]
+fn particles_spawn_at_the_emitter_rate() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    let mut scene = renderer.create_scene();
+    let emitter = ParticleEmitter::new(1000)
+        .with_rate(100.0)
+        .with_lifetime(100.0, 100.0)
+        .with_shape(EmitterShape::Box { half_extents: Vec3::new(1.0, 2.0, 3.0) })
+        .with_position(Vec3::new(5.0, 0.0, 0.0))
+        .with_velocity(Vec3::ZERO, 0.0);
+    let id = scene.add_particle_system(emitter).unwrap();
+    render_frames(&mut renderer, &scene, 5);
+
+    let particles = scene.read_particles(id).unwrap();
+    assert_eq!(particles.len(), 1000);
+    let expected = (renderer.get_frame_uniform().time * 100.0).floor() as usize;
+    let alive = alive(&particles).collect::<Vec<_>>();
+    assert!(alive.len().abs_diff(expected) <= 1, "expected {expected} particles, got {}", alive.len());
+    for particle in alive {
+        let offset = particle.position - Vec3::new(5.0, 0.0, 0.0);
+        assert!(offset.abs().cmple(Vec3::new(1.0, 2.0, 3.0)).all(), "{particle:?}");
+    }
+}
+
+#[test]
+fn new_particles_replace_the_oldest() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    let mut scene = renderer.create_scene();
+    let id = scene.add_particle_system(ParticleEmitter::new(8).with_rate(10_000.0).with_lifetime(100.0, 100.0)).unwrap();
+    render_frames(&mut renderer, &scene, 3);
+    assert_eq!(alive(&scene.read_particles(id).unwrap()).count(), 8);
+
+    let emitter = scene.get_particle_system(id).unwrap().get_emitter().clone();
+    assert!(scene.get_particle_system_mut(id).unwrap().set_emitter(emitter.with_rate(1.0)).is_ok());
+    let emitter = ParticleEmitter::new(16);
+    assert!(scene.get_particle_system_mut(id).unwrap().set_emitter(emitter).is_err());
+}
+
+#[test]
+fn particles_collide_with_the_ground() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    let mut scene = renderer.create_scene();
+    // Spawned below the ground and pulled down, so every particle ends up on it
+    let emitter = ParticleEmitter::new(256)
+        .with_rate(500.0)
+        .with_lifetime(10.0, 10.0)
+        .with_shape(EmitterShape::Sphere { radius: 0.5 })
+        .with_position(Vec3::new(0.0, -1.0, 0.0))
+        .with_velocity(Vec3::ZERO, 1.0)
+        .with_force(Vec3::new(0.0, -9.81, 0.0))
+        .with_ground(0.0, 0.0);
+    let id = scene.add_particle_system(emitter).unwrap();
+    render_frames(&mut renderer, &scene, 4);
+
+    let particles = scene.read_particles(id).unwrap();
+    assert!(alive(&particles).count() > 0);
+    for particle in alive(&particles) {
+        assert_eq!(particle.position.y, 0.0, "{particle:?}");
+        assert!(particle.velocity.y >= 0.0, "{particle:?}");
+    }
+}
+
+#[test]
+fn hidden_particle_systems_are_not_simulated() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    let mut scene = renderer.create_scene();
+    let id = scene.add_particle_system(ParticleEmitter::new(64).with_rate(1000.0)).unwrap();
+    scene.set_particle_system_visible(id, false).unwrap();
+    render_frames(&mut renderer, &scene, 3);
+    assert_eq!(alive(&scene.read_particles(id).unwrap()).count(), 0);
+    assert!(scene.remove_particle_system(id).is_some());
+    assert!(scene.read_particles(id).is_err());
+}
+
+#[test]
+fn particles_are_drawn_as_billboards() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    let mut scene = renderer.create_scene();
+    render_frames(&mut renderer, &scene, 1);
+    let background = renderer.read_frame().unwrap().get_pixel(WIDTH / 2, HEIGHT / 2).0;
+
+    // Large red particles that stay at the origin
+    let emitter = ParticleEmitter::new(16)
+        .with_material(ADDITIVE_PARTICLES_MATERIAL_NAME)
+        .with_rate(1000.0)
+        .with_lifetime(100.0, 100.0)
+        .with_velocity(Vec3::ZERO, 0.0)
+        .with_color_over_life(Vec4::new(1.0, 0.0, 0.0, 1.0), Vec4::new(1.0, 0.0, 0.0, 1.0))
+        .with_size_over_life(1.0, 1.0);
+    scene.add_particle_system(emitter).unwrap();
+    render_frames(&mut renderer, &scene, 3);
+
+    let image = renderer.read_frame().unwrap();
+    let center = image.get_pixel(WIDTH / 2, HEIGHT / 2).0;
+    assert_eq!(center[0], 255, "{center:?}");
+    assert_eq!(center[1..3], background[1..3]);
+    // The billboard is round and small, the corners keep the background
+    assert_eq!(image.get_pixel(0, 0).0, background);
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use glam::Vec2;
+use fragma::renderer::playground::{Playground, PlaygroundChannel};
+use fragma::renderer::Renderer;
+use common::{assert_matches_golden, create_headless_renderer, linear_to_srgb, Tolerance, WIDTH, HEIGHT};
+
+fn render_playground(renderer: &mut Renderer, playground: &mut Playground) -> image::RgbaImage {
+    renderer.render_playground(playground).unwrap();
+    renderer.read_frame().unwrap()
+}
+
+#[test]
+fn playground_image_pass() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    let mut playground = Playground::builder()
+        .with_image_pass(
+            "fn main_image(frag_coord: vec2<f32>) -> vec4<f32> {
+                let uv = frag_coord / playground.resolution;
+                let ring = step(0.5, fract(length(frag_coord - playground.mouse.xy) / 8.0 - playground.time));
+                return vec4<f32>(uv, ring, 1.0);
+            }",
+            &[],
+        )
+        .build(&renderer)
+        .unwrap();
+    playground.set_time(0.25);
+    playground.set_mouse(Vec2::new(16.0, 40.0), true, false);
+
+    let image = render_playground(&mut renderer, &mut playground);
+    assert_matches_golden("playground_image_pass", &image, Tolerance::default());
+}
+
+#[test]
+fn playground_buffers_feed_back() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    // Buffer A adds a quarter to its previous frame, B copies A of the current frame
+    let mut playground = Playground::builder()
+        .with_buffer_pass(
+            "A",
+            "fn main_image(frag_coord: vec2<f32>) -> vec4<f32> {
+                let previous = textureLoad(channel0, vec2<i32>(frag_coord), 0);
+                return vec4<f32>(previous.r + 0.25, 0.0, 0.0, 1.0);
+            }",
+            &[PlaygroundChannel::Buffer("A".to_owned())],
+        )
+        .with_buffer_pass(
+            "B",
+            "fn main_image(frag_coord: vec2<f32>) -> vec4<f32> {
+                return textureLoad(channel0, vec2<i32>(frag_coord), 0);
+            }",
+            &[PlaygroundChannel::Buffer("A".to_owned())],
+        )
+        .with_image_pass(
+            "fn main_image(frag_coord: vec2<f32>) -> vec4<f32> {
+                let b = textureLoad(channel1, vec2<i32>(frag_coord), 0);
+                return vec4<f32>(b.r, f32(playground.frame) / 4.0, 0.0, 1.0);
+            }",
+            &[PlaygroundChannel::Texture("black".to_owned()), PlaygroundChannel::Buffer("B".to_owned())],
+        )
+        .build(&renderer)
+        .unwrap();
+
+    // The headless target is sRGB, so linear values are encoded on output
+    let expected = [(0.25, 0.0), (0.5, 0.25), (0.75, 0.5)];
+    for (red, green) in expected {
+        let image = render_playground(&mut renderer, &mut playground);
+        let pixel = image.get_pixel(WIDTH / 2, HEIGHT / 2);
+        assert!(pixel[0].abs_diff(linear_to_srgb(red)) <= 1, "{pixel:?} for red {red}");
+        assert!(pixel[1].abs_diff(linear_to_srgb(green)) <= 1, "{pixel:?} for green {green}");
+    }
+    assert_eq!(playground.get_frame(), 3);
+
+    // Resetting clears the buffers
+    playground.reset();
+    let image = render_playground(&mut renderer, &mut playground);
+    assert!(image.get_pixel(WIDTH / 2, HEIGHT / 2)[0].abs_diff(linear_to_srgb(0.25)) <= 1);
+}
+
+#[test]
+fn playground_rejects_invalid_passes() {
+    let Some(renderer) = create_headless_renderer() else {
+        return;
+    };
+    let valid = "fn main_image(frag_coord: vec2<f32>) -> vec4<f32> { return vec4<f32>(1.0); }";
+
+    let missing_function = Playground::builder()
+        .with_image_pass("fn other() {}", &[])
+        .build(&renderer);
+    assert!(missing_function.is_err());
+
+    let unknown_buffer = Playground::builder()
+        .with_image_pass(valid, &[PlaygroundChannel::Buffer("A".to_owned())])
+        .build(&renderer);
+    assert!(unknown_buffer.is_err());
+
+    let no_image_pass = Playground::builder()
+        .with_buffer_pass("A", valid, &[])
+        .build(&renderer);
+    assert!(no_image_pass.is_err());
+}
@@ -0,0 +1,135 @@
+mod common;
+
+use fragma::renderer::post_process::{PostEffect, Tonemapper};
+use fragma::renderer::resources::registry::NameCollision;
+use fragma::renderer::resources::texture::Texture;
+use fragma::renderer::Renderer;
+use common::{assert_matches_golden, create_headless_renderer, render_headless_with_renderer, Tolerance, WIDTH, HEIGHT};
+
+fn render_white_quad(renderer: &mut Renderer) -> image::RgbaImage {
+    let mut camera = renderer.create_camera();
+    let mut scene = renderer.create_scene();
+    scene.add_render_object("basic", "white", "quad").unwrap();
+    renderer.render(&mut camera, &scene).unwrap();
+    renderer.read_frame().unwrap()
+}
+
+fn assert_channels_near(actual: [u8; 4], expected: [u8; 4]) {
+    let near = actual.iter().zip(expected).all(|(actual, expected)| actual.abs_diff(expected) <= 2);
+    assert!(near, "expected {expected:?}, got {actual:?}");
+}
+
+#[test]
+fn effects_are_applied_in_order() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    let tonemap = PostEffect::new_tonemap(Tonemapper::Reinhard, 1.0, &renderer).unwrap();
+    let vignette = PostEffect::new_vignette(1.0, 0.5, 0.5, &renderer).unwrap();
+    let mut post_process = renderer.get_post_process().borrow_mut();
+    post_process.add_effect(tonemap).unwrap();
+    post_process.add_effect(vignette).unwrap();
+    assert!(post_process.add_effect(PostEffect::new_gamma(2.2, &renderer).unwrap().with_name("tonemap")).is_err());
+    drop(post_process);
+
+    // White is tonemapped to half intensity, the corners are darkened completely
+    let image = render_white_quad(&mut renderer);
+    assert_channels_near(image.get_pixel(WIDTH / 2, HEIGHT / 2).0, [188, 188, 188, 255]);
+    assert_channels_near(image.get_pixel(0, 0).0, [0, 0, 0, 255]);
+
+    let mut post_process = renderer.get_post_process().borrow_mut();
+    post_process.get_effect_mut("tonemap").unwrap().set_parameter(0, 3.0).unwrap();
+    post_process.get_effect_mut("vignette").unwrap().set_enabled(false);
+    drop(post_process);
+    let image = render_white_quad(&mut renderer);
+    assert_channels_near(image.get_pixel(WIDTH / 2, HEIGHT / 2).0, [225, 225, 225, 255]);
+    assert_ne!(image.get_pixel(0, 0).0, [0, 0, 0, 255]);
+
+    renderer.get_post_process().borrow_mut().remove_effect("tonemap").unwrap();
+    renderer.get_post_process().borrow_mut().remove_effect("vignette").unwrap();
+    let image = render_white_quad(&mut renderer);
+    assert_eq!(image.get_pixel(WIDTH / 2, HEIGHT / 2).0, [255, 255, 255, 255]);
+}
+
+#[test]
+fn parameters_are_uploaded_again_after_a_failed_frame() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    let tonemap = PostEffect::new_tonemap(Tonemapper::Reinhard, 1.0, &renderer).unwrap();
+    let broken = PostEffect::new_gamma(1.0, &renderer).unwrap().with_aux_texture("missing");
+    let mut post_process = renderer.get_post_process().borrow_mut();
+    post_process.add_effect(tonemap).unwrap();
+    post_process.add_effect(broken).unwrap();
+    post_process.get_effect_mut("tonemap").unwrap().set_parameter(0, 3.0).unwrap();
+    drop(post_process);
+
+    let mut camera = renderer.create_camera();
+    let mut scene = renderer.create_scene();
+    scene.add_render_object("basic", "white", "quad").unwrap();
+    assert!(renderer.render(&mut camera, &scene).is_err());
+
+    // The exposure staged in the failed frame is uploaded with the next one
+    renderer.get_post_process().borrow_mut().get_effect_mut("gamma").unwrap().set_enabled(false);
+    let image = render_white_quad(&mut renderer);
+    assert_channels_near(image.get_pixel(WIDTH / 2, HEIGHT / 2).0, [225, 225, 225, 255]);
+}
+
+#[test]
+fn color_grading_maps_through_lut() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    // A LUT of size 2 mapping every color to red
+    let lut = image::RgbaImage::from_pixel(4, 2, image::Rgba([255, 0, 0, 255]));
+    let lut = Texture::new_from_image(
+        &lut.into(),
+        "red lut",
+        renderer.get_device(),
+        renderer.get_queue(),
+        &renderer.get_resources().borrow(),
+    ).unwrap();
+    renderer.get_resources().borrow_mut().add_texture("red lut", lut, NameCollision::Error).unwrap();
+
+    let color_grading = PostEffect::new_color_grading("red lut", 1.0, &renderer).unwrap();
+    assert_eq!(color_grading.get_parameter(0), Some(2.0));
+    renderer.get_post_process().borrow_mut().add_effect(color_grading).unwrap();
+
+    let image = render_white_quad(&mut renderer);
+    assert_channels_near(image.get_pixel(WIDTH / 2, HEIGHT / 2).0, [255, 0, 0, 255]);
+    assert_channels_near(image.get_pixel(0, 0).0, [255, 0, 0, 255]);
+
+    // Replacing the LUT is picked up by the next frame
+    let lut = image::RgbaImage::from_pixel(4, 2, image::Rgba([0, 255, 0, 255]));
+    let lut = Texture::new_from_image(
+        &lut.into(),
+        "red lut",
+        renderer.get_device(),
+        renderer.get_queue(),
+        &renderer.get_resources().borrow(),
+    ).unwrap();
+    renderer.get_resources().borrow_mut().add_texture("red lut", lut, NameCollision::Replace).unwrap();
+    let image = render_white_quad(&mut renderer);
+    assert_channels_near(image.get_pixel(WIDTH / 2, HEIGHT / 2).0, [0, 255, 0, 255]);
+}
+
+#[test]
+fn post_process_stack() {
+    let Some(image) = render_headless_with_renderer(|renderer, scene| {
+        scene.add_render_object("basic", "tree", "quad")?;
+        let effects = [
+            PostEffect::new_bloom(0.5, 1.0, 4.0, renderer)?,
+            PostEffect::new_tonemap(Tonemapper::Reinhard, 1.5, renderer)?,
+            PostEffect::new_fxaa(renderer)?,
+            PostEffect::new_vignette(0.8, 0.9, 0.6, renderer)?,
+        ];
+        let mut post_process = renderer.get_post_process().borrow_mut();
+        for effect in effects {
+            post_process.add_effect(effect)?;
+        }
+        Ok(())
+    }) else {
+        return;
+    };
+    assert_matches_golden("post_process_stack", &image, Tolerance::default());
+}
@@ -0,0 +1,92 @@
+mod common;
+
+use fragma::renderer::resources::mesh::Mesh;
+use fragma::renderer::Transform;
+use common::{assert_matches_golden, render_headless, Tolerance};
+use glam::{Quat, Vec3};
+
+fn create_primitives() -> Vec<(&'static str, Mesh)> {
+    vec![
+        ("cube", Mesh::new_cube(2.0)),
+        ("plane", Mesh::new_plane(2.0, 4)),
+        ("uv sphere", Mesh::new_uv_sphere(1.0, 12, 6)),
+        ("icosphere", Mesh::new_icosphere(1.0, 2)),
+        ("cylinder", Mesh::new_cylinder(1.0, 2.0, 12)),
+        ("cone", Mesh::new_cone(1.0, 2.0, 12)),
+        ("torus", Mesh::new_torus(0.75, 0.25, 12, 8)),
+        ("capsule", Mesh::new_capsule(0.5, 2.0, 12, 4)),
+    ]
+}
+
+#[test]
+fn primitives_have_valid_geometry() {
+    for (name, mesh) in create_primitives() {
+        let indices = mesh.indices.as_ref().unwrap();
+        assert!(!indices.is_empty() && indices.len() % 3 == 0, "{name}");
+        assert!(indices.iter().all(|&index| (index as usize) < mesh.vertices.len()), "{name}");
+        for vertex in &mesh.vertices {
+            assert!((vertex.normal.length() - 1.0).abs() < 1e-4, "{name}: {vertex:?}");
+            assert!(vertex.texcoord.cmpge(glam::Vec2::ZERO).all(), "{name}: {vertex:?}");
+            // Only the icosphere's seam triangles wrap around the texture
+            let max_texcoord = if name == "icosphere" { glam::Vec2::new(2.0, 1.0) } else { glam::Vec2::ONE };
+            assert!(vertex.texcoord.cmple(max_texcoord).all(), "{name}: {vertex:?}");
+        }
+        for triangle in indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| &mesh.vertices[triangle[i] as usize]);
+            let face_normal = (b.position - a.position).cross(c.position - a.position);
+            assert!(face_normal.length() > 1e-6, "{name} has a degenerate triangle {triangle:?}");
+            // Counter-clockwise seen from the side the normals point to
+            let vertex_normals = a.normal + b.normal + c.normal;
+            assert!(face_normal.dot(vertex_normals) > 0.0, "{name} has a flipped triangle {triangle:?}");
+        }
+    }
+}
+
+#[test]
+fn primitives_have_the_requested_size() {
+    let extent = |mesh: &Mesh| {
+        mesh.vertices
+            .iter()
+            .fold(Vec3::ZERO, |extent, vertex| extent.max(vertex.position.abs()))
+    };
+    assert_eq!(extent(&Mesh::new_cube(2.0)), Vec3::ONE);
+    assert_eq!(extent(&Mesh::new_plane(2.0, 4)), Vec3::new(1.0, 0.0, 1.0));
+    assert!(extent(&Mesh::new_cylinder(1.0, 3.0, 12)).abs_diff_eq(Vec3::new(1.0, 1.5, 1.0), 1e-5));
+    assert!(extent(&Mesh::new_capsule(0.5, 3.0, 12, 4)).abs_diff_eq(Vec3::new(0.5, 1.5, 0.5), 1e-5));
+    assert!(extent(&Mesh::new_torus(1.0, 0.25, 12, 8)).abs_diff_eq(Vec3::new(1.25, 0.25, 1.25), 1e-5));
+    for mesh in [Mesh::new_uv_sphere(2.0, 12, 6), Mesh::new_icosphere(2.0, 2)] {
+        assert!(mesh.vertices.iter().all(|vertex| (vertex.position.length() - 2.0).abs() < 1e-5));
+    }
+    // Each subdivision splits every triangle into four
+    assert_eq!(Mesh::new_icosphere(1.0, 2).indices.unwrap().len(), 20 * 16 * 3);
+    assert_eq!(Mesh::new_plane(1.0, 4).vertices.len(), 25);
+}
+
+#[test]
+fn sphere_texcoords_do_not_jump_across_the_seam() {
+    for (name, mesh) in [("uv sphere", Mesh::new_uv_sphere(1.0, 12, 6)), ("icosphere", Mesh::new_icosphere(1.0, 3))] {
+        for triangle in mesh.indices.as_ref().unwrap().chunks_exact(3) {
+            let u = [0, 1, 2].map(|i| mesh.vertices[triangle[i] as usize].texcoord.x);
+            let span = u.iter().copied().fold(f32::MIN, f32::max) - u.iter().copied().fold(f32::MAX, f32::min);
+            assert!(span <= 0.5, "{name} triangle {triangle:?} spans {u:?}");
+        }
+    }
+}
+
+#[test]
+fn default_primitive_models() {
+    let Some(image) = render_headless(|scene| {
+        let models = ["cube", "uv sphere", "icosphere", "cylinder", "cone", "torus", "capsule", "plane"];
+        for (i, model) in models.into_iter().enumerate() {
+            let id = scene.add_render_object("basic", "tree", model)?;
+            let (column, row) = ((i % 4) as f32, (i / 4) as f32);
+            let transform = Transform::from_translation(Vec3::new(column * 1.1 - 1.65, 0.6 - row * 1.2, -1.0))
+                .with_rotation(Quat::from_rotation_x(0.5) * Quat::from_rotation_y(0.6));
+            scene.set_render_object_transform(id, transform)?;
+        }
+        Ok(())
+    }) else {
+        return;
+    };
+    assert_matches_golden("default_primitive_models", &image, Tolerance::default());
+}
@@ -0,0 +1,132 @@
+mod common;
+
+use fragma::renderer::render_graph::{
+    GraphPass, RenderGraph, TransientTexture, COMPUTE_COPY_PASS_NAME, COMPUTE_PASS_NAME, DEPTH, OUTPUT,
+    POST_PROCESS_PASS_NAME, SCENE_COLOR, SCENE_PASS_NAME,
+};
+use fragma::renderer::viewport::Viewport;
+use common::{create_headless_renderer, WIDTH, HEIGHT};
+
+fn empty_pass(name: &str) -> GraphPass {
+    GraphPass::new(name, |_, _| Ok(()))
+}
+
+#[test]
+fn scene_passes_run_in_order() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    assert_eq!(
+        renderer.get_render_graph_mut().get_pass_order().unwrap(),
+        [COMPUTE_PASS_NAME, COMPUTE_COPY_PASS_NAME, SCENE_PASS_NAME, POST_PROCESS_PASS_NAME],
+    );
+}
+
+#[test]
+fn passes_are_ordered_by_their_resources() {
+    let mut graph = RenderGraph::new();
+    graph.add_texture("a", TransientTexture::new(wgpu::TextureFormat::Rgba8Unorm)).unwrap();
+    graph.add_texture("b", TransientTexture::new(wgpu::TextureFormat::Rgba8Unorm)).unwrap();
+
+    // Added in reverse, readers still run after the writers
+    graph.add_pass(empty_pass("present").with_reads(&["b"]).with_writes(&[OUTPUT])).unwrap();
+    graph.add_pass(empty_pass("blur").with_reads(&["a"]).with_writes(&["b"])).unwrap();
+    graph.add_pass(empty_pass("draw").with_writes(&["a"])).unwrap();
+    // Writers of the same resource keep their order
+    graph.add_pass(empty_pass("overlay").with_writes(&[OUTPUT])).unwrap();
+    graph.insert_pass_before("present", empty_pass("clear").with_writes(&[OUTPUT])).unwrap();
+    assert_eq!(graph.get_pass_order().unwrap(), ["clear", "draw", "blur", "present", "overlay"]);
+
+    assert!(graph.add_pass(empty_pass("draw")).is_err());
+    assert!(graph.add_texture(OUTPUT, TransientTexture::new(wgpu::TextureFormat::Rgba8Unorm)).is_err());
+
+    graph.remove_pass("draw").unwrap();
+    assert!(graph.get_pass_order().is_err(), "a is read but never written");
+}
+
+#[test]
+fn invalid_graphs_are_rejected() {
+    let mut unknown = RenderGraph::new();
+    unknown.add_pass(empty_pass("draw").with_writes(&["missing"])).unwrap();
+    assert!(unknown.get_pass_order().is_err());
+
+    let mut cycle = RenderGraph::new();
+    cycle.add_texture("a", TransientTexture::new(wgpu::TextureFormat::Rgba8Unorm)).unwrap();
+    cycle.add_texture("b", TransientTexture::new(wgpu::TextureFormat::Rgba8Unorm)).unwrap();
+    cycle.add_pass(empty_pass("first").with_reads(&["b"]).with_writes(&["a"])).unwrap();
+    cycle.add_pass(empty_pass("second").with_reads(&["a"]).with_writes(&["b"])).unwrap();
+    assert!(cycle.get_pass_order().is_err());
+}
+
+#[test]
+fn custom_passes_render_through_transient_textures() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    let graph = renderer.get_render_graph_mut();
+    graph.remove_pass(SCENE_PASS_NAME).unwrap();
+    graph.add_texture("custom color", TransientTexture::new(Viewport::SCENE_FORMAT)).unwrap();
+    // Added after the post-processing pass, which still reads the scene color once it is written
+    graph.add_pass(
+        GraphPass::new("copy to scene", |ctx, encoder| {
+            encoder.copy_texture_to_texture(
+                ctx.get_texture("custom color")?.as_image_copy(),
+                ctx.get_texture(SCENE_COLOR)?.as_image_copy(),
+                ctx.get_texture(SCENE_COLOR)?.size(),
+            );
+            Ok(())
+        })
+            .with_reads(&["custom color"])
+            .with_writes(&[SCENE_COLOR]),
+    ).unwrap();
+    graph.add_pass(
+        GraphPass::new("scene to texture", |ctx, encoder| {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Scene To Texture"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: ctx.get_texture_view("custom color")?,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::RED),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: ctx.get_texture_view(DEPTH)?,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: None,
+            });
+            ctx.draw_render_objects(&mut render_pass)
+        })
+            .with_writes(&["custom color", DEPTH]),
+    ).unwrap();
+
+    let mut camera = renderer.create_camera();
+    let mut scene = renderer.create_scene();
+    scene.add_render_object("basic", "white", "quad").unwrap();
+    for _ in 0..2 {
+        renderer.render(&mut camera, &scene).unwrap();
+        let image = renderer.read_frame().unwrap();
+        assert_eq!(image.get_pixel(WIDTH / 2, HEIGHT / 2).0, [255, 255, 255, 255]);
+        assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0, 255]);
+    }
+
+    // Resources a pass did not declare cannot be accessed
+    renderer.get_render_graph_mut().add_pass(
+        GraphPass::new("undeclared", |ctx, _| ctx.get_texture(OUTPUT).map(|_| ()))
+    ).unwrap();
+    assert!(renderer.render(&mut camera, &scene).is_err());
+
+    // A failed frame leaves nothing behind for the next one
+    renderer.get_render_graph_mut().remove_pass("undeclared").unwrap();
+    renderer.render(&mut camera, &scene).unwrap();
+    let image = renderer.read_frame().unwrap();
+    assert_eq!(image.get_pixel(WIDTH / 2, HEIGHT / 2).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0, 255]);
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use fragma::renderer::resources::mesh::Mesh;
+use fragma::renderer::resources::model::Model;
+use fragma::renderer::resources::registry::NameCollision;
+use common::{assert_matches_golden, create_headless_renderer, create_solid_texture, render_headless_with_renderer, Tolerance};
+
+#[test]
+fn registered_resources_are_rendered() {
+    let Some(image) = render_headless_with_renderer(|renderer, scene| {
+        let texture = create_solid_texture(renderer, [255, 128, 0, 255])?;
+        let model = Model::new(vec![Mesh::new_quad()], renderer.get_device())?;
+
+        let mut resources = renderer.get_resources().borrow_mut();
+        let texture = resources.add_texture("orange", texture, NameCollision::Error)?;
+        let model = resources.add_model("my quad", model, NameCollision::Error)?;
+        let material = resources.get_render_material_handle("basic")?;
+        drop(resources);
+
+        scene.add_render_object(material.get_name(), texture.get_name(), model.get_name())?;
+        Ok(())
+    }) else {
+        return;
+    };
+    assert_matches_golden("registered_resources_are_rendered", &image, Tolerance::default());
+}
+
+#[test]
+fn name_collisions_follow_policy() {
+    let Some(renderer) = create_headless_renderer() else {
+        return;
+    };
+    let first = create_solid_texture(&renderer, [255, 0, 0, 255]).unwrap();
+    let second = create_solid_texture(&renderer, [0, 255, 0, 255]).unwrap();
+    let third = create_solid_texture(&renderer, [0, 0, 255, 255]).unwrap();
+    let fourth = create_solid_texture(&renderer, [0, 0, 0, 255]).unwrap();
+    let mut resources = renderer.get_resources().borrow_mut();
+
+    assert!(resources.add_texture("white", first, NameCollision::Error).is_err());
+
+    let handle = resources.add_texture("white", second, NameCollision::AutoSuffix).unwrap();
+    assert_eq!(handle.get_name(), "white.1");
+    let handle = resources.add_texture("white", third, NameCollision::AutoSuffix).unwrap();
+    assert_eq!(handle.get_name(), "white.2");
+
+    let handle = resources.add_texture("white", fourth, NameCollision::Replace).unwrap();
+    assert_eq!(handle.get_name(), "white");
+    assert_eq!(resources.get_texture_handle("white").unwrap(), handle);
+}
+
+#[test]
+fn removed_resources_cannot_be_used() {
+    let Some(renderer) = create_headless_renderer() else {
+        return;
+    };
+    let mut scene = renderer.create_scene();
+
+    assert!(renderer.get_resources().borrow_mut().remove_texture("tree").is_some());
+    assert!(renderer.get_resources().borrow_mut().remove_texture("tree").is_none());
+    assert!(renderer.get_resources().borrow().get_texture_handle("tree").is_err());
+    assert!(scene.add_render_object("basic", "tree", "quad").is_err());
+
+    assert!(renderer.get_resources().borrow_mut().remove_model("quad").is_some());
+    assert!(scene.add_render_object("basic", "white", "quad").is_err());
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use common::create_headless_renderer;
+
+#[test]
+fn removed_render_object_handles_are_stale() {
+    let Some(renderer) = create_headless_renderer() else {
+        return;
+    };
+    let mut scene = renderer.create_scene();
+
+    let first = scene.add_render_object("basic", "white", "triangle").unwrap();
+    let second = scene.add_render_object("basic", "black", "quad").unwrap();
+    assert!(scene.remove_render_object(first).is_some());
+    assert!(scene.get_render_object(first).is_none());
+    assert!(scene.remove_render_object(first).is_none());
+
+    // The freed slot is reused, but the old handle must not resolve to the new object
+    let third = scene.add_render_object("basic", "tree", "triangle").unwrap();
+    assert_ne!(first, third);
+    assert!(scene.get_render_object(first).is_none());
+    assert!(scene.get_render_object(third).is_some());
+
+    let ids = scene.get_render_objects().map(|(id, _)| id).collect::<Vec<_>>();
+    assert_eq!(ids, vec![second, third]);
+}
+
+#[test]
+fn hidden_render_objects_are_skipped() {
+    let Some(renderer) = create_headless_renderer() else {
+        return;
+    };
+    let mut scene = renderer.create_scene();
+
+    let first = scene.add_render_object("basic", "white", "triangle").unwrap();
+    let second = scene.add_render_object("basic", "white", "quad").unwrap();
+    scene.set_render_object_visible(first, false).unwrap();
+
+    assert_eq!(scene.is_render_object_visible(first), Some(false));
+    let visible = scene.get_visible_render_objects().map(|(id, _)| id).collect::<Vec<_>>();
+    assert_eq!(visible, vec![second]);
+    assert_eq!(scene.get_render_objects().count(), 2);
+
+    scene.remove_render_object(second);
+    assert!(scene.set_render_object_visible(second, true).is_err());
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use std::path::Path;
+use std::time::Duration;
+use fragma::renderer::Renderer;
+use common::{create_headless_renderer, WIDTH, HEIGHT};
+
+const TEXTURE_SAMPLE: &str = "var out = textureSample(t_diffuse, s_diffuse, in.uv);";
+const MAGENTA: &str = "var out = vec4<f32>(1.0, 0.0, 1.0, 1.0);";
+
+fn render_center_pixel(renderer: &mut Renderer) -> [u8; 4] {
+    let mut camera = renderer.create_camera();
+    let mut scene = renderer.create_scene();
+    scene.add_render_object("basic", "white", "quad").unwrap();
+    renderer.render(&mut camera, &scene).unwrap();
+    renderer.read_frame().unwrap().get_pixel(WIDTH / 2, HEIGHT / 2).0
+}
+
+/// Writes the shader and waits until the watcher is due to poll again.
+fn write_shader(path: &Path, source: &str) {
+    std::fs::write(path, source).unwrap();
+    std::thread::sleep(Duration::from_millis(250));
+}
+
+#[test]
+fn edited_shaders_are_reloaded() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    let directory = Path::new(env!("CARGO_TARGET_TMPDIR")).join("hot_reload_shaders");
+    std::fs::create_dir_all(&directory).unwrap();
+    let shader_path = directory.join("basic.wgsl");
+    let source = std::fs::read_to_string(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("shaders").join("basic.wgsl")
+    ).unwrap();
+    assert!(source.contains(TEXTURE_SAMPLE));
+    std::fs::write(&shader_path, &source).unwrap();
+
+    renderer.enable_shader_hot_reload(directory.to_str().unwrap()).unwrap();
+    assert_eq!(render_center_pixel(&mut renderer), [255, 255, 255, 255]);
+
+    let magenta_source = source.replace(TEXTURE_SAMPLE, MAGENTA);
+    write_shader(&shader_path, &magenta_source);
+    assert_eq!(render_center_pixel(&mut renderer), [255, 0, 255, 255]);
+
+    // Shaders that fail to parse keep the last good pipeline
+    write_shader(&shader_path, &magenta_source.replace("fn fs_main", "fn fs_main("));
+    assert_eq!(render_center_pixel(&mut renderer), [255, 0, 255, 255]);
+
+    // So do shaders the device rejects, here for lacking the fragment entry point
+    write_shader(&shader_path, &source.replace("fn fs_main", "fn fs_other"));
+    assert_eq!(render_center_pixel(&mut renderer), [255, 0, 255, 255]);
+
+    write_shader(&shader_path, &source);
+    assert_eq!(render_center_pixel(&mut renderer), [255, 255, 255, 255]);
+}
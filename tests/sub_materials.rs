@@ -0,0 +1,120 @@
+mod common;
+
+use fragma::renderer::resources::mesh::Mesh;
+use fragma::renderer::resources::model::{MeshRange, Model};
+use fragma::renderer::resources::registry::NameCollision;
+use fragma::renderer::Renderer;
+use common::{create_headless_renderer, create_solid_texture, render_pixels, WIDTH, HEIGHT};
+use glam::Vec3;
+
+const LEFT: (u32, u32) = (WIDTH / 4, HEIGHT / 2);
+const RIGHT: (u32, u32) = (WIDTH * 3 / 4, HEIGHT / 2);
+
+/// A quad of half the default size centered at `x`
+fn create_quad_mesh(x: f32) -> Mesh {
+    let mut mesh = Mesh::new_quad();
+    for vertex in &mut mesh.vertices {
+        vertex.position = vertex.position * 0.5 + Vec3::new(x, 0.0, 0.0);
+    }
+    mesh
+}
+
+fn register_resources(renderer: &Renderer) {
+    let texture = create_solid_texture(renderer, [255, 0, 0, 255]).unwrap();
+    // Both meshes index their own vertices from 0
+    let model = Model::new(vec![create_quad_mesh(-1.0), create_quad_mesh(1.0)], renderer.get_device()).unwrap();
+
+    let mut resources = renderer.get_resources().borrow_mut();
+    resources.add_texture("red", texture, NameCollision::Error).unwrap();
+    resources.add_model("two quads", model, NameCollision::Error).unwrap();
+}
+
+#[test]
+fn meshes_are_drawn_with_their_own_ranges() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    register_resources(&renderer);
+    let ranges = renderer.get_resources().borrow().get_model("two quads").unwrap().get_mesh_ranges().to_vec();
+    assert_eq!(ranges, [
+        MeshRange { first_element: 0, element_count: 6, base_vertex: 0 },
+        MeshRange { first_element: 6, element_count: 6, base_vertex: 4 },
+    ]);
+
+    let mut scene = renderer.create_scene();
+    scene.add_render_object("basic", "white", "two quads").unwrap();
+    assert_eq!(render_pixels(&mut renderer, &scene, &[LEFT, RIGHT]), [[255, 255, 255, 255]; 2]);
+    assert_eq!(renderer.get_draw_statistics().draw_calls, 2);
+}
+
+#[test]
+fn meshes_can_have_their_own_material() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    register_resources(&renderer);
+    let mut scene = renderer.create_scene();
+    let id = scene.add_render_object("basic", "white", "two quads").unwrap();
+
+    scene.set_render_object_mesh_material(id, 1, "basic cutout", "red").unwrap();
+    let render_object = scene.get_render_object(id).unwrap();
+    assert_eq!(render_object.get_mesh_material_name(0), "basic");
+    assert_eq!(render_object.get_mesh_material_name(1), "basic cutout");
+    assert_eq!(render_object.get_mesh_texture_name(1), "red");
+    assert_eq!(render_pixels(&mut renderer, &scene, &[LEFT, RIGHT]), [[255, 255, 255, 255], [255, 0, 0, 255]]);
+    assert_eq!(renderer.get_draw_statistics().pipeline_changes, 2);
+
+    scene.clear_render_object_mesh_material(id, 1).unwrap();
+    assert_eq!(render_pixels(&mut renderer, &scene, &[LEFT, RIGHT]), [[255, 255, 255, 255]; 2]);
+
+    assert!(scene.set_render_object_mesh_material(id, 0, "missing", "red").is_err());
+    assert!(scene.set_render_object_mesh_material(id, 0, "basic", "missing").is_err());
+    // Mesh indices are checked against the model when drawing
+    scene.set_render_object_mesh_material(id, 2, "basic", "red").unwrap();
+    let mut camera = renderer.create_camera();
+    assert!(renderer.render(&mut camera, &scene).is_err());
+}
+
+#[test]
+fn models_can_assign_meshes_a_texture() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    register_resources(&renderer);
+    let mut model = Model::new(vec![create_quad_mesh(-1.0), create_quad_mesh(1.0)], renderer.get_device()).unwrap();
+    assert!(model.set_mesh_texture_names(vec![None]).is_err());
+    model.set_mesh_texture_names(vec![None, Some("red".to_owned())]).unwrap();
+    assert_eq!(model.get_mesh_texture_name(1), Some("red"));
+    renderer.get_resources().borrow_mut().add_model("textured quads", model, NameCollision::Error).unwrap();
+
+    let mut scene = renderer.create_scene();
+    let id = scene.add_render_object("basic", "white", "textured quads").unwrap();
+    assert_eq!(render_pixels(&mut renderer, &scene, &[LEFT, RIGHT]), [[255, 255, 255, 255], [255, 0, 0, 255]]);
+
+    // Sub-materials take precedence over the model's textures
+    scene.set_render_object_mesh_material(id, 1, "basic", "white").unwrap();
+    assert_eq!(render_pixels(&mut renderer, &scene, &[LEFT, RIGHT]), [[255, 255, 255, 255]; 2]);
+}
+
+#[test]
+fn obj_meshes_are_drawn_per_material() {
+    let Some(mut renderer) = create_headless_renderer() else {
+        return;
+    };
+    pollster::block_on(renderer.load_obj_from_file("two", "tests/assets/two_materials.obj", NameCollision::Error)).unwrap();
+    let resources = renderer.get_resources().borrow();
+    let ranges = resources.get_model("two").unwrap().get_mesh_ranges();
+    let base_vertices = ranges.iter().map(|range| range.base_vertex).collect::<Vec<_>>();
+    assert_eq!(base_vertices, [0, 3, 6]);
+    // Only the checker material has a diffuse map
+    let model = resources.get_model("two").unwrap();
+    let textures = (0..3).map(|mesh_index| model.get_mesh_texture_name(mesh_index)).collect::<Vec<_>>();
+    assert_eq!(textures, [None, Some("two/checker"), None]);
+    drop(resources);
+
+    let mut scene = renderer.create_scene();
+    scene.add_render_object("basic", "white", "two").unwrap();
+    let mut camera = renderer.create_camera();
+    renderer.render(&mut camera, &scene).unwrap();
+    assert_eq!(renderer.get_draw_statistics().draw_calls, 3);
+}